@@ -0,0 +1,1275 @@
+//! Pluggable diagnostic checks
+//!
+//! Every check implements `DiagnosticCheck` and declares which checks it
+//! depends on. The registry starts each enabled check as soon as its
+//! dependencies have finished, so independent checks run in parallel.
+//! Checks report their own progress; `run_diagnostic` assembles the report
+//! from the collected outputs.
+
+use crate::config::DiagnosticConfig;
+use crate::diagnostic::*;
+use crate::types::*;
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use tokio::time::{timeout, Duration};
+
+/// Value produced by a successful check
+#[derive(Debug, Clone)]
+pub enum CheckValue {
+    Dns(DnsResult),
+    Tcp(TcpResult),
+    PerIpTcp(BTreeMap<String, TcpResult>),
+    SslCert(SslCertInfo),
+    PortCheck(PortCheckResult),
+    Routing(RoutingResult),
+    Stability(StabilityResult),
+    LatencyBySize(LatencyBySizeResult),
+    Blocklist(BlocklistResult),
+    SecureDns(SecureDnsResult),
+    Proxy(ProxyDetectionResult),
+    Bandwidth(BandwidthResult),
+}
+
+/// How a check ended
+#[derive(Debug, Clone)]
+pub enum CheckOutcome {
+    /// Boxed: result types vary a lot in size
+    Done(Box<CheckValue>),
+    /// Not applicable to this target or turned off by config
+    Skipped,
+    /// Error or timeout (details already reported through progress events)
+    Failed,
+}
+
+impl From<Result<Result<CheckValue, String>, tokio::time::error::Elapsed>> for CheckOutcome {
+    fn from(result: Result<Result<CheckValue, String>, tokio::time::error::Elapsed>) -> Self {
+        match result {
+            Ok(Ok(value)) => CheckOutcome::Done(Box::new(value)),
+            Ok(Err(_)) | Err(_) => CheckOutcome::Failed,
+        }
+    }
+}
+
+/// Outcomes of the finished checks, by check name
+#[derive(Debug, Default)]
+pub struct CheckOutputs(HashMap<&'static str, CheckOutcome>);
+
+impl CheckOutputs {
+    fn value(&self, name: &str) -> Option<&CheckValue> {
+        match self.0.get(name) {
+            Some(CheckOutcome::Done(value)) => Some(value.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn dns(&self) -> Option<&DnsResult> {
+        match self.value("dns") {
+            Some(CheckValue::Dns(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn tcp(&self) -> Option<&TcpResult> {
+        match self.value("tcp") {
+            Some(CheckValue::Tcp(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn per_ip_tcp(&self) -> Option<&BTreeMap<String, TcpResult>> {
+        match self.value("per_ip") {
+            Some(CheckValue::PerIpTcp(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn ssl_cert(&self) -> Option<&SslCertInfo> {
+        match self.value("ssl_cert") {
+            Some(CheckValue::SslCert(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn port_check(&self) -> Option<&PortCheckResult> {
+        match self.value("port") {
+            Some(CheckValue::PortCheck(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn routing(&self) -> Option<&RoutingResult> {
+        match self.value("routing") {
+            Some(CheckValue::Routing(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn stability(&self) -> Option<&StabilityResult> {
+        match self.value("stability") {
+            Some(CheckValue::Stability(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn latency_by_size(&self) -> Option<&LatencyBySizeResult> {
+        match self.value("latency_by_size") {
+            Some(CheckValue::LatencyBySize(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn blocklist(&self) -> Option<&BlocklistResult> {
+        match self.value("blocklist") {
+            Some(CheckValue::Blocklist(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn secure_dns(&self) -> Option<&SecureDnsResult> {
+        match self.value("secure_dns") {
+            Some(CheckValue::SecureDns(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn proxy(&self) -> Option<&ProxyDetectionResult> {
+        match self.value("proxy") {
+            Some(CheckValue::Proxy(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn bandwidth(&self) -> Option<&BandwidthResult> {
+        match self.value("bandwidth") {
+            Some(CheckValue::Bandwidth(r)) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+/// Receives the progress events checks emit (forwarded to the frontend)
+pub type ProgressSink = Box<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Everything a check needs: target, config, and the outputs of earlier checks
+pub struct CheckContext {
+    progress: ProgressSink,
+    pub config: DiagnosticConfig,
+    pub binding: SourceBinding,
+    /// Connect address / Host+SNI override for the HTTP probes
+    pub target_override: Option<TargetOverride>,
+    pub domain: String,
+    pub url: String,
+    /// Raw TCP mode target (host, port); `None` for web targets
+    pub raw_target: Option<(String, u16)>,
+    outputs: Mutex<CheckOutputs>,
+}
+
+impl CheckContext {
+    pub fn new(
+        progress: ProgressSink,
+        config: DiagnosticConfig,
+        binding: SourceBinding,
+        target_override: Option<TargetOverride>,
+        url: String,
+        domain: String,
+        raw_target: Option<(String, u16)>,
+    ) -> Self {
+        Self {
+            progress,
+            config,
+            binding,
+            target_override,
+            domain,
+            url,
+            raw_target,
+            outputs: Mutex::new(CheckOutputs::default()),
+        }
+    }
+
+    /// Outputs of the checks finished so far
+    pub fn outputs(&self) -> MutexGuard<'_, CheckOutputs> {
+        self.outputs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Move the collected outputs out once the registry has finished
+    pub fn take_outputs(&self) -> CheckOutputs {
+        std::mem::take(&mut *self.outputs())
+    }
+
+    fn record(&self, name: &'static str, outcome: CheckOutcome) {
+        self.outputs().0.insert(name, outcome);
+    }
+
+    /// Name the DNS check resolves: the override's connect host when it is a
+    /// name, so the network checks follow the server actually connected to
+    pub fn dns_host(&self) -> &str {
+        match &self.target_override {
+            Some(o) if o.connect_ip().is_none() => o.connect_to(),
+            _ => &self.domain,
+        }
+    }
+
+    /// Addresses the probes go to: the override's connect IP, else the IPv4
+    /// addresses from the DNS check
+    pub fn resolved_ips(&self) -> Vec<String> {
+        if let Some(ip) = self.target_override.as_ref().and_then(TargetOverride::connect_ip) {
+            return vec![ip.to_string()];
+        }
+        self.outputs()
+            .dns()
+            .map(|d| d.resolved_ips.clone())
+            .unwrap_or_default()
+    }
+
+    /// First of `resolved_ips` (empty when DNS failed)
+    pub fn target_ip(&self) -> String {
+        self.resolved_ips().into_iter().next().unwrap_or_default()
+    }
+
+    fn emit(&self, step: &str, status: DiagnosticStatus, message: &str) {
+        (self.progress)(ProgressEvent {
+            step: step.to_string(),
+            status,
+            message: message.to_string(),
+            data: None,
+        });
+    }
+
+    fn emit_data(&self, step: &str, status: DiagnosticStatus, message: &str, data: serde_json::Value) {
+        (self.progress)(ProgressEvent {
+            step: step.to_string(),
+            status,
+            message: message.to_string(),
+            data: Some(data),
+        });
+    }
+}
+
+/// A single diagnostic check
+///
+/// `run` returns a boxed future (rather than being an `async fn`) so checks
+/// can be stored as trait objects in the registry.
+pub trait DiagnosticCheck: Send + Sync {
+    /// Unique name, used for dependencies and to disable the check in config
+    fn name(&self) -> &'static str;
+
+    /// Checks that must finish before this one starts
+    fn dependencies(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// External programs the check runs with `config`, `None` when it would
+    /// skip itself (best guess before DNS, used by `validate_diagnostic`)
+    fn requirements(&self, _config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        Some(&[])
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome>;
+
+    /// Whether `requirements` lets the check run in `ctx`; `run` skips itself
+    /// when this is false so the two never disagree
+    fn enabled(&self, ctx: &CheckContext) -> bool {
+        self.requirements(&ctx.config, ctx.raw_target.is_some()).is_some()
+    }
+}
+
+/// Ordered set of checks, run with dependencies respected
+#[derive(Default)]
+pub struct CheckRegistry {
+    checks: Vec<Box<dyn DiagnosticCheck>>,
+}
+
+impl CheckRegistry {
+    /// Registry with every built-in check
+    pub fn with_default_checks() -> Self {
+        let mut registry = Self::default();
+        registry.register(DnsCheck);
+        registry.register(TcpCheck);
+        registry.register(PerIpTcpCheck);
+        registry.register(SslCertCheck);
+        registry.register(PortCheck);
+        registry.register(RoutingCheck);
+        registry.register(StabilityCheck);
+        registry.register(LatencyBySizeCheck);
+        registry.register(BlocklistCheck);
+        registry.register(SecureDnsCheck);
+        registry.register(ProxyCheck);
+        registry.register(BandwidthCheck);
+        registry
+    }
+
+    pub fn register(&mut self, check: impl DiagnosticCheck + 'static) {
+        self.checks.push(Box::new(check));
+    }
+
+    /// Names of all registered checks
+    pub fn names(&self) -> Vec<&'static str> {
+        self.checks.iter().map(|c| c.name()).collect()
+    }
+
+    /// Checks a run with `config` would start, with the programs each needs
+    pub fn plan(&self, config: &DiagnosticConfig, raw_tcp: bool) -> Vec<(&'static str, &'static [&'static str])> {
+        self.checks
+            .iter()
+            .filter(|c| !config.disabled_checks.iter().any(|d| d == c.name()))
+            .filter_map(|c| c.requirements(config, raw_tcp).map(|tools| (c.name(), tools)))
+            .collect()
+    }
+
+    /// Dependencies naming no registered check, as (check, dependency)
+    pub fn unknown_dependencies(&self) -> Vec<(&'static str, &'static str)> {
+        let names = self.names();
+        self.checks
+            .iter()
+            .flat_map(|c| c.dependencies().iter().map(move |dep| (c.name(), *dep)))
+            .filter(|(_, dep)| !names.contains(dep))
+            .collect()
+    }
+
+    /// Run all checks not disabled in the config, recording outcomes in `ctx`
+    ///
+    /// Dependencies on disabled checks count as satisfied. A dependency on an
+    /// unregistered check is a typo and trips a debug assertion.
+    pub async fn run(&self, ctx: &CheckContext) {
+        debug_assert!(
+            self.unknown_dependencies().is_empty(),
+            "unknown check dependencies: {:?}",
+            self.unknown_dependencies()
+        );
+        let disabled: HashSet<&str> = ctx.config.disabled_checks.iter().map(|s| s.as_str()).collect();
+        let mut waiting: Vec<&dyn DiagnosticCheck> = Vec::new();
+        for check in &self.checks {
+            if disabled.contains(check.name()) {
+                ctx.emit(check.name(), DiagnosticStatus::Success, "Bỏ qua theo cấu hình");
+                ctx.record(check.name(), CheckOutcome::Skipped);
+            } else {
+                waiting.push(check.as_ref());
+            }
+        }
+
+        let enabled: HashSet<&str> = waiting.iter().map(|c| c.name()).collect();
+        let mut finished: HashSet<&str> = HashSet::new();
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            // Start every check whose dependencies are done
+            let (ready, blocked): (Vec<_>, Vec<_>) = waiting.into_iter().partition(|c| {
+                c.dependencies()
+                    .iter()
+                    .all(|dep| finished.contains(dep) || !enabled.contains(dep))
+            });
+            waiting = blocked;
+            for check in ready {
+                running.push(check.run(ctx).map(move |outcome| (check.name(), outcome)));
+            }
+
+            match running.next().await {
+                Some((name, outcome)) => {
+                    ctx.record(name, outcome);
+                    finished.insert(name);
+                }
+                None => break,
+            }
+        }
+
+        // Only reachable with a dependency cycle
+        for check in waiting {
+            ctx.record(check.name(), CheckOutcome::Failed);
+        }
+    }
+}
+
+/// DNS resolution, required by most other checks
+struct DnsCheck;
+
+impl DiagnosticCheck for DnsCheck {
+    fn name(&self) -> &'static str {
+        "dns"
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            // Local resolver (5s) plus two public resolver retries (3s each)
+            match timeout(Duration::from_secs(15), check_dns(ctx.dns_host())).await {
+                Ok(Ok(result)) => {
+                    let status = if result.resolved_ips.is_empty() {
+                        DiagnosticStatus::Error
+                    } else if result.lookup_time_ms > ctx.config.thresholds.dns_ms || result.resolver.is_some() {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    };
+
+                    let via = result
+                        .resolver
+                        .as_ref()
+                        .map(|r| format!(" (qua DNS công cộng {}, resolver cục bộ lỗi)", r))
+                        .unwrap_or_default();
+
+                    ctx.emit(
+                        "dns",
+                        status,
+                        &format!(
+                            "Tìm thấy {} IP, lookup {:.0}ms{}",
+                            result.resolved_ips.len(),
+                            result.lookup_time_ms,
+                            via
+                        ),
+                    );
+                    CheckOutcome::Done(Box::new(CheckValue::Dns(result)))
+                }
+                Ok(Err(e)) => {
+                    ctx.emit("dns", DiagnosticStatus::Error, &format!("Lỗi: {}", e));
+                    CheckOutcome::Failed
+                }
+                Err(_) => {
+                    ctx.emit("dns", DiagnosticStatus::Error, "Timeout sau 15 giây");
+                    CheckOutcome::Failed
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// HTTP(S) timing breakdown; also reports the SSL and HTTP steps
+struct TcpCheck;
+
+impl DiagnosticCheck for TcpCheck {
+    fn name(&self) -> &'static str {
+        "tcp"
+    }
+
+    fn requirements(&self, _config: &DiagnosticConfig, raw_tcp: bool) -> Option<&'static [&'static str]> {
+        (!raw_tcp).then_some(&["curl"])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            if !self.enabled(ctx) {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("tcp", DiagnosticStatus::Running, "Đang kiểm tra kết nối TCP...");
+
+            let thresholds = &ctx.config.thresholds;
+            let tcp_check = check_tcp_timing(&ctx.url, &ctx.binding, ctx.target_override.as_ref());
+            match timeout(Duration::from_secs(30), tcp_check).await {
+                Ok(Ok(result)) if result.http_code == 0 && result.failure_reason.is_some() => {
+                    let reason = result.failure_reason.clone().unwrap_or_default();
+                    ctx.emit("tcp", DiagnosticStatus::Error, &format!("Lỗi: {}", reason));
+                    ctx.emit("ssl", DiagnosticStatus::Error, "Không thể kiểm tra SSL");
+                    ctx.emit("http", DiagnosticStatus::Error, "Không thể kiểm tra HTTP");
+                    CheckOutcome::Done(Box::new(CheckValue::Tcp(result)))
+                }
+                Ok(Ok(result)) => {
+                    // Update SSL and HTTP status based on TCP result
+                    let ssl_only = result.ssl_time_ms - result.connect_time_ms;
+                    let ssl_status = if result.ssl_time_ms == 0.0 {
+                        DiagnosticStatus::Error
+                    } else if ssl_only > thresholds.ssl_ms {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    };
+                    ctx.emit("ssl", ssl_status, &format!("SSL handshake: {:.0}ms", ssl_only));
+
+                    let http_status = if result.http_code >= 200 && result.http_code < 400 {
+                        DiagnosticStatus::Success
+                    } else if result.http_code >= 400 {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Error
+                    };
+                    ctx.emit(
+                        "http",
+                        http_status,
+                        &format!("HTTP {}, tổng thời gian: {:.0}ms", result.http_code, result.total_time_ms),
+                    );
+
+                    let tcp_status = if result.total_time_ms > thresholds.total_ms {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    };
+                    ctx.emit(
+                        "tcp",
+                        tcp_status,
+                        &format!("Connect: {:.0}ms, TTFB: {:.0}ms", result.connect_time_ms, result.ttfb_ms),
+                    );
+                    CheckOutcome::Done(Box::new(CheckValue::Tcp(result)))
+                }
+                Ok(Err(e)) => {
+                    ctx.emit("tcp", DiagnosticStatus::Error, &format!("Lỗi: {}", e));
+                    ctx.emit("ssl", DiagnosticStatus::Error, "Không thể kiểm tra SSL");
+                    ctx.emit("http", DiagnosticStatus::Error, "Không thể kiểm tra HTTP");
+                    CheckOutcome::Failed
+                }
+                Err(_) => {
+                    ctx.emit("tcp", DiagnosticStatus::Error, "Timeout sau 30 giây");
+                    ctx.emit("ssl", DiagnosticStatus::Error, "Timeout");
+                    ctx.emit("http", DiagnosticStatus::Error, "Timeout");
+                    CheckOutcome::Failed
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// TCP timing against each resolved IP (load-balanced targets)
+struct PerIpTcpCheck;
+
+impl DiagnosticCheck for PerIpTcpCheck {
+    fn name(&self) -> &'static str {
+        "per_ip"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, raw_tcp: bool) -> Option<&'static [&'static str]> {
+        (config.test_each_ip && !raw_tcp && !config.has_target_override()).then_some(&["curl"])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            // Per-IP timing only matters for load-balanced (multi-IP) web targets;
+            // an override pins every probe to one server
+            let resolved_ips = ctx.resolved_ips();
+            if !self.enabled(ctx) || resolved_ips.len() < 2 {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit(
+                "per_ip",
+                DiagnosticStatus::Running,
+                &format!("Đang kiểm tra {} IP riêng lẻ...", resolved_ips.len()),
+            );
+
+            let result = timeout(
+                Duration::from_secs(35),
+                check_tcp_timing_per_ip(&ctx.url, &resolved_ips, &ctx.binding),
+            )
+            .await;
+            match &result {
+                Ok(Ok(result)) => {
+                    let failing = result.values().filter(|r| !r.is_healthy()).count();
+                    let status = if failing > 0 {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    };
+                    let summary = result
+                        .iter()
+                        .map(|(ip, r)| {
+                            if r.is_healthy() {
+                                format!("{}: {:.0}ms", ip, r.total_time_ms)
+                            } else if r.http_code > 0 {
+                                format!("{}: HTTP {}", ip, r.http_code)
+                            } else {
+                                format!("{}: lỗi", ip)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ctx.emit("per_ip", status, &summary);
+                }
+                Ok(Err(e)) => ctx.emit("per_ip", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("per_ip", DiagnosticStatus::Warning, "Timeout sau 35 giây"),
+            }
+            result.map(|r| r.map(CheckValue::PerIpTcp)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Certificate chain completeness (reported under the SSL step)
+struct SslCertCheck;
+
+impl DiagnosticCheck for SslCertCheck {
+    fn name(&self) -> &'static str {
+        "ssl_cert"
+    }
+
+    fn requirements(&self, _config: &DiagnosticConfig, raw_tcp: bool) -> Option<&'static [&'static str]> {
+        (!raw_tcp).then_some(&["openssl"])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            // Certificate chain only applies to https targets
+            if !self.enabled(ctx) || !ctx.url.starts_with("https://") {
+                return CheckOutcome::Skipped;
+            }
+            let Some((host, port)) = url::Url::parse(&ctx.url)
+                .ok()
+                .and_then(|u| Some((u.host_str()?.to_string(), u.port_or_known_default()?)))
+            else {
+                return CheckOutcome::Skipped;
+            };
+
+            let connect_host = ctx.target_override.as_ref().map_or(host.as_str(), |o| o.connect_to());
+            let result =
+                timeout(Duration::from_secs(15), check_ssl_cert(&host, port, connect_host)).await;
+            if let Ok(Ok(cert)) = &result {
+                if !cert.chain_complete {
+                    ctx.emit(
+                        "ssl",
+                        DiagnosticStatus::Warning,
+                        &format!("Certificate chain không đầy đủ ({} cert)", cert.chain_subjects.len()),
+                    );
+                }
+            }
+            result.map(|r| r.map(CheckValue::SslCert)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Raw TCP connect to host:port (non-web services)
+struct PortCheck;
+
+impl DiagnosticCheck for PortCheck {
+    fn name(&self) -> &'static str {
+        "port"
+    }
+
+    fn requirements(&self, _config: &DiagnosticConfig, raw_tcp: bool) -> Option<&'static [&'static str]> {
+        raw_tcp.then_some(&[])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            let Some((host, port)) = &ctx.raw_target else {
+                return CheckOutcome::Skipped;
+            };
+            ctx.emit("tcp", DiagnosticStatus::Running, "Đang kiểm tra kết nối TCP...");
+
+            let result = timeout(
+                Duration::from_secs(15),
+                check_port(host, *port, ctx.config.grab_banner, &ctx.binding),
+            )
+            .await;
+            match &result {
+                Ok(Ok(result)) if result.connected => {
+                    let status = if result.connect_time_ms > ctx.config.thresholds.connect_ms {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    };
+                    let banner = result
+                        .banner
+                        .as_ref()
+                        .map(|b| format!(", banner: {}", b))
+                        .unwrap_or_default();
+                    ctx.emit(
+                        "tcp",
+                        status,
+                        &format!("Port {} mở, connect: {:.0}ms{}", result.port, result.connect_time_ms, banner),
+                    );
+                }
+                Ok(Ok(result)) => ctx.emit(
+                    "tcp",
+                    DiagnosticStatus::Error,
+                    &format!(
+                        "Port {} không kết nối được: {}",
+                        result.port,
+                        result.error.as_deref().unwrap_or("không rõ lý do")
+                    ),
+                ),
+                Ok(Err(e)) => ctx.emit("tcp", DiagnosticStatus::Error, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("tcp", DiagnosticStatus::Error, "Timeout sau 15 giây"),
+            }
+            result.map(|r| r.map(CheckValue::PortCheck)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Traceroute to the address the TCP check actually connected to
+struct RoutingCheck;
+
+impl DiagnosticCheck for RoutingCheck {
+    fn name(&self) -> &'static str {
+        "routing"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        config.run_routing.then_some(&["traceroute", "curl"])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns", "tcp"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            if !self.enabled(ctx) {
+                ctx.emit("routing", DiagnosticStatus::Success, "Bỏ qua theo profile");
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("routing", DiagnosticStatus::Running, "Đang chạy traceroute...");
+
+            // curl may have picked IPv6 or another A record than the first one
+            let connected_ip = ctx.outputs().tcp().and_then(|t| t.remote_ip.clone());
+            let trace_ip = connected_ip.unwrap_or_else(|| ctx.target_ip());
+
+            // The public IP tells the NAT analysis where the local network ends
+            let (mut result, public_ip) = tokio::join!(
+                timeout(Duration::from_secs(30), check_routing(ctx.dns_host(), &trace_ip, &ctx.binding)),
+                detect_public_ip(),
+            );
+            if let Ok(Ok(routing)) = &mut result {
+                routing.public_ip = public_ip;
+            }
+            match &result {
+                Ok(Ok(result)) if result.needs_privileges => {
+                    ctx.emit("routing", DiagnosticStatus::Warning, "Traceroute cần quyền admin");
+                }
+                Ok(Ok(result)) => {
+                    let failed_hops = result.hops.iter().filter(|h| h.ip_address == "*").count();
+                    let status = if !result.loop_ips.is_empty()
+                        || failed_hops as f64 / result.hops.len().max(1) as f64 > 0.5
+                    {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    };
+                    let note = if let Some(loop_ip) = result.loop_ips.first() {
+                        format!(", vòng lặp tại {}", loop_ip)
+                    } else if result.destination_reached == Some(false) {
+                        ", chưa đến đích".to_string()
+                    } else {
+                        String::new()
+                    };
+                    ctx.emit(
+                        "routing",
+                        status,
+                        &format!("{} hop, {:.0}ms{}", result.total_hops, result.total_time_ms, note),
+                    );
+                }
+                Ok(Err(e)) => ctx.emit("routing", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("routing", DiagnosticStatus::Warning, "Timeout sau 30 giây"),
+            }
+            result.map(|r| r.map(CheckValue::Routing)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Repeated requests (or raw connects) to measure success rate and jitter
+///
+/// Probes by domain (curl resolves it itself), so it starts alongside DNS.
+struct StabilityCheck;
+
+impl DiagnosticCheck for StabilityCheck {
+    fn name(&self) -> &'static str {
+        "stability"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, raw_tcp: bool) -> Option<&'static [&'static str]> {
+        // Raw TCP mode times plain connects instead of curl requests
+        match (config.run_stability, raw_tcp) {
+            (false, _) => None,
+            (true, false) => Some(&["curl"]),
+            (true, true) => Some(&[]),
+        }
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            let config = &ctx.config;
+            if !self.enabled(ctx) {
+                ctx.emit("stability", DiagnosticStatus::Success, "Bỏ qua theo profile");
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("stability", DiagnosticStatus::Running, "Đang kiểm tra độ ổn định...");
+
+            let options = StabilityOptions {
+                num_tests: config.stability_tests,
+                delay: Duration::from_millis(config.stability_delay_ms),
+                warm_up: config.stability_warm_up,
+                reresolve: config.stability_reresolve,
+            };
+            // Longer inter-probe delays need a proportionally longer timeout
+            let stability_probes = config.stability_tests + u32::from(config.stability_warm_up);
+            let stability_timeout = Duration::from_secs(30) + options.delay * stability_probes;
+
+            // Live counter + sparkline while the probes run
+            let on_stability_probe = |probe: u32, successful: u32, rtt_ms: Option<f64>| {
+                let message = match rtt_ms {
+                    Some(rtt) => format!(
+                        "Lần {}/{}: {:.0}ms ({} thành công)",
+                        probe, config.stability_tests, rtt, successful
+                    ),
+                    None => format!(
+                        "Lần {}/{}: thất bại ({} thành công)",
+                        probe, config.stability_tests, successful
+                    ),
+                };
+                ctx.emit_data(
+                    "stability",
+                    DiagnosticStatus::Running,
+                    &message,
+                    serde_json::json!({
+                        "probe": probe,
+                        "total": config.stability_tests,
+                        "successful": successful,
+                        "rtt_ms": rtt_ms,
+                    }),
+                );
+            };
+
+            let result = match &ctx.raw_target {
+                Some((host, port)) => {
+                    timeout(
+                        stability_timeout,
+                        check_connect_stability(
+                            host,
+                            *port,
+                            &options,
+                            &ctx.binding,
+                            &on_stability_probe,
+                        ),
+                    )
+                    .await
+                }
+                None => {
+                    timeout(
+                        stability_timeout,
+                        check_stability(
+                            &ctx.domain,
+                            &options,
+                            &ctx.binding,
+                            ctx.target_override.as_ref(),
+                            &on_stability_probe,
+                        ),
+                    )
+                    .await
+                }
+            };
+
+            match &result {
+                Ok(Ok(result)) => {
+                    let status = if result.success_rate >= 100.0 {
+                        DiagnosticStatus::Success
+                    } else if result.success_rate >= 80.0 {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Error
+                    };
+                    ctx.emit(
+                        "stability",
+                        status,
+                        &format!(
+                            "{:.0}% thành công, avg {:.0}ms, jitter {:.0}ms",
+                            result.success_rate, result.avg_time_ms, result.jitter_ms
+                        ),
+                    );
+                }
+                Ok(Err(e)) => ctx.emit("stability", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit(
+                    "stability",
+                    DiagnosticStatus::Warning,
+                    &format!("Timeout sau {} giây", stability_timeout.as_secs()),
+                ),
+            }
+            result.map(|r| r.map(CheckValue::Stability)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Ping sweep with increasing payload sizes
+struct LatencyBySizeCheck;
+
+impl DiagnosticCheck for LatencyBySizeCheck {
+    fn name(&self) -> &'static str {
+        "latency_by_size"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        config.run_latency_by_size.then_some(&["ping"])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            let target_ip = ctx.target_ip();
+            if !self.enabled(ctx) || target_ip.is_empty() {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("latency_by_size", DiagnosticStatus::Running, "Đang ping với nhiều kích thước gói...");
+
+            let result = timeout(Duration::from_secs(45), check_latency_by_size(&target_ip, &ctx.binding)).await;
+            match &result {
+                Ok(Ok(result)) => {
+                    let summary = result
+                        .samples
+                        .iter()
+                        .chain(&result.mtu_probe)
+                        .map(|s| match s.avg_rtt_ms {
+                            Some(rtt) => format!("{}B: {:.0}ms", s.payload_bytes, rtt),
+                            None => format!("{}B: mất gói", s.payload_bytes),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ctx.emit("latency_by_size", DiagnosticStatus::Success, &summary);
+                }
+                Ok(Err(e)) => ctx.emit("latency_by_size", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("latency_by_size", DiagnosticStatus::Warning, "Timeout sau 45 giây"),
+            }
+            result.map(|r| r.map(CheckValue::LatencyBySize)).into()
+        }
+        .boxed()
+    }
+}
+
+/// DNS-based blocklist lookups of the resolved (and own) IPs
+struct BlocklistCheck;
+
+impl DiagnosticCheck for BlocklistCheck {
+    fn name(&self) -> &'static str {
+        "blocklist"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        // curl only looks up the user's own public IP
+        match (config.check_blocklists, config.check_own_ip_reputation) {
+            (false, _) => None,
+            (true, true) => Some(&["curl"]),
+            (true, false) => Some(&[]),
+        }
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            if !self.enabled(ctx) {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("blocklist", DiagnosticStatus::Running, "Đang kiểm tra blocklist...");
+
+            let result = timeout(
+                Duration::from_secs(20),
+                check_blocklists(&ctx.resolved_ips(), ctx.config.check_own_ip_reputation),
+            )
+            .await;
+            match &result {
+                Ok(Ok(result)) => {
+                    let status = if result.listings.is_empty() {
+                        DiagnosticStatus::Success
+                    } else {
+                        DiagnosticStatus::Warning
+                    };
+                    ctx.emit(
+                        "blocklist",
+                        status,
+                        &format!(
+                            "{} IP x {} blocklist, {} kết quả bị liệt kê",
+                            result.checked_ips.len(),
+                            result.blocklists.len(),
+                            result.listings.len()
+                        ),
+                    );
+                }
+                Ok(Err(e)) => ctx.emit("blocklist", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("blocklist", DiagnosticStatus::Warning, "Timeout sau 20 giây"),
+            }
+            result.map(|r| r.map(CheckValue::Blocklist)).into()
+        }
+        .boxed()
+    }
+}
+
+/// DNS-over-HTTPS / DNS-over-TLS reachability
+struct SecureDnsCheck;
+
+impl DiagnosticCheck for SecureDnsCheck {
+    fn name(&self) -> &'static str {
+        "secure_dns"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        // kdig is optional: DoT is reported as not checked without it
+        config.check_secure_dns.then_some(&["curl"])
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["dns"]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            if !self.enabled(ctx) {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("secure_dns", DiagnosticStatus::Running, "Đang kiểm tra DoH/DoT...");
+
+            let result = timeout(Duration::from_secs(20), check_secure_dns(&ctx.domain)).await;
+            match &result {
+                Ok(Ok(result)) => {
+                    let dot_text = match result.dot_available {
+                        Some(true) => "OK",
+                        Some(false) => "bị chặn",
+                        None => "không kiểm tra (thiếu kdig)",
+                    };
+                    let status = if result.doh_available && result.dot_available != Some(false) {
+                        DiagnosticStatus::Success
+                    } else {
+                        DiagnosticStatus::Warning
+                    };
+                    ctx.emit(
+                        "secure_dns",
+                        status,
+                        &format!(
+                            "DoH: {}, DoT: {}",
+                            if result.doh_available { "OK" } else { "bị chặn" },
+                            dot_text
+                        ),
+                    );
+                }
+                Ok(Err(e)) => ctx.emit("secure_dns", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("secure_dns", DiagnosticStatus::Warning, "Timeout sau 20 giây"),
+            }
+            result.map(|r| r.map(CheckValue::SecureDns)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Transparent proxy detection via a known control resource
+struct ProxyCheck;
+
+impl DiagnosticCheck for ProxyCheck {
+    fn name(&self) -> &'static str {
+        "proxy"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        config.detect_proxy.then_some(&["curl"])
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            if !self.enabled(ctx) {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("proxy", DiagnosticStatus::Running, "Đang kiểm tra proxy trung gian...");
+
+            let result = timeout(Duration::from_secs(15), check_transparent_proxy(&ctx.binding)).await;
+            match &result {
+                Ok(Ok(result)) if result.proxy_detected => {
+                    ctx.emit("proxy", DiagnosticStatus::Warning, "Phát hiện proxy trung gian")
+                }
+                Ok(Ok(_)) => ctx.emit("proxy", DiagnosticStatus::Success, "Không phát hiện proxy"),
+                Ok(Err(e)) => ctx.emit("proxy", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("proxy", DiagnosticStatus::Warning, "Timeout sau 15 giây"),
+            }
+            result.map(|r| r.map(CheckValue::Proxy)).into()
+        }
+        .boxed()
+    }
+}
+
+/// Separate download/upload throughput against a speed test server
+struct BandwidthCheck;
+
+impl DiagnosticCheck for BandwidthCheck {
+    fn name(&self) -> &'static str {
+        "bandwidth"
+    }
+
+    fn requirements(&self, config: &DiagnosticConfig, _raw_tcp: bool) -> Option<&'static [&'static str]> {
+        config.measure_bandwidth.then_some(&["curl"])
+    }
+
+    /// Saturating the link would skew every other measurement, so it runs last
+    fn dependencies(&self) -> &'static [&'static str] {
+        &[
+            "dns",
+            "tcp",
+            "per_ip",
+            "ssl_cert",
+            "port",
+            "routing",
+            "stability",
+            "latency_by_size",
+            "blocklist",
+            "secure_dns",
+            "proxy",
+        ]
+    }
+
+    fn run<'a>(&'a self, ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+        async move {
+            if !self.enabled(ctx) {
+                return CheckOutcome::Skipped;
+            }
+            ctx.emit("bandwidth", DiagnosticStatus::Running, "Đang đo tốc độ download/upload...");
+
+            let result = timeout(Duration::from_secs(130), check_bandwidth(&ctx.binding)).await;
+            match &result {
+                Ok(Ok(result)) => ctx.emit(
+                    "bandwidth",
+                    if result.upload_ratio.is_some_and(|r| r < ASYMMETRIC_UPLOAD_RATIO) {
+                        DiagnosticStatus::Warning
+                    } else {
+                        DiagnosticStatus::Success
+                    },
+                    &format!(
+                        "Download: {:.2} KB/s, Upload: {:.2} KB/s",
+                        result.download_kbps, result.upload_kbps
+                    ),
+                ),
+                Ok(Err(e)) => ctx.emit("bandwidth", DiagnosticStatus::Warning, &format!("Lỗi: {}", e)),
+                Err(_) => ctx.emit("bandwidth", DiagnosticStatus::Warning, "Timeout sau 130 giây"),
+            }
+            result.map(|r| r.map(CheckValue::Bandwidth)).into()
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Logs when it starts and when it finishes, `delay_ms` later
+    struct StubCheck {
+        name: &'static str,
+        dependencies: &'static [&'static str],
+        delay_ms: u64,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl DiagnosticCheck for StubCheck {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn dependencies(&self) -> &'static [&'static str] {
+            self.dependencies
+        }
+
+        fn run<'a>(&'a self, _ctx: &'a CheckContext) -> BoxFuture<'a, CheckOutcome> {
+            async move {
+                self.log.lock().unwrap().push(format!("start {}", self.name));
+                tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+                self.log.lock().unwrap().push(format!("end {}", self.name));
+                CheckOutcome::Skipped
+            }
+            .boxed()
+        }
+    }
+
+    /// Registry of stubs given as (name, dependencies, delay), sharing one log
+    fn stub_registry(
+        stubs: &[(&'static str, &'static [&'static str], u64)],
+    ) -> (CheckRegistry, Arc<Mutex<Vec<String>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = CheckRegistry::default();
+        for &(name, dependencies, delay_ms) in stubs {
+            registry.register(StubCheck { name, dependencies, delay_ms, log: log.clone() });
+        }
+        (registry, log)
+    }
+
+    /// Context with the given checks disabled, collecting progress events
+    fn stub_context(disabled: &[&str]) -> (CheckContext, Arc<Mutex<Vec<ProgressEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let config = DiagnosticConfig {
+            disabled_checks: disabled.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let ctx = CheckContext::new(
+            Box::new(move |event| sink.lock().unwrap().push(event)),
+            config,
+            SourceBinding::default(),
+            None,
+            "https://example.com".to_string(),
+            "example.com".to_string(),
+            None,
+        );
+        (ctx, events)
+    }
+
+    fn position(log: &[String], entry: &str) -> usize {
+        log.iter().position(|e| e == entry).unwrap_or_else(|| panic!("{} not in {:?}", entry, log))
+    }
+
+    #[tokio::test]
+    async fn dependents_start_after_their_dependencies_finish() {
+        let (registry, log) = stub_registry(&[("a", &[], 30), ("b", &["a"], 0), ("c", &["a", "b"], 0)]);
+        let (ctx, _) = stub_context(&[]);
+        registry.run(&ctx).await;
+
+        let log = log.lock().unwrap();
+        assert!(position(&log, "end a") < position(&log, "start b"));
+        assert!(position(&log, "end b") < position(&log, "start c"));
+    }
+
+    #[tokio::test]
+    async fn independent_checks_run_concurrently() {
+        let (registry, log) = stub_registry(&[("a", &[], 30), ("b", &[], 30)]);
+        let (ctx, _) = stub_context(&[]);
+        registry.run(&ctx).await;
+
+        let log = log.lock().unwrap();
+        assert!(position(&log, "start b") < position(&log, "end a"));
+        assert!(position(&log, "start a") < position(&log, "end b"));
+    }
+
+    #[tokio::test]
+    async fn disabled_checks_are_skipped_and_satisfy_dependents() {
+        let (registry, log) = stub_registry(&[("a", &[], 0), ("b", &["a"], 0)]);
+        let (ctx, events) = stub_context(&["a"]);
+        registry.run(&ctx).await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["start b", "end b"]);
+        assert!(matches!(ctx.outputs().0.get("a"), Some(CheckOutcome::Skipped)));
+        assert!(events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| e.step == "a" && e.message == "Bỏ qua theo cấu hình"));
+    }
+
+    #[test]
+    fn default_checks_only_depend_on_registered_checks() {
+        assert_eq!(CheckRegistry::with_default_checks().unknown_dependencies(), Vec::new());
+    }
+
+    #[test]
+    fn bandwidth_runs_after_every_other_check() {
+        let others: Vec<&str> = CheckRegistry::with_default_checks()
+            .names()
+            .into_iter()
+            .filter(|name| *name != BandwidthCheck.name())
+            .collect();
+        assert_eq!(BandwidthCheck.dependencies(), others.as_slice());
+    }
+}
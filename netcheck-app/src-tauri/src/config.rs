@@ -0,0 +1,255 @@
+//! Diagnostic run configuration
+//!
+//! Holds the knobs that control which phases run and how results are graded,
+//! plus named profiles that preconfigure them for common kinds of targets.
+
+use serde::{Deserialize, Serialize};
+
+/// Named preset tuned for a kind of target
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticProfile {
+    /// Regular website: balanced checks (default behaviour)
+    Web,
+    /// Game server: latency and jitter matter most
+    Gaming,
+    /// Media/download mirror: sustained, steady transfers matter most
+    Streaming,
+    /// API endpoint: fast server response (TTFB) matters most
+    Api,
+}
+
+impl DiagnosticProfile {
+    /// Human-readable profile name for reports
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiagnosticProfile::Web => "Web",
+            DiagnosticProfile::Gaming => "Gaming",
+            DiagnosticProfile::Streaming => "Streaming",
+            DiagnosticProfile::Api => "API",
+        }
+    }
+
+    /// Expand the profile into a full diagnostic config
+    pub fn config(self) -> DiagnosticConfig {
+        let defaults = DiagnosticThresholds::default();
+
+        match self {
+            DiagnosticProfile::Web => DiagnosticConfig {
+                profile: Some(self),
+                ..DiagnosticConfig::default()
+            },
+            DiagnosticProfile::Gaming => DiagnosticConfig {
+                profile: Some(self),
+                stability_tests: 20,
+                thresholds: DiagnosticThresholds {
+                    connect_ms: 150.0,
+                    total_ms: 2000.0,
+                    jitter_ms: 30.0,
+                    ..defaults
+                },
+                ..DiagnosticConfig::default()
+            },
+            DiagnosticProfile::Streaming => DiagnosticConfig {
+                profile: Some(self),
+                stability_tests: 20,
+                thresholds: DiagnosticThresholds {
+                    total_ms: 5000.0,
+                    jitter_ms: 50.0,
+                    ..defaults
+                },
+                ..DiagnosticConfig::default()
+            },
+            DiagnosticProfile::Api => DiagnosticConfig {
+                profile: Some(self),
+                run_routing: false,
+                thresholds: DiagnosticThresholds {
+                    dns_ms: 100.0,
+                    total_ms: 1000.0,
+                    ttfb_ms: Some(500.0),
+                    ..defaults
+                },
+                ..DiagnosticConfig::default()
+            },
+        }
+    }
+}
+
+/// When a completed report is posted to the webhook
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookCondition {
+    /// After every run
+    #[default]
+    Always,
+    /// Only when the overall status is Poor or Failed
+    Degraded,
+}
+
+/// Contractual limits for a pass/fail verdict, independent of the scoring
+/// 
+/// Unset limits are not checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlaConfig {
+    pub max_dns_ms: Option<f64>,
+    pub max_ttfb_ms: Option<f64>,
+    pub max_total_ms: Option<f64>,
+    /// Stability success rate in percent
+    pub min_success_rate: Option<f64>,
+    pub max_jitter_ms: Option<f64>,
+}
+
+/// Webhook that receives the final report JSON
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Full header line, e.g. "Authorization: Bearer <token>"
+    pub auth_header: Option<String>,
+    #[serde(default)]
+    pub post_on: WebhookCondition,
+}
+
+// The auth header is a secret, so Debug is implemented by hand
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("auth_header", &self.auth_header.as_ref().map(|_| "<redacted>"))
+            .field("post_on", &self.post_on)
+            .finish()
+    }
+}
+
+/// Thresholds used to grade results (values above them are flagged)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagnosticThresholds {
+    pub dns_ms: f64,
+    pub connect_ms: f64,
+    pub ssl_ms: f64,
+    pub total_ms: f64,
+    /// Only checked when set (the API profile cares about server response time)
+    pub ttfb_ms: Option<f64>,
+    pub jitter_ms: f64,
+}
+
+impl Default for DiagnosticThresholds {
+    fn default() -> Self {
+        Self {
+            dns_ms: 200.0,
+            connect_ms: 500.0,
+            ssl_ms: 500.0,
+            total_ms: 3000.0,
+            ttfb_ms: None,
+            jitter_ms: 100.0,
+        }
+    }
+}
+
+/// Configuration for a single diagnostic run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagnosticConfig {
+    pub profile: Option<DiagnosticProfile>,
+    pub run_routing: bool,
+    pub run_stability: bool,
+    pub stability_tests: u32,
+    /// Pause between stability probes. 0 measures burst behaviour (back-to-back
+    /// connections); larger values probe gently and spread samples over time,
+    /// which tends to raise jitter as it captures more network variation.
+    pub stability_delay_ms: u64,
+    /// Send one untimed request before the stability probes so the cold first
+    /// connection (DNS cache miss, route setup) doesn't inflate max/avg/jitter.
+    /// Not counted in `total_tests` or the success rate.
+    pub stability_warm_up: bool,
+    /// Resolve the domain again before each stability probe and pin the probe
+    /// to that IP, counting IP changes (catches DNS failover flapping)
+    pub stability_reresolve: bool,
+    pub thresholds: DiagnosticThresholds,
+    /// Ping with increasing payload sizes to spot fragmentation/shaping
+    pub run_latency_by_size: bool,
+    /// Check resolved IPs against DNS-based blocklists
+    pub check_blocklists: bool,
+    /// Also check the user's own public IP (requires `check_blocklists`)
+    pub check_own_ip_reputation: bool,
+    /// Probe DNS-over-HTTPS / DNS-over-TLS resolvers (contacts public resolvers)
+    pub check_secure_dns: bool,
+    /// Fetch a control resource over plain HTTP to detect transparent proxies
+    pub detect_proxy: bool,
+    /// Measure download and upload throughput separately against a public
+    /// speed test server (transfers ~15 MB)
+    pub measure_bandwidth: bool,
+    /// Run the TCP timing check against every resolved IP individually
+    pub test_each_ip: bool,
+    /// Treat the target as a raw host:port service (no HTTP/SSL)
+    pub raw_tcp: bool,
+    /// Read the service greeting after connecting in raw TCP mode
+    pub grab_banner: bool,
+    /// Send probes out of this network interface (e.g. "utun3", "eth0")
+    pub interface: Option<String>,
+    /// Bind probes to this local source IP
+    pub source_ip: Option<String>,
+    /// Connect HTTP probes to this IP instead of the resolved one
+    pub connect_ip: Option<String>,
+    /// Present this name as Host header and SNI instead of the URL host
+    pub host_override: Option<String>,
+    /// Checks to leave out, by name (see `CheckRegistry::names`)
+    pub disabled_checks: Vec<String>,
+    /// Treat any Warning issue as a failure: the overall status is capped at
+    /// Poor. The score itself is unchanged (for CI gating)
+    pub strict: bool,
+    /// POST the final report to this webhook
+    pub webhook: Option<WebhookConfig>,
+    /// Grade the run against these limits as well (`sla_met` on the report)
+    pub sla: Option<SlaConfig>,
+    /// Attach the raw stdout/stderr of every tool run (dig, curl, traceroute,
+    /// ping, openssl) to the report, for support staff
+    pub capture_raw: bool,
+}
+
+impl Default for DiagnosticConfig {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            run_routing: true,
+            run_stability: true,
+            stability_tests: 10,
+            stability_delay_ms: 100,
+            stability_warm_up: false,
+            stability_reresolve: false,
+            thresholds: DiagnosticThresholds::default(),
+            run_latency_by_size: false,
+            check_blocklists: false,
+            check_own_ip_reputation: false,
+            check_secure_dns: false,
+            detect_proxy: false,
+            measure_bandwidth: false,
+            test_each_ip: false,
+            raw_tcp: false,
+            grab_banner: true,
+            interface: None,
+            source_ip: None,
+            connect_ip: None,
+            host_override: None,
+            disabled_checks: Vec::new(),
+            strict: false,
+            webhook: None,
+            sla: None,
+            capture_raw: false,
+        }
+    }
+}
+
+impl DiagnosticConfig {
+    /// An explicit config wins over a profile preset
+    pub fn effective(profile: Option<DiagnosticProfile>, config: Option<DiagnosticConfig>) -> Self {
+        config.unwrap_or_else(|| profile.map(|p| p.config()).unwrap_or_default())
+    }
+
+    /// A connect IP or Host/SNI override is set (blank values don't count)
+    pub fn has_target_override(&self) -> bool {
+        [&self.connect_ip, &self.host_override]
+            .iter()
+            .any(|v| v.as_deref().is_some_and(|v| !v.trim().is_empty()))
+    }
+}
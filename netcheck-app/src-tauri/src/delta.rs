@@ -0,0 +1,222 @@
+//! Session comparison against the previous run
+//!
+//! Remembers the last report per target for the lifetime of the app and
+//! summarizes what changed, for quick feedback while troubleshooting.
+
+use crate::types::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The parts of a report the delta compares
+#[derive(Debug, Clone)]
+pub struct DeltaSnapshot {
+    overall_status: OverallStatus,
+    dns_ms: Option<f64>,
+    /// HTTP total time, or the raw TCP connect time
+    connection_ms: Option<f64>,
+    /// (success rate, jitter)
+    stability: Option<(f64, f64)>,
+    issue_titles: Vec<String>,
+}
+
+impl DeltaSnapshot {
+    pub fn of(report: &DiagnosticReport) -> Self {
+        Self {
+            overall_status: report.overall_status.clone(),
+            dns_ms: report.dns.as_ref().map(|d| d.lookup_time_ms),
+            connection_ms: report
+                .tcp
+                .as_ref()
+                .filter(|t| t.http_code != 0)
+                .map(|t| t.total_time_ms)
+                .or_else(|| {
+                    report
+                        .port_check
+                        .as_ref()
+                        .filter(|p| p.connected)
+                        .map(|p| p.connect_time_ms)
+                }),
+            stability: report.stability.as_ref().map(|s| (s.success_rate, s.jitter_ms)),
+            issue_titles: report.issues.iter().map(|i| i.title.clone()).collect(),
+        }
+    }
+}
+
+/// Last run per target URL (Tauri managed state)
+///
+/// Only a snapshot is kept, not the full report with its raw output.
+#[derive(Default)]
+pub struct PreviousReports(Mutex<HashMap<String, DeltaSnapshot>>);
+
+impl PreviousReports {
+    /// Store `report` as the latest for its target, returning the snapshot it replaces
+    pub fn replace(&self, report: &DiagnosticReport) -> Option<DeltaSnapshot> {
+        let mut reports = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        reports.insert(report.target_url.clone(), DeltaSnapshot::of(report))
+    }
+}
+
+/// Minimum change (ms) for a timing to be mentioned
+const MIN_TIMING_DELTA_MS: f64 = 20.0;
+
+/// Minimum relative change for a timing to be mentioned
+const MIN_TIMING_DELTA_RATIO: f64 = 0.2;
+
+/// Summarize the main changes versus the previous report
+///
+/// e.g. "DNS 40ms faster, stability dropped to 80%"
+pub fn describe_delta(previous: &DeltaSnapshot, current: &DeltaSnapshot) -> String {
+    let mut changes: Vec<String> = Vec::new();
+
+    if status_rank(&previous.overall_status) != status_rank(&current.overall_status) {
+        changes.push(format!(
+            "status {} -> {}",
+            status_label(&previous.overall_status),
+            status_label(&current.overall_status)
+        ));
+    }
+
+    if let Some(change) = timing_change("DNS", previous.dns_ms, current.dns_ms) {
+        changes.push(change);
+    }
+
+    if let Some(change) = timing_change("connection", previous.connection_ms, current.connection_ms) {
+        changes.push(change);
+    }
+
+    if let (Some((rate_before, jitter_before)), Some((rate_after, jitter_after))) =
+        (previous.stability, current.stability)
+    {
+        if (rate_after - rate_before).abs() >= 1.0 {
+            let direction = if rate_after > rate_before { "rose" } else { "dropped" };
+            changes.push(format!("stability {} to {:.0}%", direction, rate_after));
+        }
+        if let Some(change) = timing_change("jitter", Some(jitter_before), Some(jitter_after)) {
+            changes.push(change);
+        }
+    }
+
+    if let Some(change) = issue_change(&previous.issue_titles, &current.issue_titles) {
+        changes.push(change);
+    }
+
+    if changes.is_empty() {
+        "No significant change since the previous run".to_string()
+    } else {
+        changes.join(", ")
+    }
+}
+
+/// Describe a timing change, `None` when it is too small to matter
+fn timing_change(label: &str, before: Option<f64>, after: Option<f64>) -> Option<String> {
+    let (before, after) = (before?, after?);
+    let delta = after - before;
+
+    if delta.abs() < MIN_TIMING_DELTA_MS || delta.abs() < before * MIN_TIMING_DELTA_RATIO {
+        return None;
+    }
+
+    Some(format!(
+        "{} {:.0}ms {}",
+        label,
+        delta.abs(),
+        if delta < 0.0 { "faster" } else { "slower" }
+    ))
+}
+
+/// Count issues that appeared or went away, matched by title
+fn issue_change(before: &[String], after: &[String]) -> Option<String> {
+    let mut resolved: Vec<&String> = before.iter().collect();
+    let mut new = 0;
+    for title in after {
+        match resolved.iter().position(|t| *t == title) {
+            Some(i) => {
+                resolved.remove(i);
+            }
+            None => new += 1,
+        }
+    }
+
+    let issues = |n: usize| if n == 1 { "issue" } else { "issues" };
+    match (new, resolved.len()) {
+        (0, 0) => None,
+        (new, 0) => Some(format!("{} new {}", new, issues(new))),
+        (0, resolved) => Some(format!("{} {} resolved", resolved, issues(resolved))),
+        (new, resolved) => Some(format!("{} new {}, {} resolved", new, issues(new), resolved)),
+    }
+}
+
+/// Order of statuses from best to worst
+fn status_rank(status: &OverallStatus) -> u8 {
+    match status {
+        OverallStatus::Excellent => 0,
+        OverallStatus::Good => 1,
+        OverallStatus::Acceptable => 2,
+        OverallStatus::Poor => 3,
+        OverallStatus::Failed => 4,
+    }
+}
+
+/// Lowercase status name
+fn status_label(status: &OverallStatus) -> &'static str {
+    match status {
+        OverallStatus::Excellent => "excellent",
+        OverallStatus::Good => "good",
+        OverallStatus::Acceptable => "acceptable",
+        OverallStatus::Poor => "poor",
+        OverallStatus::Failed => "failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(status: OverallStatus, dns_ms: f64, success_rate: f64, issues: &[&str]) -> DeltaSnapshot {
+        DeltaSnapshot {
+            overall_status: status,
+            dns_ms: Some(dns_ms),
+            connection_ms: Some(200.0),
+            stability: Some((success_rate, 5.0)),
+            issue_titles: issues.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn improvement_is_described() {
+        let before = snapshot(OverallStatus::Poor, 120.0, 80.0, &["DNS chậm", "Mất gói"]);
+        let after = snapshot(OverallStatus::Good, 40.0, 100.0, &["Mất gói"]);
+
+        assert_eq!(
+            describe_delta(&before, &after),
+            "status poor -> good, DNS 80ms faster, stability rose to 100%, 1 issue resolved"
+        );
+    }
+
+    #[test]
+    fn regression_is_described() {
+        let before = snapshot(OverallStatus::Excellent, 30.0, 100.0, &[]);
+        let after = snapshot(OverallStatus::Acceptable, 90.0, 80.0, &["DNS chậm", "Mất gói"]);
+
+        assert_eq!(
+            describe_delta(&before, &after),
+            "status excellent -> acceptable, DNS 60ms slower, stability dropped to 80%, 2 new issues"
+        );
+    }
+
+    #[test]
+    fn small_changes_are_no_change() {
+        let before = snapshot(OverallStatus::Good, 40.0, 100.0, &["DNS chậm"]);
+        let after = snapshot(OverallStatus::Good, 45.0, 99.5, &["DNS chậm"]);
+
+        assert_eq!(describe_delta(&before, &after), "No significant change since the previous run");
+    }
+
+    #[test]
+    fn swapped_issues_count_as_new_and_resolved() {
+        let before = snapshot(OverallStatus::Good, 40.0, 100.0, &["DNS chậm"]);
+        let after = snapshot(OverallStatus::Good, 40.0, 100.0, &["Mất gói"]);
+
+        assert_eq!(describe_delta(&before, &after), "1 new issue, 1 resolved");
+    }
+}
@@ -8,9 +8,14 @@
 //! - Routing path analysis
 //! - Connection stability testing
 
+use crate::config::{DiagnosticThresholds, SlaConfig};
+use crate::explain::{Metric, UNSTABLE_SUCCESS_RATE};
+use crate::raw_log;
 use crate::types::*;
+use chrono::Datelike;
 use regex::Regex;
-use std::process::Command;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 use url::Url;
 
@@ -27,69 +32,779 @@ pub fn parse_domain(url_str: &str) -> Result<String, String> {
         .ok_or_else(|| "Cannot extract domain from URL".to_string())
 }
 
+/// Normalize user input into a full URL (defaults to https)
+pub fn normalize_url(target_url: &str) -> String {
+    if target_url.starts_with("http") {
+        target_url.to_string()
+    } else {
+        format!("https://{}", target_url)
+    }
+}
+
+/// Interface / source IP that probes are sent from (multi-homed machines)
+/// 
+/// curl, ping and traceroute get the matching flags; native TCP connects bind
+/// the source IP (and the interface on Linux). DNS lookups are not bound.
+#[derive(Debug, Clone, Default)]
+pub struct SourceBinding {
+    pub interface: Option<String>,
+    pub source_ip: Option<std::net::IpAddr>,
+}
+
+impl SourceBinding {
+    /// Build from config, checking the interface / IP exist on this machine
+    /// 
+    /// Only one of the two may be set: curl's `--interface` binds to either an
+    /// interface or an address, so the other would be silently ignored.
+    pub fn from_config(interface: Option<&str>, source_ip: Option<&str>) -> Result<Self, String> {
+        let interface = interface.map(str::trim).filter(|i| !i.is_empty());
+        let source_ip = source_ip.map(str::trim).filter(|ip| !ip.is_empty());
+        if interface.is_some() && source_ip.is_some() {
+            return Err("Set either an interface or a source IP, not both".to_string());
+        }
+        if let Some(name) = interface {
+            check_interface(name)?;
+        }
+        
+        let source_ip = match source_ip {
+            Some(ip) => {
+                let addr: std::net::IpAddr = ip
+                    .parse()
+                    .map_err(|_| format!("Invalid source IP: {}", ip))?;
+                // Binding only succeeds for addresses assigned to a local interface
+                std::net::UdpSocket::bind((addr, 0))
+                    .map_err(|e| format!("Source IP {} is not assigned to this machine: {}", ip, e))?;
+                Some(addr)
+            }
+            None => None,
+        };
+        
+        Ok(Self {
+            interface: interface.map(|i| i.to_string()),
+            source_ip,
+        })
+    }
+    
+    /// Human-readable description, `None` when probes use the default route
+    pub fn describe(&self) -> Option<String> {
+        match (&self.interface, &self.source_ip) {
+            (Some(iface), Some(ip)) => Some(format!("interface {} ({})", iface, ip)),
+            (Some(iface), None) => Some(format!("interface {}", iface)),
+            (None, Some(ip)) => Some(format!("source IP {}", ip)),
+            (None, None) => None,
+        }
+    }
+    
+    /// curl `--interface` with the source IP or the interface
+    fn curl_args(&self) -> Vec<String> {
+        match (&self.source_ip, &self.interface) {
+            (Some(ip), _) => vec!["--interface".to_string(), ip.to_string()],
+            (None, Some(iface)) => vec!["--interface".to_string(), iface.clone()],
+            (None, None) => Vec::new(),
+        }
+    }
+    
+    /// ping source flags (`-S`/`-b` on macOS, `-I` elsewhere)
+    fn ping_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if cfg!(target_os = "macos") {
+            if let Some(ip) = &self.source_ip {
+                args.extend(["-S".to_string(), ip.to_string()]);
+            }
+            if let Some(iface) = &self.interface {
+                args.extend(["-b".to_string(), iface.clone()]);
+            }
+        } else if let Some(source) = self.source_ip.map(|ip| ip.to_string()).or(self.interface.clone()) {
+            args.extend(["-I".to_string(), source]);
+        }
+        args
+    }
+    
+    /// traceroute `-i <interface>` / `-s <source ip>`
+    fn traceroute_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(iface) = &self.interface {
+            args.extend(["-i".to_string(), iface.clone()]);
+        }
+        if let Some(ip) = &self.source_ip {
+            args.extend(["-s".to_string(), ip.to_string()]);
+        }
+        args
+    }
+    
+    /// Open a TCP connection from the bound source
+    async fn connect(&self, addr: std::net::SocketAddr) -> std::io::Result<tokio::net::TcpStream> {
+        if self.interface.is_none() && self.source_ip.is_none() {
+            return tokio::net::TcpStream::connect(addr).await;
+        }
+        
+        let socket = if addr.is_ipv4() {
+            tokio::net::TcpSocket::new_v4()?
+        } else {
+            tokio::net::TcpSocket::new_v6()?
+        };
+        if let Some(ip) = self.source_ip {
+            socket.bind(std::net::SocketAddr::new(ip, 0))?;
+        }
+        if let Some(iface) = &self.interface {
+            bind_device(&socket, iface)?;
+        }
+        socket.connect(addr).await
+    }
+}
+
+/// Bind a socket to a network interface (SO_BINDTODEVICE)
+#[cfg(target_os = "linux")]
+fn bind_device(socket: &tokio::net::TcpSocket, interface: &str) -> std::io::Result<()> {
+    socket.bind_device(Some(interface.as_bytes()))
+}
+
+/// Interface binding for native sockets is Linux-only; elsewhere the source IP is used
+#[cfg(not(target_os = "linux"))]
+fn bind_device(_socket: &tokio::net::TcpSocket, _interface: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Connect to a chosen server while presenting a chosen name (Host + SNI)
+/// 
+/// For testing an origin behind a load balancer or before a DNS cutover. curl
+/// keeps the presented name in the URL (so Host and SNI match it) and is sent
+/// to the connect address with `--connect-to`.
+#[derive(Debug, Clone)]
+pub struct TargetOverride {
+    /// Name sent as Host header and SNI
+    pub host: String,
+    /// Address (or original host) the connection goes to
+    connect_to: String,
+}
+
+impl TargetOverride {
+    /// Build from config, `None` when neither override is set
+    /// 
+    /// `url_host` fills in whichever side is not overridden.
+    pub fn from_config(
+        connect_ip: Option<&str>,
+        host_override: Option<&str>,
+        url_host: &str,
+    ) -> Result<Option<Self>, String> {
+        let connect_ip = connect_ip.map(str::trim).filter(|ip| !ip.is_empty());
+        let host_override = host_override.map(str::trim).filter(|h| !h.is_empty());
+        if connect_ip.is_none() && host_override.is_none() {
+            return Ok(None);
+        }
+        
+        if let Some(ip) = connect_ip {
+            ip.parse::<std::net::IpAddr>()
+                .map_err(|_| format!("Invalid connect IP: {}", ip))?;
+        }
+        if let Some(host) = host_override {
+            if host.contains(['/', ':', ' ']) {
+                return Err(format!("Invalid host override (expected a bare host name): {}", host));
+            }
+        }
+        
+        Ok(Some(Self {
+            host: host_override.unwrap_or(url_host).to_lowercase(),
+            connect_to: connect_ip.unwrap_or(url_host).to_string(),
+        }))
+    }
+    
+    /// Human-readable description for the report
+    pub fn describe(&self) -> String {
+        format!("Host/SNI {}, connecting to {}", self.host, self.connect_to)
+    }
+    
+    /// Host the connection actually goes to
+    pub fn connect_to(&self) -> &str {
+        &self.connect_to
+    }
+    
+    /// The connect address when it is an IP (what routing/ping should target)
+    pub fn connect_ip(&self) -> Option<&str> {
+        self.connect_to
+            .parse::<std::net::IpAddr>()
+            .is_ok()
+            .then_some(self.connect_to.as_str())
+    }
+    
+    /// Put the presented name into `url`
+    pub fn apply_to_url(&self, url: &str) -> Result<String, String> {
+        let mut parsed = Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+        parsed
+            .set_host(Some(&self.host))
+            .map_err(|e| format!("Invalid host override {}: {}", self.host, e))?;
+        Ok(parsed.to_string())
+    }
+    
+    /// curl `--connect-to <host>::<address>:` (any port, port kept)
+    /// 
+    /// Scoped to the presented name so redirects to other hosts (`www.`,
+    /// SSO, CDN) still go to their own servers.
+    fn curl_args(&self) -> Vec<String> {
+        vec![
+            "--connect-to".to_string(),
+            format!("{}::{}:", bracket_ipv6(&self.host), bracket_ipv6(&self.connect_to)),
+        ]
+    }
+}
+
+/// Wrap an IPv6 literal in brackets for curl host:port arguments
+fn bracket_ipv6(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// Check a network interface exists on this machine
+#[cfg(unix)]
+fn check_interface(name: &str) -> Result<(), String> {
+    let sys_net = std::path::Path::new("/sys/class/net");
+    let exists = if sys_net.is_dir() {
+        sys_net.join(name).exists()
+    } else {
+        std::process::Command::new("ifconfig")
+            .arg(name)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    };
+    if exists {
+        Ok(())
+    } else {
+        Err(format!("Network interface not found: {}", name))
+    }
+}
+
+/// curl on Windows binds only to addresses, and there is no ifconfig to ask
+#[cfg(not(unix))]
+fn check_interface(name: &str) -> Result<(), String> {
+    Err(format!(
+        "Binding to an interface ({}) is not supported on this platform, set a source IP instead",
+        name
+    ))
+}
+
+/// Answer from a single DNS backend, normalized across tools
+#[derive(Debug, Default)]
+struct DnsLookup {
+    resolved_ips: Vec<String>,
+    resolved_ipv6: Vec<String>,
+    lookup_time_ms: f64,
+    ttl: Option<u32>,
+    nameservers: Option<Vec<String>>,
+}
+
+/// Backends in the order they are tried
+const DNS_BACKENDS: [DnsBackend; 4] = [
+    DnsBackend::Native,
+    DnsBackend::Dig,
+    DnsBackend::Nslookup,
+    DnsBackend::Host,
+];
+
+/// Public resolvers tried when the local resolver fails
+const PUBLIC_RESOLVERS: [&str; 2] = ["1.1.1.1", "8.8.8.8"];
+
+/// Time allowed for the local resolver before falling back
+const LOCAL_DNS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for each public resolver
+const PUBLIC_DNS_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// Run DNS resolution diagnostic
+/// 
+/// Tries each backend in turn (native resolver, dig, nslookup, host) and uses
+/// the first one that returns addresses. Backends that are not installed are
+/// skipped, so the check works on machines without `dig`.
+/// 
+/// If the local resolver fails, times out or finds nothing, the query is
+/// retried against public resolvers (1.1.1.1, then 8.8.8.8). An answer from a
+/// public resolver is returned with `resolver` and `local_resolver_error` set.
 pub async fn check_dns(domain: &str) -> Result<DnsResult, String> {
+    let local = match tokio::time::timeout(LOCAL_DNS_TIMEOUT, check_dns_local(domain)).await {
+        Ok(Ok(result)) if !result.resolved_ips.is_empty() => return Ok(result),
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(format!("Local resolver timed out after {}s", LOCAL_DNS_TIMEOUT.as_secs())),
+    };
+    
+    for resolver in PUBLIC_RESOLVERS {
+        if let Ok(Ok((backend, lookup))) = tokio::time::timeout(PUBLIC_DNS_TIMEOUT, lookup_public(resolver, domain)).await {
+            if lookup.resolved_ips.is_empty() {
+                continue;
+            }
+            
+            let local_error = match &local {
+                Ok(_) => "Local resolver returned no addresses".to_string(),
+                Err(e) => e.clone(),
+            };
+            let using_cdn = detect_cdn(&lookup.nameservers.clone().unwrap_or_default());
+            
+            return Ok(DnsResult {
+                domain: domain.to_string(),
+                resolved_ips: lookup.resolved_ips,
+                resolved_ipv6: lookup.resolved_ipv6,
+                lookup_time_ms: lookup.lookup_time_ms,
+                ttl: lookup.ttl,
+                nameservers: lookup.nameservers,
+                using_cdn,
+                backend: Some(backend),
+                resolver: Some(resolver.to_string()),
+                local_resolver_error: Some(local_error),
+            });
+        }
+    }
+    
+    // Public resolvers found nothing either: the local answer stands
+    local
+}
+
+/// Resolve through the local (system-configured) resolver
+async fn check_dns_local(domain: &str) -> Result<DnsResult, String> {
+    let mut answer: Option<(DnsBackend, DnsLookup)> = None;
+    let mut errors: Vec<String> = Vec::new();
+    
+    for backend in DNS_BACKENDS {
+        match lookup_with_backend(backend, domain).await {
+            Ok(lookup) if !lookup.resolved_ips.is_empty() => {
+                answer = Some((backend, lookup));
+                break;
+            }
+            // Keep the first empty answer in case no backend finds anything
+            Ok(lookup) => {
+                if answer.is_none() {
+                    answer = Some((backend, lookup));
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    
+    let (backend, mut lookup) = answer
+        .ok_or_else(|| format!("No DNS backend available: {}", errors.join("; ")))?;
+    
+    // The native resolver only returns addresses, so fill TTL/nameservers
+    // from the first command-line tool that can provide them
+    if lookup.ttl.is_none() || lookup.nameservers.is_none() {
+        for extra in DNS_BACKENDS.iter().filter(|b| **b != backend && **b != DnsBackend::Native) {
+            if let Ok(extra_lookup) = lookup_with_backend(*extra, domain).await {
+                lookup.ttl = lookup.ttl.or(extra_lookup.ttl);
+                if lookup.resolved_ipv6.is_empty() {
+                    lookup.resolved_ipv6 = extra_lookup.resolved_ipv6;
+                }
+                lookup.nameservers = lookup.nameservers.or(extra_lookup.nameservers);
+                break;
+            }
+        }
+    }
+    
+    // Detect CDN
+    let using_cdn = detect_cdn(&lookup.nameservers.clone().unwrap_or_default());
+    
+    Ok(DnsResult {
+        domain: domain.to_string(),
+        resolved_ips: lookup.resolved_ips,
+        resolved_ipv6: lookup.resolved_ipv6,
+        lookup_time_ms: lookup.lookup_time_ms,
+        ttl: lookup.ttl,
+        nameservers: lookup.nameservers,
+        using_cdn,
+        backend: Some(backend),
+        resolver: None,
+        local_resolver_error: None,
+    })
+}
+
+/// Resolve against a specific public resolver with dig, falling back to nslookup
+async fn lookup_public(resolver: &str, domain: &str) -> Result<(DnsBackend, DnsLookup), String> {
+    let server = format!("@{}", resolver);
+    
+    if let Ok((a_output, lookup_time)) = timed_a_query("dig", [server.as_str(), "+short", domain, "A"]).await {
+        let (aaaa, ns, answer) = tokio::join!(
+            run_dns_query("dig", [server.as_str(), "+short", domain, "AAAA"]),
+            run_dns_query("dig", [server.as_str(), domain, "NS", "+short"]),
+            run_dns_query("dig", [server.as_str(), domain, "+noall", "+answer"]),
+        );
+        
+        return Ok((DnsBackend::Dig, DnsLookup {
+            resolved_ips: parse_ip_lines(&a_output),
+            resolved_ipv6: aaaa.map(|out| parse_ip_lines(&out)).unwrap_or_default(),
+            lookup_time_ms: lookup_time,
+            ttl: answer.and_then(|out| parse_answer_ttl(&out)),
+            nameservers: ns.map(|out| {
+                out.lines()
+                    .filter(|l| !l.is_empty())
+                    .map(|s| s.trim().to_string())
+                    .collect()
+            }),
+        }));
+    }
+    
+    let (a_output, lookup_time) = timed_a_query("nslookup", ["-type=A", domain, resolver]).await?;
+    let aaaa = run_dns_query("nslookup", ["-type=AAAA", domain, resolver]).await;
+    
+    Ok((DnsBackend::Nslookup, DnsLookup {
+        resolved_ips: parse_nslookup_addresses(&a_output, true),
+        resolved_ipv6: aaaa
+            .map(|out| parse_nslookup_addresses(&out, false))
+            .unwrap_or_default(),
+        lookup_time_ms: lookup_time,
+        ttl: None,
+        nameservers: None,
+    }))
+}
+
+/// Resolve a domain with a single backend
+async fn lookup_with_backend(backend: DnsBackend, domain: &str) -> Result<DnsLookup, String> {
+    match backend {
+        DnsBackend::Native => lookup_native(domain).await,
+        DnsBackend::Dig => lookup_dig(domain).await,
+        DnsBackend::Nslookup => lookup_nslookup(domain).await,
+        DnsBackend::Host => lookup_host(domain).await,
+    }
+}
+
+/// Locale for external tools whose output is parsed: untranslated messages
+/// and `.` as the decimal separator, whatever the user's language
+const PARSE_LOCALE: (&str, &str) = ("LC_ALL", "C");
+
+/// Run a DNS tool and return its stdout, `None` if it could not be run
+async fn run_dns_query<const N: usize>(program: &str, args: [&str; N]) -> Option<String> {
+    let mut command = tokio::process::Command::new(program);
+    command.args(args).env(PARSE_LOCALE.0, PARSE_LOCALE.1);
+    raw_log::output_async(&mut command)
+        .await
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+/// Run the primary A query, timing only this query
+async fn timed_a_query<const N: usize>(program: &str, args: [&str; N]) -> Result<(String, f64), String> {
     let start = Instant::now();
     
-    // Run dig command
-    let output = Command::new("dig")
-        .args(["+short", domain, "A"])
-        .output()
-        .map_err(|e| format!("Failed to run dig: {}", e))?;
+    let mut command = tokio::process::Command::new(program);
+    command.args(args).env(PARSE_LOCALE.0, PARSE_LOCALE.1);
+    let output = raw_log::output_async(&mut command)
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
     
     let lookup_time = start.elapsed().as_secs_f64() * 1000.0;
+    Ok((String::from_utf8_lossy(&output.stdout).to_string(), lookup_time))
+}
+
+/// Resolve with the operating system resolver (addresses only)
+async fn lookup_native(domain: &str) -> Result<DnsLookup, String> {
+    let start = Instant::now();
     
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let resolved_ips: Vec<String> = stdout
-        .lines()
-        .filter(|line| !line.is_empty() && !line.starts_with(';'))
-        .map(|s| s.trim().to_string())
-        .collect();
+    let addrs = tokio::net::lookup_host((domain, 0))
+        .await
+        .map_err(|e| format!("Native resolver failed: {}", e))?;
     
-    // Get TTL
-    let ttl_output = Command::new("dig")
-        .args([domain, "+noall", "+answer"])
-        .output()
-        .ok();
+    let lookup_time = start.elapsed().as_secs_f64() * 1000.0;
     
-    let ttl = ttl_output.and_then(|o| {
-        let s = String::from_utf8_lossy(&o.stdout);
-        // Parse TTL from dig output (4th column)
-        s.lines()
-            .filter(|l| !l.starts_with(';') && !l.is_empty())
-            .next()
-            .and_then(|line| {
-                line.split_whitespace().nth(1).and_then(|t| t.parse().ok())
-            })
-    });
+    let mut resolved_ips: Vec<String> = Vec::new();
+    let mut resolved_ipv6: Vec<String> = Vec::new();
+    for addr in addrs {
+        let ip = addr.ip().to_string();
+        let list = if addr.is_ipv4() { &mut resolved_ips } else { &mut resolved_ipv6 };
+        if !list.contains(&ip) {
+            list.push(ip);
+        }
+    }
     
-    // Get nameservers
-    let ns_output = Command::new("dig")
-        .args([domain, "NS", "+short"])
-        .output()
-        .ok();
+    Ok(DnsLookup {
+        resolved_ips,
+        resolved_ipv6,
+        lookup_time_ms: lookup_time,
+        ..DnsLookup::default()
+    })
+}
+
+/// Resolve with `dig` (A, AAAA, NS and TTL queries run concurrently)
+async fn lookup_dig(domain: &str) -> Result<DnsLookup, String> {
+    let (a, aaaa, ns, answer) = tokio::join!(
+        timed_a_query("dig", ["+short", domain, "A"]),
+        run_dns_query("dig", ["+short", domain, "AAAA"]),
+        run_dns_query("dig", [domain, "NS", "+short"]),
+        run_dns_query("dig", [domain, "+noall", "+answer"]),
+    );
     
-    let nameservers = ns_output.map(|o| {
-        String::from_utf8_lossy(&o.stdout)
-            .lines()
+    let (a_output, lookup_time) = a?;
+    
+    let nameservers = ns.map(|out| {
+        out.lines()
             .filter(|l| !l.is_empty())
             .map(|s| s.trim().to_string())
             .collect::<Vec<_>>()
     });
     
-    // Detect CDN
-    let using_cdn = detect_cdn(&nameservers.clone().unwrap_or_default());
+    Ok(DnsLookup {
+        resolved_ips: parse_ip_lines(&a_output),
+        resolved_ipv6: aaaa.map(|out| parse_ip_lines(&out)).unwrap_or_default(),
+        lookup_time_ms: lookup_time,
+        ttl: answer.and_then(|out| parse_answer_ttl(&out)),
+        nameservers,
+    })
+}
+
+/// Resolve with `nslookup` (no TTL without debug output)
+async fn lookup_nslookup(domain: &str) -> Result<DnsLookup, String> {
+    let (a, aaaa, ns) = tokio::join!(
+        timed_a_query("nslookup", ["-type=A", domain]),
+        run_dns_query("nslookup", ["-type=AAAA", domain]),
+        run_dns_query("nslookup", ["-type=NS", domain]),
+    );
     
-    Ok(DnsResult {
-        domain: domain.to_string(),
+    let (a_output, lookup_time) = a?;
+    
+    let nameservers = ns.map(|out| {
+        out.lines()
+            .filter_map(|l| l.split_once("nameserver = ").map(|(_, ns)| ns.trim().to_string()))
+            .collect::<Vec<_>>()
+    });
+    
+    Ok(DnsLookup {
+        resolved_ips: parse_nslookup_addresses(&a_output, true),
+        resolved_ipv6: aaaa
+            .map(|out| parse_nslookup_addresses(&out, false))
+            .unwrap_or_default(),
+        lookup_time_ms: lookup_time,
+        ttl: None,
+        nameservers,
+    })
+}
+
+/// Parse answer addresses from nslookup output
+/// 
+/// Addresses listed before the "Name:" line belong to the DNS server itself.
+fn parse_nslookup_addresses(output: &str, ipv4: bool) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|l| !l.trim_start().starts_with("Name:"))
+        .filter_map(|l| l.trim().strip_prefix("Address:"))
+        .map(|ip| ip.trim())
+        .filter(|ip| match ip.parse::<std::net::IpAddr>() {
+            Ok(addr) => addr.is_ipv4() == ipv4,
+            Err(_) => false,
+        })
+        .map(|ip| ip.to_string())
+        .collect()
+}
+
+/// Resolve with `host` (A, AAAA, NS and TTL queries run concurrently)
+async fn lookup_host(domain: &str) -> Result<DnsLookup, String> {
+    let (a, aaaa, ns, verbose) = tokio::join!(
+        timed_a_query("host", ["-t", "A", domain]),
+        run_dns_query("host", ["-t", "AAAA", domain]),
+        run_dns_query("host", ["-t", "NS", domain]),
+        // Verbose mode prints a dig-style answer section that carries the TTL
+        run_dns_query("host", ["-v", "-t", "A", domain]),
+    );
+    
+    let (a_output, lookup_time) = a?;
+    
+    let resolved_ips: Vec<String> = a_output
+        .lines()
+        .filter_map(|l| l.split_once(" has address ").map(|(_, ip)| ip.trim().to_string()))
+        .collect();
+    
+    let resolved_ipv6: Vec<String> = aaaa
+        .map(|out| {
+            out.lines()
+                .filter_map(|l| l.split_once(" has IPv6 address ").map(|(_, ip)| ip.trim().to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    let nameservers = ns.map(|out| {
+        out.lines()
+            .filter_map(|l| l.split_once(" name server ").map(|(_, ns)| ns.trim().to_string()))
+            .collect::<Vec<_>>()
+    });
+    
+    Ok(DnsLookup {
         resolved_ips,
+        resolved_ipv6,
         lookup_time_ms: lookup_time,
-        ttl,
+        ttl: verbose.and_then(|out| parse_answer_ttl(&out)),
         nameservers,
-        using_cdn,
     })
 }
 
+/// Keep only lines that are IP addresses (drops CNAME targets from `+short`)
+fn parse_ip_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|s| s.trim())
+        .filter(|line| line.parse::<std::net::IpAddr>().is_ok())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Parse TTL from the first record of a dig-style answer section
+/// ("name TTL IN TYPE data")
+fn parse_answer_ttl(output: &str) -> Option<u32> {
+    output
+        .lines()
+        .filter(|l| !l.starts_with(';') && !l.is_empty())
+        .map(|l| l.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 5 && fields[2] == "IN")
+        .and_then(|fields| fields[1].parse().ok())
+}
+
+/// DNS-over-HTTPS resolvers: (provider, JSON API endpoint)
+const DOH_RESOLVERS: [(&str, &str); 2] = [
+    ("Cloudflare", "https://cloudflare-dns.com/dns-query"),
+    ("Google", "https://dns.google/resolve"),
+];
+
+/// DNS-over-TLS resolvers: (provider, resolver IP)
+const DOT_RESOLVERS: [(&str, &str); 2] = [
+    ("Cloudflare", "1.1.1.1"),
+    ("Google", "8.8.8.8"),
+];
+
+/// Check whether encrypted DNS (DoH and DoT) works on this network
+/// 
+/// DoH uses the resolvers' JSON API through curl. DoT needs `kdig` (knot-dnsutils);
+/// when it is not installed DoT is reported as untested rather than blocked.
+pub async fn check_secure_dns(domain: &str) -> Result<SecureDnsResult, String> {
+    let kdig_available = raw_log::output_async(tokio::process::Command::new("kdig").arg("-V"))
+        .await
+        .is_ok();
+    
+    let doh_probes = DOH_RESOLVERS
+        .iter()
+        .map(|(provider, endpoint)| probe_doh(provider, endpoint, domain));
+    let dot_probes = DOT_RESOLVERS
+        .iter()
+        .filter(|_| kdig_available)
+        .map(|(provider, server)| probe_dot(provider, server, domain));
+    
+    let (doh, dot) = tokio::join!(
+        futures::future::join_all(doh_probes),
+        futures::future::join_all(dot_probes)
+    );
+    
+    let doh_available = doh.iter().any(|p| p.success);
+    let dot_available = if kdig_available {
+        Some(dot.iter().any(|p| p.success))
+    } else {
+        None
+    };
+    
+    Ok(SecureDnsResult {
+        probes: doh.into_iter().chain(dot).collect(),
+        doh_available,
+        dot_available,
+    })
+}
+
+/// Resolve the A record through a DoH JSON endpoint
+async fn probe_doh(provider: &str, endpoint: &str, domain: &str) -> SecureDnsProbe {
+    let query_url = format!("{}?name={}&type=A", endpoint, domain);
+    let start = Instant::now();
+    
+    let output = raw_log::output_async(
+        tokio::process::Command::new("curl")
+            .args([
+                "-s",
+                "--connect-timeout", "5",
+                "--max-time", "8",
+                "-H", "accept: application/dns-json",
+                &query_url,
+            ]),
+    )
+    .await;
+    
+    let time_ms = start.elapsed().as_secs_f64() * 1000.0;
+    
+    let result = match output {
+        Ok(output) if output.status.success() => {
+            parse_doh_answer(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => Err(curl_error_reason(output.status.code().unwrap_or(-1))),
+        Err(e) => Err(format!("Failed to run curl: {}", e)),
+    };
+    
+    secure_dns_probe(provider, SecureDnsProtocol::Doh, endpoint, time_ms, result)
+}
+
+/// Extract A records from a DoH JSON response
+fn parse_doh_answer(body: &str) -> Result<Vec<String>, String> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|_| "Invalid DoH response".to_string())?;
+    
+    let ips = json["Answer"]
+        .as_array()
+        .map(|answers| {
+            answers
+                .iter()
+                .filter(|a| a["type"].as_u64() == Some(1))
+                .filter_map(|a| a["data"].as_str().map(|d| d.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    Ok(ips)
+}
+
+/// Resolve the A record over DNS-over-TLS with kdig
+async fn probe_dot(provider: &str, server: &str, domain: &str) -> SecureDnsProbe {
+    let at_server = format!("@{}", server);
+    let start = Instant::now();
+    
+    let output = raw_log::output_async(
+        tokio::process::Command::new("kdig")
+            .args([at_server.as_str(), "+tls", "+short", "+timeout=5", "+retry=0", domain, "A"]),
+    )
+    .await;
+    
+    let time_ms = start.elapsed().as_secs_f64() * 1000.0;
+    
+    let result = match output {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if stdout.contains(";;") {
+                // kdig prints ";; WARNING: ..." when the TLS session fails
+                Err("TLS connection to port 853 failed".to_string())
+            } else {
+                Ok(parse_ip_lines(&stdout))
+            }
+        }
+        Ok(_) => Err("TLS connection to port 853 failed".to_string()),
+        Err(e) => Err(format!("Failed to run kdig: {}", e)),
+    };
+    
+    secure_dns_probe(provider, SecureDnsProtocol::Dot, server, time_ms, result)
+}
+
+/// Build a probe result; an empty answer counts as a working resolver
+fn secure_dns_probe(
+    provider: &str,
+    protocol: SecureDnsProtocol,
+    endpoint: &str,
+    time_ms: f64,
+    result: Result<Vec<String>, String>,
+) -> SecureDnsProbe {
+    let (success, resolved_ips, error) = match result {
+        Ok(ips) => (true, ips, None),
+        Err(e) => (false, Vec::new(), Some(e)),
+    };
+    
+    SecureDnsProbe {
+        provider: provider.to_string(),
+        protocol,
+        endpoint: endpoint.to_string(),
+        success,
+        resolved_ips,
+        time_ms,
+        error,
+    }
+}
+
 /// Detect CDN from nameservers
 fn detect_cdn(nameservers: &[String]) -> Option<String> {
     let ns_str = nameservers.join(" ").to_lowercase();
@@ -111,63 +826,377 @@ fn detect_cdn(nameservers: &[String]) -> Option<String> {
     }
 }
 
+/// curl `-w` template producing the JSON parsed by `parse_curl_timing`
+const CURL_TIMING_FORMAT: &str = r#"{"dns": %{time_namelookup}, "connect": %{time_connect}, "ssl": %{time_appconnect}, "ttfb": %{time_starttransfer}, "total": %{time_total}, "http_code": "%{http_code}", "speed": %{speed_download}, "remote_ip": "%{remote_ip}"}"#;
+
 /// Run TCP/HTTP connection timing diagnostic
-pub async fn check_tcp_timing(url: &str) -> Result<TcpResult, String> {
-    let curl_format = r#"{"dns": %{time_namelookup}, "connect": %{time_connect}, "ssl": %{time_appconnect}, "ttfb": %{time_starttransfer}, "total": %{time_total}, "http_code": "%{http_code}", "speed": %{speed_download}}"#;
-    
-    let output = Command::new("curl")
-        .args([
-            "-o", "/dev/null",
-            "-s",
-            "-w", curl_format,
-            "--connect-timeout", "10",
-            "--max-time", "30",
-            "-L",  // Follow redirects
-            url,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run curl: {}", e))?;
+pub async fn check_tcp_timing(
+    url: &str,
+    binding: &SourceBinding,
+    target_override: Option<&TargetOverride>,
+) -> Result<TcpResult, String> {
+    let output = raw_log::output_async(
+        tokio::process::Command::new("curl")
+            .args([
+                "-o", "/dev/null",
+                "-s",
+                "-D", "-",  // Response headers to stdout, before the -w output
+                "-w", CURL_TIMING_FORMAT,
+                "--connect-timeout", "10",
+                "--max-time", "30",
+                "-L",  // Follow redirects
+                url,
+            ])
+            .args(target_override.map(TargetOverride::curl_args).unwrap_or_default())
+            .args(binding.curl_args())
+            .kill_on_drop(true),
+    )
+    .await
+    .map_err(|e| format!("Failed to run curl: {}", e))?;
     
     let stdout = String::from_utf8_lossy(&output.stdout);
     
-    // Parse JSON output
-    let json: serde_json::Value = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse curl output: {} - Raw: {}", e, stdout))?;
+    // The -w JSON is the last line; everything before it is the header dump
+    let (headers, timing) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
     
-    Ok(TcpResult {
-        dns_time_ms: json["dns"].as_f64().unwrap_or(0.0) * 1000.0,
-        connect_time_ms: json["connect"].as_f64().unwrap_or(0.0) * 1000.0,
-        ssl_time_ms: json["ssl"].as_f64().unwrap_or(0.0) * 1000.0,
-        ttfb_ms: json["ttfb"].as_f64().unwrap_or(0.0) * 1000.0,
-        total_time_ms: json["total"].as_f64().unwrap_or(0.0) * 1000.0,
-        http_code: json["http_code"]
-            .as_str()
-            .and_then(|s| s.parse::<u16>().ok())
-            .unwrap_or(0),
-        download_speed_kbps: json["speed"].as_f64().unwrap_or(0.0) / 1024.0,
-    })
-}
+    let mut result = parse_curl_timing(output.status.code(), timing);
+    if result.http_code != 0 {
+        result.clock_skew_secs = parse_date_header(headers)
+            .map(|server_now| (chrono::Utc::now() - server_now).num_seconds());
+        result.security_headers = Some(parse_security_headers(headers));
+        let chain = parse_redirect_chain(url, headers);
+        if chain.len() > 1 {
+            result.redirect_chain = chain;
+        }
+    }
+    Ok(result)
+}
+
+/// URLs visited while following redirects, starting with `url`
+/// 
+/// Built from the `Location` header of each 3xx block in a `curl -D -L` dump;
+/// relative locations are resolved against the URL that returned them.
+fn parse_redirect_chain(url: &str, dump: &str) -> Vec<String> {
+    let mut chain = vec![url.to_string()];
+    
+    let redirects = dump.split("\r\n\r\n").filter(|block| {
+        let status = block.split_whitespace().nth(1).unwrap_or("");
+        block.trim_start().starts_with("HTTP/") && status.starts_with('3')
+    });
+    for block in redirects {
+        let location = block.lines().skip(1).find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim().eq_ignore_ascii_case("location").then(|| value.trim())
+        });
+        let Some(location) = location.filter(|l| !l.is_empty()) else {
+            continue;
+        };
+        
+        let current = chain.last().map(String::as_str).unwrap_or(url);
+        let next = Url::parse(current)
+            .and_then(|base| base.join(location))
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| location.to_string());
+        chain.push(next);
+    }
+    
+    chain
+}
+
+/// Header block of the final response in a `curl -D` dump
+/// 
+/// With `-L` the dump holds one header block per redirect; only the last
+/// block describes the page actually served.
+fn final_header_block(dump: &str) -> &str {
+    dump.split("\r\n\r\n")
+        .filter(|block| block.trim_start().starts_with("HTTP/"))
+        .last()
+        .unwrap_or("")
+}
+
+/// Server time from the `Date` header of the final response
+fn parse_date_header(dump: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    final_header_block(dump).lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("date") {
+            return None;
+        }
+        chrono::DateTime::parse_from_rfc2822(value.trim())
+            .ok()
+            .map(|t| t.with_timezone(&chrono::Utc))
+    })
+}
+
+/// Extract security headers from the final response of a `curl -D` dump
+fn parse_security_headers(dump: &str) -> SecurityHeaders {
+    let mut headers = SecurityHeaders::default();
+    for line in final_header_block(dump).lines().skip(1) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match name.trim().to_lowercase().as_str() {
+            "strict-transport-security" => headers.hsts = value,
+            "content-security-policy" => headers.content_security_policy = value,
+            "x-frame-options" => headers.x_frame_options = value,
+            "x-content-type-options" => headers.x_content_type_options = value,
+            _ => {}
+        }
+    }
+    headers
+}
+
+/// Run the TCP timing check once per resolved IP
+/// 
+/// Each request is pinned with curl's `--resolve host:port:ip`, so SNI and the
+/// Host header stay correct while the connection goes to that specific backend.
+/// Redirects on the same host stay pinned to it.
+pub async fn check_tcp_timing_per_ip(
+    url: &str,
+    ips: &[String],
+    binding: &SourceBinding,
+) -> Result<BTreeMap<String, TcpResult>, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let host = parsed.host_str().ok_or("No host in URL")?.to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    
+    let probes = ips.iter().map(|ip| {
+        let host = host.clone();
+        async move {
+            let address = if ip.contains(':') { format!("[{}]", ip) } else { ip.clone() };
+            let resolve = format!("{}:{}:{}", host, port, address);
+            
+            let output = raw_log::output_async(
+                tokio::process::Command::new("curl")
+                    .args([
+                        "-o", "/dev/null",
+                        "-s",
+                        "-w", CURL_TIMING_FORMAT,
+                        "-L",  // Follow redirects, like the main TCP check
+                        "--connect-timeout", "10",
+                        "--max-time", "30",
+                        "--resolve", &resolve,
+                        url,
+                    ])
+                    .args(binding.curl_args()),
+            )
+            .await;
+            
+            let result = match output {
+                Ok(output) => {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    parse_curl_timing(output.status.code(), &stdout)
+                }
+                Err(e) => TcpResult::failed(format!("Failed to run curl: {}", e)),
+            };
+            (ip.clone(), result)
+        }
+    });
+    
+    Ok(futures::future::join_all(probes).await.into_iter().collect())
+}
+
+/// Build a `TcpResult` from curl's exit code and `-w` JSON output
+/// 
+/// When curl exits with an error its `-w` output may be zeros or malformed,
+/// and a successful run can still print something unreadable; either way a
+/// result with `http_code: 0` and a readable reason is returned instead of
+/// a JSON parse error.
+fn parse_curl_timing(exit_code: Option<i32>, stdout: &str) -> TcpResult {
+    let failure_reason = exit_code.filter(|c| *c != 0).map(curl_error_reason);
+    
+    // Parse JSON output
+    let json: serde_json::Value = match serde_json::from_str(stdout) {
+        Ok(json) => json,
+        Err(_) => {
+            return TcpResult::failed(failure_reason.unwrap_or_else(|| {
+                "curl không trả về số liệu đo thời gian hợp lệ".to_string()
+            }));
+        }
+    };
+    
+    TcpResult {
+        dns_time_ms: json["dns"].as_f64().unwrap_or(0.0) * 1000.0,
+        connect_time_ms: json["connect"].as_f64().unwrap_or(0.0) * 1000.0,
+        ssl_time_ms: json["ssl"].as_f64().unwrap_or(0.0) * 1000.0,
+        ttfb_ms: json["ttfb"].as_f64().unwrap_or(0.0) * 1000.0,
+        total_time_ms: json["total"].as_f64().unwrap_or(0.0) * 1000.0,
+        http_code: json["http_code"]
+            .as_str()
+            .and_then(|s| s.parse::<u16>().ok())
+            .unwrap_or(0),
+        download_speed_kbps: json["speed"].as_f64().unwrap_or(0.0) / 1024.0,
+        failure_reason,
+        security_headers: None,
+        remote_ip: json["remote_ip"]
+            .as_str()
+            .filter(|ip| !ip.is_empty())
+            .map(|ip| ip.to_string()),
+        redirect_chain: Vec::new(),
+        clock_skew_secs: None,
+    }
+}
+
+/// Human-readable reason for a curl exit code
+fn curl_error_reason(code: i32) -> String {
+    match code {
+        6 => "Không phân giải được host (curl 6)".to_string(),
+        7 => "Không thể kết nối đến server (curl 7)".to_string(),
+        28 => "Hết thời gian chờ kết nối/phản hồi (curl 28)".to_string(),
+        35 => "Lỗi SSL handshake (curl 35)".to_string(),
+        52 => "Server không trả về dữ liệu (curl 52)".to_string(),
+        56 => "Kết nối bị reset khi nhận dữ liệu (curl 56)".to_string(),
+        60 => "Certificate của server không hợp lệ (curl 60)".to_string(),
+        _ => format!("curl thất bại với mã lỗi {}", code),
+    }
+}
 
 /// Run routing/traceroute diagnostic
-pub async fn check_routing(domain: &str, target_ip: &str) -> Result<RoutingResult, String> {
+/// 
+/// Traces `target_ip` itself (IPv4 or IPv6) so the route matches the address
+/// that was actually connected to; the domain is only used when no IP is known.
+pub async fn check_routing(domain: &str, target_ip: &str, binding: &SourceBinding) -> Result<RoutingResult, String> {
     let start = Instant::now();
     
-    // Try traceroute with timeout
-    let output = Command::new("traceroute")
-        .args(["-n", "-m", "15", "-w", "1", "-q", "1", domain])
-        .output()
-        .map_err(|e| format!("Failed to run traceroute: {}", e))?;
+    let destination = if target_ip.is_empty() { domain } else { target_ip };
+    // macOS ships a separate binary for IPv6; Linux traceroute handles both
+    let program = if cfg!(target_os = "macos") && destination.contains(':') {
+        "traceroute6"
+    } else {
+        "traceroute"
+    };
+    
+    let run = |method_args: &'static [&'static str]| {
+        let mut command = tokio::process::Command::new(program);
+        command
+            .args(["-n", "-m", &MAX_TRACE_HOPS.to_string(), "-w", "1", "-q", "1"])
+            .args(method_args)
+            .args(binding.traceroute_args())
+            .arg(destination)
+            .env(PARSE_LOCALE.0, PARSE_LOCALE.1)
+            .kill_on_drop(true);
+        async move {
+            raw_log::output_async(&mut command)
+                .await
+                .map_err(|e| format!("Failed to run traceroute: {}", e))
+        }
+    };
+    
+    let output = run(&[]).await?;
+    let mut hops = parse_traceroute_hops(&String::from_utf8_lossy(&output.stdout));
+    let mut stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    
+    // The default probe method may need raw sockets; retry once with the other protocol
+    if hops.is_empty() {
+        let output = run(TRACEROUTE_FALLBACK_ARGS).await?;
+        hops = parse_traceroute_hops(&String::from_utf8_lossy(&output.stdout));
+        stderr.push_str(&String::from_utf8_lossy(&output.stderr));
+    }
     
     let total_time = start.elapsed().as_secs_f64() * 1000.0;
-    let stdout = String::from_utf8_lossy(&output.stdout);
     
-    // Parse traceroute output
-    let hop_regex = Regex::new(r"^\s*(\d+)\s+(?:(\d+\.\d+\.\d+\.\d+)|(\*))\s+(?:(\d+\.?\d*)\s*ms)?")
+    // Empty output alone may be a rejected flag or an unreachable network
+    let needs_privileges = hops.is_empty() && is_privilege_error(&stderr);
+    if hops.is_empty() && !needs_privileges {
+        return Err(format!("traceroute returned no hops: {}", stderr.trim()));
+    }
+    
+    Ok(routing_from_hops(target_ip, hops, total_time, needs_privileges))
+}
+
+/// Assemble the routing result from parsed hops
+/// 
+/// A looping trace is cut after the first repeat, and the hop count and RTT
+/// statistics describe the hops that are kept.
+fn routing_from_hops(
+    target_ip: &str,
+    mut hops: Vec<RouteHop>,
+    total_time_ms: f64,
+    needs_privileges: bool,
+) -> RoutingResult {
+    let hit_hop_limit = hops.len() as u32 >= MAX_TRACE_HOPS;
+    let destination_reached = destination_reached(&hops, target_ip);
+    
+    // A trace that got through has no loop, whatever repeats along the way.
+    // Otherwise, past the first repeat it only cycles through the same routers
+    let loop_ips = if destination_reached == Some(true) {
+        Vec::new()
+    } else {
+        RoutingResult::repeated_ips(&hops)
+    };
+    if let Some(first_loop_ip) = loop_ips.first() {
+        if let Some(i) = RoutingResult::cycle_repeat_index(&hops, first_loop_ip) {
+            hops.truncate(i + 1);
+        }
+    }
+    
+    let total_hops = hops.len() as u32;
+    let rtt_series = RoutingResult::hop_rtt_series(&hops);
+    let avg_hop_latency_ms = RoutingResult::average_hop_rtt(&hops);
+    let max_hop_latency_ms = RoutingResult::max_hop_rtt(&hops);
+    
+    RoutingResult {
+        target_ip: target_ip.to_string(),
+        hops,
+        total_hops,
+        total_time_ms,
+        rtt_series,
+        avg_hop_latency_ms,
+        max_hop_latency_ms,
+        needs_privileges,
+        destination_reached,
+        hit_hop_limit,
+        loop_ips,
+        public_ip: None,
+    }
+}
+
+/// Whether any hop answered from `target_ip`, `None` when that can't be told
+fn destination_reached(hops: &[RouteHop], target_ip: &str) -> Option<bool> {
+    let target = target_ip.parse::<std::net::IpAddr>().ok()?;
+    if hops.is_empty() {
+        return None;
+    }
+    // Compare parsed addresses so IPv6 spelling differences don't matter
+    Some(hops.iter().any(|h| h.ip_address.parse::<std::net::IpAddr>().ok() == Some(target)))
+}
+
+/// TTL limit passed to traceroute
+pub const MAX_TRACE_HOPS: u32 = 15;
+
+/// traceroute flags for the retry, using a different protocol than the default run
+/// (Linux already defaults to UDP; ICMP echo works unprivileged via `ping_group_range`)
+#[cfg(target_os = "macos")]
+const TRACEROUTE_FALLBACK_ARGS: &[&str] = &["-P", "udp"];
+#[cfg(not(target_os = "macos"))]
+const TRACEROUTE_FALLBACK_ARGS: &[&str] = &["-I"];
+
+/// Whether traceroute's stderr says it lacks privileges
+fn is_privilege_error(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    [
+        "operation not permitted",
+        "permission denied",
+        "enough privileges",
+        "must be root",
+        "requires root",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
+/// Parse hop lines from traceroute output
+fn parse_traceroute_hops(stdout: &str) -> Vec<RouteHop> {
+    // RTT accepts a decimal comma and any unit spelling for localized builds
+    let hop_regex = Regex::new(r"^\s*(\d+)\s+(?:(\d+\.\d+\.\d+\.\d+|[0-9a-fA-F]*:[0-9a-fA-F:.]+)|(\*))(?:\s+(\d+(?:[.,]\d+)?))?")
         .unwrap();
     
     let mut hops: Vec<RouteHop> = Vec::new();
     
-    for line in stdout.lines().skip(1) {  // Skip header
+    // No blind header skip: the header ("traceroute to ...", possibly translated)
+    // never starts with a hop number, and macOS prints it on stderr instead, so
+    // dropping the first stdout line would lose hop 1 there
+    for line in stdout.lines() {
         if let Some(caps) = hop_regex.captures(line) {
             let hop_number: u32 = caps.get(1)
                 .and_then(|m| m.as_str().parse().ok())
@@ -177,107 +1206,1536 @@ pub async fn check_routing(domain: &str, target_ip: &str) -> Result<RoutingResul
                 .map(|m| m.as_str().to_string())
                 .unwrap_or_else(|| "*".to_string());
             
-            let rtt_ms: f64 = caps.get(4)
-                .and_then(|m| m.as_str().parse().ok())
-                .unwrap_or(0.0);
-            
             let is_timeout = ip_address == "*";
             
+            let rtt_ms_opt: Option<f64> = if is_timeout {
+                None
+            } else {
+                caps.get(4).and_then(|m| m.as_str().replace(',', ".").parse().ok())
+            };
+            
             hops.push(RouteHop {
                 hop_number,
                 ip_address,
                 hostname: None,
-                rtt_ms,
+                rtt_ms: rtt_ms_opt.unwrap_or(0.0),
+                rtt_ms_opt,
                 packet_loss_percent: if is_timeout { 100.0 } else { 0.0 },
             });
         }
     }
-    
-    let total_hops = hops.len() as u32;
-    
-    Ok(RoutingResult {
-        target_ip: target_ip.to_string(),
-        hops,
-        total_hops,
-        total_time_ms: total_time,
-    })
+    
+    hops
+}
+
+/// How the stability probes are run
+#[derive(Debug, Clone)]
+pub struct StabilityOptions {
+    pub num_tests: u32,
+    /// Pause between probes
+    pub delay: Duration,
+    /// Send one extra request first, excluded from the statistics
+    pub warm_up: bool,
+    /// Resolve the domain again before each probe and pin the probe to the
+    /// result, so DNS-based failover shows up as IP changes (HTTP only)
+    pub reresolve: bool,
+}
+
+/// Run connection stability test
+/// 
+/// With a `target_override` every probe goes to the override address, and
+/// re-resolving is skipped. `on_probe` is called after each counted probe
+/// with (probe number, successes so far, RTT if it succeeded).
+pub async fn check_stability<F>(
+    domain: &str,
+    options: &StabilityOptions,
+    binding: &SourceBinding,
+    target_override: Option<&TargetOverride>,
+    on_probe: F,
+) -> Result<StabilityResult, String>
+where
+    F: Fn(u32, u32, Option<f64>),
+{
+    let StabilityOptions { num_tests, delay, warm_up, reresolve } = *options;
+    let reresolve = reresolve && target_override.is_none();
+    let override_args = target_override.map(TargetOverride::curl_args).unwrap_or_default();
+    let url = format!("https://{}", domain);
+    let mut outcomes: Vec<ProbeOutcome> = Vec::new();
+    let mut times: Vec<f64> = Vec::new();
+    let mut seen_ips: Vec<String> = Vec::new();
+    let mut last_ips: Option<Vec<String>> = None;
+    let mut ip_changes = 0;
+    
+    if warm_up {
+        http_probe(&url, &override_args, binding).await;
+        tokio::time::sleep(delay).await;
+    }
+    
+    for probe in 1..=num_tests {
+        let mut pin_args = override_args.clone();
+        if reresolve {
+            if let Ok(lookup) = lookup_native(domain).await {
+                // Compare the sorted set: round-robin reordering is not a change
+                let mut ips = lookup.resolved_ips;
+                ips.sort();
+                if last_ips.as_ref().is_some_and(|last| *last != ips) {
+                    ip_changes += 1;
+                }
+                for ip in &ips {
+                    if !seen_ips.contains(ip) {
+                        seen_ips.push(ip.clone());
+                    }
+                }
+                if let Some(ip) = ips.first() {
+                    pin_args = vec!["--resolve".to_string(), format!("{}:443:{}", domain, ip)];
+                }
+                last_ips = Some(ips);
+            }
+        }
+        
+        let (outcome, elapsed) = http_probe(&url, &pin_args, binding).await;
+        let rtt = (outcome == ProbeOutcome::Success).then_some(elapsed);
+        if let Some(rtt) = rtt {
+            times.push(rtt);
+        }
+        outcomes.push(outcome);
+        on_probe(probe, times.len() as u32, rtt);
+        
+        tokio::time::sleep(delay).await;
+    }
+    
+    Ok(StabilityResult {
+        ip_changes,
+        seen_ips,
+        ..stability_from_probes(&outcomes, &times)
+    })
+}
+
+/// How one stability probe ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProbeOutcome {
+    Success,
+    /// A response arrived, but not a 2xx/3xx
+    HttpError,
+    /// Connect or total time limit hit
+    Timeout,
+    /// Refused, reset, DNS or TLS failure
+    ConnectionError,
+}
+
+/// Time one HTTP request and classify how it ended
+/// 
+/// `pin_args` are curl `--resolve`/`--connect-to` flags pinning the request.
+async fn http_probe(url: &str, pin_args: &[String], binding: &SourceBinding) -> (ProbeOutcome, f64) {
+    let start = Instant::now();
+    
+    let output = raw_log::output_async(
+        tokio::process::Command::new("curl")
+            .args([
+                "-o", "/dev/null",
+                "-s",
+                "--connect-timeout", "5",
+                "--max-time", "10",
+                "-w", "%{http_code}",
+                url,
+            ])
+            .args(pin_args)
+            .args(binding.curl_args())
+            .kill_on_drop(true),
+    )
+    .await;
+    
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    let outcome = match output {
+        Ok(output) => classify_http_probe(output.status.code(), &String::from_utf8_lossy(&output.stdout)),
+        Err(_) => ProbeOutcome::ConnectionError,
+    };
+    
+    (outcome, elapsed)
+}
+
+/// Classify a probe from curl's exit code and `%{http_code}` output
+fn classify_http_probe(exit_code: Option<i32>, http_code: &str) -> ProbeOutcome {
+    match exit_code {
+        Some(0) if http_code.starts_with('2') || http_code.starts_with('3') => ProbeOutcome::Success,
+        Some(0) => ProbeOutcome::HttpError,
+        Some(28) => ProbeOutcome::Timeout,
+        _ => ProbeOutcome::ConnectionError,
+    }
+}
+
+/// Run connection stability test with raw TCP connects (no HTTP)
+/// 
+/// `on_probe` is called like in `check_stability`.
+pub async fn check_connect_stability<F>(
+    host: &str,
+    port: u16,
+    options: &StabilityOptions,
+    binding: &SourceBinding,
+    on_probe: F,
+) -> Result<StabilityResult, String>
+where
+    F: Fn(u32, u32, Option<f64>),
+{
+    let StabilityOptions { num_tests, delay, warm_up, .. } = *options;
+    let addr = resolve_socket_addr(host, port).await?;
+    let mut outcomes: Vec<ProbeOutcome> = Vec::new();
+    let mut times: Vec<f64> = Vec::new();
+    
+    if warm_up {
+        connect_probe(addr, binding).await;
+        tokio::time::sleep(delay).await;
+    }
+    
+    for probe in 1..=num_tests {
+        let (outcome, elapsed) = connect_probe(addr, binding).await;
+        let rtt = (outcome == ProbeOutcome::Success).then_some(elapsed);
+        if let Some(rtt) = rtt {
+            times.push(rtt);
+        }
+        outcomes.push(outcome);
+        on_probe(probe, times.len() as u32, rtt);
+        
+        tokio::time::sleep(delay).await;
+    }
+    
+    Ok(stability_from_probes(&outcomes, &times))
+}
+
+/// Time one TCP connect and classify how it ended (never an HTTP error)
+async fn connect_probe(addr: std::net::SocketAddr, binding: &SourceBinding) -> (ProbeOutcome, f64) {
+    let start = Instant::now();
+    
+    let outcome = match tokio::time::timeout(Duration::from_secs(5), binding.connect(addr)).await {
+        Ok(Ok(_)) => ProbeOutcome::Success,
+        Ok(Err(_)) => ProbeOutcome::ConnectionError,
+        Err(_) => ProbeOutcome::Timeout,
+    };
+    
+    (outcome, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// Compute stability statistics from the probe outcomes and successful probe times
+fn stability_from_probes(outcomes: &[ProbeOutcome], times: &[f64]) -> StabilityResult {
+    let count = |kind: ProbeOutcome| outcomes.iter().filter(|&&o| o == kind).count() as u32;
+    let num_tests = outcomes.len() as u32;
+    let successful = count(ProbeOutcome::Success);
+    let success_rate = (successful as f64 / num_tests.max(1) as f64) * 100.0;
+    
+    let (min_time, avg_time, max_time, jitter) = if !times.is_empty() {
+        let min = times.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = times.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let avg = times.iter().sum::<f64>() / times.len() as f64;
+        
+        // Calculate jitter (average deviation from mean)
+        let jitter = times.iter()
+            .map(|t| (t - avg).abs())
+            .sum::<f64>() / times.len() as f64;
+        
+        (min, avg, max, jitter)
+    } else {
+        (0.0, 0.0, 0.0, 0.0)
+    };
+    
+    StabilityResult {
+        total_tests: num_tests,
+        successful_tests: successful,
+        success_rate,
+        min_time_ms: min_time,
+        avg_time_ms: avg_time,
+        max_time_ms: max_time,
+        jitter_ms: jitter,
+        http_errors: count(ProbeOutcome::HttpError),
+        timeouts: count(ProbeOutcome::Timeout),
+        connection_errors: count(ProbeOutcome::ConnectionError),
+        ip_changes: 0,
+        seen_ips: Vec::new(),
+    }
+}
+
+/// Payload sizes for the latency-by-size sweep (1400 stays under a 1500 MTU)
+const PING_SWEEP_SIZES: [u32; 3] = [64, 512, 1400];
+
+/// Echo requests sent per payload size
+const PING_SWEEP_COUNT: u32 = 4;
+
+/// ICMP payload that fills a 1500-byte packet (20 IP + 8 ICMP header bytes)
+const FULL_MTU_PAYLOAD: u32 = 1472;
+
+/// ping flags that forbid fragmentation
+#[cfg(target_os = "macos")]
+const DONT_FRAGMENT_ARGS: &[&str] = &["-D"];
+#[cfg(not(target_os = "macos"))]
+const DONT_FRAGMENT_ARGS: &[&str] = &["-M", "do"];
+
+/// Ping a host with a given payload size
+/// 
+/// With `dont_fragment` a packet larger than the path MTU is dropped (or
+/// refused locally) instead of being split.
+pub async fn ping(
+    target: &str,
+    payload_bytes: u32,
+    count: u32,
+    dont_fragment: bool,
+    binding: &SourceBinding,
+) -> Result<PingSizeSample, String> {
+    let size = payload_bytes.to_string();
+    let count_arg = count.to_string();
+    
+    let mut command = tokio::process::Command::new("ping");
+    command.args(["-n", "-c", &count_arg, "-i", "0.5", "-s", &size]);
+    if dont_fragment {
+        command.args(DONT_FRAGMENT_ARGS);
+    }
+    let output = raw_log::output_async(
+        command
+            .args(binding.ping_args())
+            .arg(target)
+            .env(PARSE_LOCALE.0, PARSE_LOCALE.1)
+            .kill_on_drop(true),
+    )
+    .await
+    .map_err(|e| format!("Failed to run ping: {}", e))?;
+    
+    let mut sample = parse_ping_output(
+        &String::from_utf8_lossy(&output.stdout),
+        &String::from_utf8_lossy(&output.stderr),
+        payload_bytes,
+    )?;
+    sample.dont_fragment = dont_fragment;
+    Ok(sample)
+}
+
+/// Parse the summary lines shared by Linux and macOS ping
+/// ("x packets transmitted, y received" and "min/avg/max/... = a/b/c/d ms")
+/// 
+/// A "message too long" error counts as total loss even if ping gave up
+/// before printing a summary.
+fn parse_ping_output(stdout: &str, stderr: &str, payload_bytes: u32) -> Result<PingSizeSample, String> {
+    let loss_regex = Regex::new(r"(\d+) packets transmitted, (\d+) (?:packets )?received").unwrap();
+    let rtt_regex = Regex::new(r"= ([\d.]+)/([\d.]+)/([\d.]+)/[\d.]+ ms").unwrap();
+    
+    let too_large = [stdout, stderr]
+        .iter()
+        .any(|text| text.to_lowercase().contains("message too long"));
+    
+    let counts = loss_regex
+        .captures(stdout)
+        .and_then(|caps| Some((caps[1].parse::<u32>().ok()?, caps[2].parse::<u32>().ok()?)));
+    let (transmitted, received) = match counts {
+        Some(counts) => counts,
+        None if too_large => (0, 0),
+        None => return Err(format!("Unexpected ping output: {}", stderr.trim())),
+    };
+    
+    let rtt = rtt_regex.captures(stdout);
+    let rtt_at = |idx: usize| rtt.as_ref().and_then(|caps| caps[idx].parse::<f64>().ok());
+    
+    Ok(PingSizeSample {
+        payload_bytes,
+        min_rtt_ms: rtt_at(1),
+        avg_rtt_ms: rtt_at(2),
+        max_rtt_ms: rtt_at(3),
+        packet_loss_percent: if transmitted == 0 {
+            100.0
+        } else {
+            (transmitted - received.min(transmitted)) as f64 / transmitted as f64 * 100.0
+        },
+        dont_fragment: false,
+        too_large,
+    })
+}
+
+/// Profile latency across payload sizes (64, 512, 1400 bytes), then probe
+/// a full 1500-byte packet with fragmentation forbidden
+/// 
+/// Sizes are pinged one after another so they don't compete for the link.
+pub async fn check_latency_by_size(target_ip: &str, binding: &SourceBinding) -> Result<LatencyBySizeResult, String> {
+    let mut samples: Vec<PingSizeSample> = Vec::new();
+    
+    for size in PING_SWEEP_SIZES {
+        samples.push(ping(target_ip, size, PING_SWEEP_COUNT, false, binding).await?);
+    }
+    
+    // Older pings may not know the don't-fragment flag; the sweep still stands
+    let mtu_probe = ping(target_ip, FULL_MTU_PAYLOAD, PING_SWEEP_COUNT, true, binding)
+        .await
+        .ok();
+    
+    Ok(LatencyBySizeResult {
+        target_ip: target_ip.to_string(),
+        samples,
+        mtu_probe,
+    })
+}
+
+/// Small pings get through but a full 1500-byte packet with don't-fragment
+/// set does not, so the path MTU is below 1500
+fn full_mtu_blocked(sweep: &LatencyBySizeResult) -> bool {
+    let small_ok = sweep.samples.first().is_some_and(|s| s.packet_loss_percent < 100.0);
+    let probe_failed = sweep
+        .mtu_probe
+        .as_ref()
+        .is_some_and(|p| p.too_large || p.packet_loss_percent >= 100.0);
+    small_ok && probe_failed
+}
+
+/// Extract the server certificate chain and verify it is complete
+/// 
+/// Uses `openssl s_client -showcerts`: every certificate's issuer must be the
+/// subject of the next one, and openssl must not report a missing issuer.
+/// 
+/// `connect_host` is where to connect (differs from `host` with a target override).
+pub async fn check_ssl_cert(host: &str, port: u16, connect_host: &str) -> Result<SslCertInfo, String> {
+    let connect = if connect_host.contains(':') {
+        format!("[{}]:{}", connect_host, port)
+    } else {
+        format!("{}:{}", connect_host, port)
+    };
+    
+    let output = raw_log::output_async(
+        tokio::process::Command::new("openssl")
+            .args(["s_client", "-connect", &connect, "-servername", host, "-showcerts"])
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true),
+    )
+    .await
+    .map_err(|e| format!("Failed to run openssl: {}", e))?;
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut cert = parse_ssl_chain(&stdout)?;
+    if let Some((not_before, not_after)) = leaf_cert_validity(&stdout).await {
+        cert.not_before = Some(not_before);
+        cert.not_after = Some(not_after);
+    }
+    Ok(cert)
+}
+
+/// Validity window of the first (leaf) PEM certificate in `s_client` output
+async fn leaf_cert_validity(
+    showcerts: &str,
+) -> Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
+    const PEM_END: &str = "-----END CERTIFICATE-----";
+    let start = showcerts.find("-----BEGIN CERTIFICATE-----")?;
+    let end = start + showcerts[start..].find(PEM_END)? + PEM_END.len();
+    
+    let mut command = tokio::process::Command::new("openssl");
+    command
+        .args(["x509", "-noout", "-dates"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command.spawn().ok()?;
+    {
+        use tokio::io::AsyncWriteExt;
+        let mut stdin = child.stdin.take()?;
+        stdin.write_all(&showcerts.as_bytes()[start..end]).await.ok()?;
+    }
+    let output = child.wait_with_output().await.ok()?;
+    raw_log::record(command.as_std(), &output);
+    
+    parse_cert_dates(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `openssl x509 -dates` output ("notBefore=Jan  1 00:00:00 2024 GMT")
+fn parse_cert_dates(output: &str) -> Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
+    let parse = |key: &str| {
+        let value = output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(key))?;
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        chrono::NaiveDateTime::parse_from_str(&value, "%b %d %H:%M:%S %Y GMT")
+            .ok()
+            .map(|t| t.and_utc())
+    };
+    Some((parse("notBefore=")?, parse("notAfter=")?))
+}
+
+/// Parse `openssl s_client -showcerts` output into certificate info
+fn parse_ssl_chain(output: &str) -> Result<SslCertInfo, String> {
+    let subject_regex = Regex::new(r"^\s*\d+\s+s:\s*(.*)$").unwrap();
+    let issuer_regex = Regex::new(r"^\s*i:\s*(.*)$").unwrap();
+    let verify_regex = Regex::new(r"Verify return code:\s*(\d+)\s*\(([^)]*)\)").unwrap();
+    
+    let mut subjects: Vec<String> = Vec::new();
+    let mut issuers: Vec<String> = Vec::new();
+    let mut verify: Option<(i32, String)> = None;
+    
+    for line in output.lines() {
+        if let Some(caps) = subject_regex.captures(line) {
+            subjects.push(caps[1].trim().to_string());
+        } else if let Some(caps) = issuer_regex.captures(line) {
+            issuers.push(caps[1].trim().to_string());
+        } else if let Some(caps) = verify_regex.captures(line) {
+            verify = Some((caps[1].parse().unwrap_or(-1), caps[2].trim().to_string()));
+        }
+    }
+    
+    if subjects.is_empty() || subjects.len() != issuers.len() {
+        return Err("No certificate chain presented by server".to_string());
+    }
+    
+    // Each certificate must be issued by the next one in the chain
+    let links_ok = (0..subjects.len() - 1).all(|k| issuers[k] == subjects[k + 1]);
+    
+    // 2: an intermediate is missing, 21: only the leaf was sent. 20 ("unable
+    // to get local issuer") is left out: openssl also returns it for a
+    // complete chain when it has no CA store, as on stock macOS.
+    let missing_issuer = matches!(verify.as_ref().map(|(code, _)| *code), Some(2) | Some(21));
+    
+    Ok(SslCertInfo {
+        subject: subjects[0].clone(),
+        issuer: issuers[0].clone(),
+        chain_subjects: subjects,
+        chain_complete: links_ok && !missing_issuer,
+        verify_code: verify.as_ref().map(|(code, _)| *code),
+        verify_message: verify.map(|(_, message)| message),
+        not_before: None,
+        not_after: None,
+    })
+}
+
+/// Parse a scheme-less `host:port` target (e.g. `mail.example.com:25`)
+pub fn parse_host_port(target: &str) -> Option<(String, u16)> {
+    if target.contains("://") {
+        return None;
+    }
+    
+    let (host, port) = target.rsplit_once(':')?;
+    let port: u16 = port.parse().ok()?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    
+    // Bare IPv6 addresses must be bracketed ("[::1]:25")
+    if host.is_empty() || (host.contains(':') && !target.starts_with('[')) {
+        return None;
+    }
+    Some((host.to_string(), port))
+}
+
+/// Resolve host and port to the first socket address
+async fn resolve_socket_addr(host: &str, port: u16) -> Result<std::net::SocketAddr, String> {
+    tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("No address found for {}", host))
+}
+
+/// Run raw TCP connect timing (and optional banner grab) against host:port
+pub async fn check_port(
+    host: &str,
+    port: u16,
+    grab_banner: bool,
+    binding: &SourceBinding,
+) -> Result<PortCheckResult, String> {
+    let addr = resolve_socket_addr(host, port).await?;
+    
+    let start = Instant::now();
+    let connect = tokio::time::timeout(
+        Duration::from_secs(10),
+        binding.connect(addr),
+    )
+    .await;
+    let connect_time = start.elapsed().as_secs_f64() * 1000.0;
+    
+    let mut result = PortCheckResult {
+        host: host.to_string(),
+        port,
+        remote_ip: addr.ip().to_string(),
+        connected: false,
+        connect_time_ms: connect_time,
+        banner: None,
+        error: None,
+    };
+    
+    match connect {
+        Ok(Ok(mut stream)) => {
+            result.connected = true;
+            if grab_banner {
+                result.banner = read_banner(&mut stream).await;
+            }
+        }
+        Ok(Err(e)) => result.error = Some(e.to_string()),
+        Err(_) => result.error = Some("Timeout sau 10 giây".to_string()),
+    }
+    
+    Ok(result)
+}
+
+/// Default scan host: portquiz.net answers on every TCP port, so a failed
+/// connect means the local network/ISP blocks the port
+pub const PORT_SCAN_HOST: &str = "portquiz.net";
+
+/// Ports commonly needed by applications, with their usual service
+const COMMON_PORTS: [(u16, &str); 15] = [
+    (21, "FTP"),
+    (22, "SSH"),
+    (25, "SMTP"),
+    (53, "DNS"),
+    (80, "HTTP"),
+    (110, "POP3"),
+    (143, "IMAP"),
+    (443, "HTTPS"),
+    (465, "SMTPS"),
+    (587, "SMTP submission"),
+    (993, "IMAPS"),
+    (995, "POP3S"),
+    (3389, "RDP"),
+    (5060, "SIP"),
+    (8080, "HTTP alternate"),
+];
+
+/// Per-port connect timeout for the common-ports scan
+const PORT_SCAN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Test which common outbound TCP ports this network allows
+/// 
+/// `host` should accept connections on every port (see `PORT_SCAN_HOST`).
+/// All ports are tried concurrently against one resolved address.
+pub async fn check_common_ports(host: &str, binding: &SourceBinding) -> Result<PortScanResult, String> {
+    let addr = resolve_socket_addr(host, 80).await?;
+    
+    let probes = COMMON_PORTS.iter().map(|&(port, service)| async move {
+        let start = Instant::now();
+        let target = std::net::SocketAddr::new(addr.ip(), port);
+        let (status, connect_time_ms) =
+            match tokio::time::timeout(PORT_SCAN_TIMEOUT, binding.connect(target)).await {
+                Ok(Ok(_)) => (PortStatus::Open, Some(start.elapsed().as_secs_f64() * 1000.0)),
+                Ok(Err(_)) => (PortStatus::Blocked, None),
+                Err(_) => (PortStatus::Filtered, None),
+            };
+        PortProbe {
+            port,
+            service: service.to_string(),
+            status,
+            connect_time_ms,
+        }
+    });
+    let ports = futures::future::join_all(probes).await;
+    
+    Ok(PortScanResult {
+        host: host.to_string(),
+        remote_ip: addr.ip().to_string(),
+        summary: port_scan_summary(&ports),
+        ports,
+    })
+}
+
+/// One-line summary of a port scan, listing the ports that are not open
+fn port_scan_summary(ports: &[PortProbe]) -> String {
+    let list = |status: PortStatus| {
+        ports
+            .iter()
+            .filter(|p| p.status == status)
+            .map(|p| format!("{} ({})", p.port, p.service))
+            .collect::<Vec<_>>()
+    };
+    
+    let open = ports.iter().filter(|p| p.status == PortStatus::Open).count();
+    let mut summary = format!("{} of {} ports open", open, ports.len());
+    for (label, status) in [("blocked", PortStatus::Blocked), ("filtered", PortStatus::Filtered)] {
+        let closed = list(status);
+        if !closed.is_empty() {
+            summary.push_str(&format!("; {}: {}", label, closed.join(", ")));
+        }
+    }
+    summary
+}
+
+/// Read the greeting many services send on connect (SMTP, SSH, FTP, ...)
+async fn read_banner(stream: &mut tokio::net::TcpStream) -> Option<String> {
+    use tokio::io::AsyncReadExt;
+    
+    let mut buf = [0u8; 512];
+    let n = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+        .await
+        .ok()?
+        .ok()?;
+    
+    let banner: String = String::from_utf8_lossy(&buf[..n])
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    
+    if banner.trim().is_empty() {
+        None
+    } else {
+        Some(banner.trim().to_string())
+    }
+}
+
+/// Download a real asset from the target and measure sustained throughput
+/// 
+/// `on_progress` is called periodically with (bytes downloaded, total bytes
+/// if the server announced a Content-Length).
+pub async fn measure_asset_download<F>(url: &str, on_progress: F) -> Result<AssetDownloadResult, String>
+where
+    F: Fn(u64, Option<u64>),
+{
+    // Ask for the size first so progress can be reported as a percentage
+    let total_bytes = raw_log::output_async(
+        tokio::process::Command::new("curl")
+            .args(["-s", "-I", "-L", "--connect-timeout", "10", "--max-time", "15", url]),
+    )
+    .await
+    .ok()
+    .and_then(|o| parse_content_length(&String::from_utf8_lossy(&o.stdout)));
+    
+    let temp_path = std::env::temp_dir()
+        .join(format!(
+            "netcheck-asset-{}-{}.tmp",
+            std::process::id(),
+            chrono::Utc::now().timestamp_millis()
+        ));
+    let temp_path_str = temp_path.to_string_lossy().to_string();
+    
+    let curl_format = r#"{"size": %{size_download}, "total": %{time_total}, "speed": %{speed_download}, "http_code": "%{http_code}"}"#;
+    
+    let start = Instant::now();
+    let mut child = tokio::process::Command::new("curl")
+        .args([
+            "-s",
+            "-L",
+            "-o", &temp_path_str,
+            "-w", curl_format,
+            "--connect-timeout", "10",
+            "--max-time", "120",
+            url,
+        ])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    
+    // Poll the partially written file while curl runs
+    let mut ticker = tokio::time::interval(Duration::from_millis(250));
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status,
+            _ = ticker.tick() => {
+                let bytes = std::fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
+                on_progress(bytes, total_bytes);
+            }
+        }
+    };
+    
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    let _ = std::fs::remove_file(&temp_path);
+    status.map_err(|e| format!("Failed to run curl: {}", e))?;
+    
+    let mut stdout = String::new();
+    if let Some(mut out) = child.stdout.take() {
+        use tokio::io::AsyncReadExt;
+        let _ = out.read_to_string(&mut stdout).await;
+    }
+    
+    let json: serde_json::Value = serde_json::from_str(&stdout)
+        .map_err(|e| format!("Failed to parse curl output: {} - Raw: {}", e, stdout))?;
+    
+    let bytes_downloaded = json["size"].as_f64().unwrap_or(0.0) as u64;
+    on_progress(bytes_downloaded, total_bytes.or(Some(bytes_downloaded)));
+    
+    Ok(AssetDownloadResult {
+        url: url.to_string(),
+        http_code: json["http_code"]
+            .as_str()
+            .and_then(|s| s.parse::<u16>().ok())
+            .unwrap_or(0),
+        bytes_downloaded,
+        total_bytes,
+        elapsed_ms: json["total"].as_f64().map(|t| t * 1000.0).unwrap_or(elapsed),
+        throughput_kbps: json["speed"].as_f64().unwrap_or(0.0) / 1024.0,
+    })
+}
+
+/// Parse Content-Length of the final response from `curl -I -L` headers
+fn parse_content_length(headers: &str) -> Option<u64> {
+    headers
+        .lines()
+        .rev()
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                value.trim().parse().ok()
+            } else {
+                None
+            }
+        })
+}
+
+/// Speed test server for the bandwidth check (Cloudflare, anycast)
+const SPEED_TEST_SERVER: &str = "speed.cloudflare.com";
+
+/// Bytes downloaded by the bandwidth check
+const DOWNLOAD_TEST_BYTES: usize = 10 * 1024 * 1024;
+
+/// Bytes uploaded by the bandwidth check
+const UPLOAD_TEST_BYTES: usize = 5 * 1024 * 1024;
+
+/// Size of each write of the upload payload to curl's stdin
+const UPLOAD_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Upload below this fraction of download counts as a strongly asymmetric link
+pub const ASYMMETRIC_UPLOAD_RATIO: f64 = 0.1;
+
+/// Handshakes timed against the speed test server while the download runs
+const LOADED_LATENCY_PROBES: usize = 5;
+
+/// Measure download and upload throughput separately
+/// 
+/// Runs the download first and the upload after it, so the two transfers don't
+/// compete for the link. While the download runs, TCP handshakes to the same
+/// server give the latency under load.
+pub async fn check_bandwidth(binding: &SourceBinding) -> Result<BandwidthResult, String> {
+    let download_url = format!("https://{}/__down?bytes={}", SPEED_TEST_SERVER, DOWNLOAD_TEST_BYTES);
+    let mut download_command = tokio::process::Command::new("curl");
+    download_command
+        .args([
+            "-o", "/dev/null",
+            "-s",
+            "-w", "%{http_code} %{speed_download}",
+            "--connect-timeout", "10",
+            "--max-time", "60",
+            &download_url,
+        ])
+        .args(binding.curl_args());
+    
+    let probe_addr = resolve_socket_addr(SPEED_TEST_SERVER, 443).await.ok();
+    let mut probes_sent = 0;
+    let mut loaded_rtts: Vec<f64> = Vec::new();
+    
+    // Give the transfer a second to ramp up before the first probe
+    let download = raw_log::output_async(&mut download_command);
+    tokio::pin!(download);
+    let mut ticker = tokio::time::interval_at(
+        tokio::time::Instant::now() + Duration::from_secs(1),
+        Duration::from_millis(500),
+    );
+    let download = loop {
+        tokio::select! {
+            output = &mut download => break output,
+            _ = ticker.tick(), if probes_sent < LOADED_LATENCY_PROBES => {
+                probes_sent += 1;
+                if let Some(addr) = probe_addr {
+                    loaded_rtts.extend(handshake_time_ms(binding, addr).await);
+                }
+            }
+        }
+    }
+    .map_err(|e| format!("Failed to run curl: {}", e))?;
+    let download_kbps = parse_transfer_speed(&download)?;
+    
+    // The payload is streamed to curl's stdin in chunks instead of written to disk
+    let upload_url = format!("https://{}/__up", SPEED_TEST_SERVER);
+    let mut upload_command = tokio::process::Command::new("curl");
+    upload_command
+        .args([
+            "-o", "/dev/null",
+            "-s",
+            "-w", "%{http_code} %{speed_upload}",
+            "-H", "Content-Type: application/octet-stream",
+            "--data-binary", "@-",
+            "--connect-timeout", "10",
+            "--max-time", "60",
+            &upload_url,
+        ])
+        .args(binding.curl_args())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = upload_command
+        .spawn()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        let chunk = [0u8; UPLOAD_CHUNK_BYTES];
+        for _ in 0..UPLOAD_TEST_BYTES / UPLOAD_CHUNK_BYTES {
+            stdin
+                .write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to send upload payload: {}", e))?;
+        }
+    }
+    let upload = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    raw_log::record(upload_command.as_std(), &upload);
+    let upload_kbps = parse_transfer_speed(&upload)?;
+    
+    Ok(BandwidthResult {
+        server: SPEED_TEST_SERVER.to_string(),
+        download_kbps,
+        upload_kbps,
+        upload_ratio: (download_kbps > 0.0).then(|| upload_kbps / download_kbps),
+        loaded_latency_ms: (!loaded_rtts.is_empty())
+            .then(|| loaded_rtts.iter().sum::<f64>() / loaded_rtts.len() as f64),
+    })
+}
+
+/// Time a TCP connect to `addr`, `None` on error or after 3 seconds
+async fn handshake_time_ms(binding: &SourceBinding, addr: std::net::SocketAddr) -> Option<f64> {
+    let start = Instant::now();
+    match tokio::time::timeout(Duration::from_secs(3), binding.connect(addr)).await {
+        Ok(Ok(_)) => Some(start.elapsed().as_secs_f64() * 1000.0),
+        _ => None,
+    }
+}
+
+/// Transfer speed from curl's `-w "%{http_code} %{speed_*}"` output
+fn parse_transfer_speed(output: &std::process::Output) -> Result<f64, String> {
+    if !output.status.success() {
+        return Err(curl_error_reason(output.status.code().unwrap_or(-1)));
+    }
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split_whitespace();
+    let http_code = fields.next().and_then(|code| code.parse::<u16>().ok()).unwrap_or(0);
+    if !(200..300).contains(&http_code) {
+        return Err(format!("Speed test server returned HTTP {}", http_code));
+    }
+    
+    fields
+        .next()
+        .and_then(|speed| speed.parse::<f64>().ok())
+        .map(|bytes_per_sec| bytes_per_sec / 1024.0)
+        .ok_or_else(|| format!("Failed to parse curl output: {}", stdout))
+}
+
+/// DNS-based blocklists queried for IP reputation
+/// 
+/// Barracuda only answers resolvers registered with it (free, at
+/// barracudacentral.org); through an unregistered one every IP looks unlisted.
+/// Spamhaus refuses queries from public resolvers with a 127.255.255.x code,
+/// which `query_dnsbl` does not count as a listing.
+const DNSBL_ZONES: [&str; 3] = [
+    "zen.spamhaus.org",
+    "bl.spamcop.net",
+    "b.barracudacentral.org",
+];
+
+/// Check IPs against DNS-based blocklists (DNSBL)
+/// 
+/// Each IP is queried as `<reversed-ip>.<zone>`; an answer in 127.0.0.0/8
+/// means the IP is listed, NXDOMAIN means it is not.
+pub async fn check_blocklists(ips: &[String], include_own_ip: bool) -> Result<BlocklistResult, String> {
+    // DNSBLs only cover IPv4
+    let mut targets: Vec<(String, bool)> = ips
+        .iter()
+        .filter(|ip| ip.parse::<std::net::Ipv4Addr>().is_ok())
+        .map(|ip| (ip.clone(), false))
+        .collect();
+    
+    let public_ip = if include_own_ip { detect_public_ip().await } else { None };
+    if let Some(ip) = &public_ip {
+        targets.push((ip.clone(), true));
+    }
+    
+    if targets.is_empty() {
+        return Err("No IPv4 address to check".to_string());
+    }
+    
+    let queries = targets.iter().flat_map(|(ip, is_own_ip)| {
+        DNSBL_ZONES.iter().map(move |zone| async move {
+            query_dnsbl(ip, zone).await.map(|return_code| BlocklistListing {
+                ip: ip.clone(),
+                blocklist: zone.to_string(),
+                return_code,
+                is_own_ip: *is_own_ip,
+            })
+        })
+    });
+    
+    let listings: Vec<BlocklistListing> = futures::future::join_all(queries)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    
+    Ok(BlocklistResult {
+        checked_ips: targets.into_iter().map(|(ip, _)| ip).collect(),
+        blocklists: DNSBL_ZONES.iter().map(|z| z.to_string()).collect(),
+        public_ip,
+        listings,
+    })
+}
+
+/// Query one DNSBL zone, returning the listing code if the IP is listed
+async fn query_dnsbl(ip: &str, zone: &str) -> Option<String> {
+    let reversed: Vec<&str> = ip.split('.').rev().collect();
+    let query = format!("{}.{}", reversed.join("."), zone);
+    
+    let addrs = tokio::time::timeout(
+        Duration::from_secs(5),
+        tokio::net::lookup_host((query.as_str(), 0)),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    
+    // 127.255.255.x is an error code (e.g. query via public resolver), not a listing
+    let codes: Vec<String> = addrs.map(|a| a.ip().to_string()).collect();
+    codes
+        .into_iter()
+        .find(|code| code.starts_with("127.") && !code.starts_with("127.255.255."))
+}
+
+/// Control resource for proxy detection: always an empty 204 over plain HTTP
+const PROXY_CONTROL_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+/// Status code the control resource answers with
+const PROXY_CONTROL_STATUS: u16 = 204;
+
+/// SHA-256 of the control resource body (empty)
+const PROXY_CONTROL_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Headers added by caching/intercepting proxies
+const PROXY_HEADERS: [&str; 5] = ["via", "x-cache", "x-cache-lookup", "x-squid-error", "proxy-connection"];
+
+/// Detect a transparent HTTP proxy between the user and the internet
+/// 
+/// Fetches a control resource with a known status and body checksum over plain
+/// HTTP. A different status/body (injected content, block page) or proxy headers
+/// the control server never sends mean an intermediary is rewriting traffic.
+pub async fn check_transparent_proxy(binding: &SourceBinding) -> Result<ProxyDetectionResult, String> {
+    let output = raw_log::output_async(
+        tokio::process::Command::new("curl")
+            .args([
+                "-s",
+                "-i",
+                "--connect-timeout", "5",
+                "--max-time", "10",
+                PROXY_CONTROL_URL,
+            ])
+            .args(binding.curl_args()),
+    )
+    .await
+    .map_err(|e| format!("Failed to run curl: {}", e))?;
+    
+    if !output.status.success() {
+        return Err(curl_error_reason(output.status.code().unwrap_or(-1)));
+    }
+    
+    let response = output.stdout;
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+        .unwrap_or(response.len());
+    let (head, body) = response.split_at(header_end);
+    let head = String::from_utf8_lossy(head);
+    
+    let http_code = head
+        .lines()
+        .next()
+        .and_then(|status| status.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .unwrap_or(0);
+    
+    let proxy_headers: Vec<String> = head
+        .lines()
+        .skip(1)
+        .filter(|line| {
+            line.split_once(':')
+                .map(|(name, _)| PROXY_HEADERS.contains(&name.trim().to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .map(|line| line.trim().to_string())
+        .collect();
+    
+    let actual_sha256: String = Sha256::digest(body)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    
+    let content_modified = http_code != PROXY_CONTROL_STATUS || actual_sha256 != PROXY_CONTROL_SHA256;
+    
+    Ok(ProxyDetectionResult {
+        control_url: PROXY_CONTROL_URL.to_string(),
+        http_code,
+        expected_sha256: PROXY_CONTROL_SHA256.to_string(),
+        actual_sha256,
+        content_modified,
+        proxy_detected: content_modified || !proxy_headers.is_empty(),
+        proxy_headers,
+    })
+}
+
+/// Detect the user's public IP address
+pub async fn detect_public_ip() -> Option<String> {
+    let output = raw_log::output_async(
+        tokio::process::Command::new("curl")
+            .args(["-s", "--connect-timeout", "5", "--max-time", "5", "https://api.ipify.org"]),
+    )
+    .await
+    .ok()?;
+    
+    let ip = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    ip.parse::<std::net::IpAddr>().ok().map(|_| ip)
+}
+
+/// Combine latency and throughput results into one responsiveness summary
+/// 
+/// RPM (round trips per minute) is computed from the latency under load when
+/// the bandwidth check measured it, otherwise from the average HTTP round trip
+/// of the stability test, falling back to the idle handshake latency.
+/// Throughput comes from the bandwidth download when available.
+pub fn summarize_quality(
+    tcp: &Option<TcpResult>,
+    stability: &Option<StabilityResult>,
+    bandwidth: &Option<BandwidthResult>,
+) -> Option<NetworkQuality> {
+    let idle_latency_ms = tcp
+        .as_ref()
+        .filter(|t| t.http_code != 0)
+        .map(|t| t.connect_time_ms - t.dns_time_ms)
+        .filter(|ms| *ms > 0.0);
+    
+    let loaded_latency_ms = bandwidth.as_ref().and_then(|b| b.loaded_latency_ms);
+    
+    let throughput_kbps = bandwidth
+        .as_ref()
+        .map(|b| b.download_kbps)
+        .filter(|kbps| *kbps > 0.0)
+        .or_else(|| {
+            tcp.as_ref()
+                .filter(|t| t.http_code != 0)
+                .map(|t| t.download_speed_kbps)
+        });
+    
+    let stability_latency = stability
+        .as_ref()
+        .filter(|s| s.successful_tests > 0)
+        .map(|s| s.avg_time_ms);
+    
+    if idle_latency_ms.is_none()
+        && throughput_kbps.is_none()
+        && stability_latency.is_none()
+        && loaded_latency_ms.is_none()
+    {
+        return None;
+    }
+    
+    let working_latency = loaded_latency_ms.or(stability_latency).or(idle_latency_ms);
+    let rpm = working_latency
+        .filter(|ms| *ms > 0.0)
+        .map(|ms| (60_000.0 / ms).round() as u32);
+    
+    let grade = rpm.map(|rpm| {
+        if rpm >= 1000 {
+            QualityGrade::A
+        } else if rpm >= 600 {
+            QualityGrade::B
+        } else if rpm >= 300 {
+            QualityGrade::C
+        } else if rpm >= 100 {
+            QualityGrade::D
+        } else {
+            QualityGrade::F
+        }
+    });
+    
+    Some(NetworkQuality {
+        idle_latency_ms,
+        loaded_latency_ms,
+        throughput_kbps,
+        rpm,
+        grade,
+    })
+}
+
+/// VoIP: jitter and loss matter more than raw latency
+const VOIP_MAX_JITTER_MS: f64 = 30.0;
+const VOIP_MAX_LOSS_PERCENT: f64 = 1.0;
+
+/// Gaming: round trip latency dominates
+const GAMING_MAX_LATENCY_MS: f64 = 50.0;
+
+/// Video calls: tolerate more latency and some loss
+const VIDEO_CALL_MAX_LATENCY_MS: f64 = 100.0;
+const VIDEO_CALL_MAX_LOSS_PERCENT: f64 = 3.0;
+
+/// Compare the results with a user-defined SLA
+/// 
+/// Independent of the score. A metric that was not measured fails, since it
+/// can't be shown to meet the limit. An SLA without limits yields no checks.
+pub fn evaluate_sla(
+    sla: &SlaConfig,
+    dns: &Option<DnsResult>,
+    tcp: &Option<TcpResult>,
+    stability: &Option<StabilityResult>,
+) -> Vec<SlaCheck> {
+    // A failed lookup has a lookup time but nothing to show for it
+    let dns = dns.as_ref().filter(|d| !d.resolved_ips.is_empty());
+    let tcp = tcp.as_ref().filter(|t| t.http_code != 0);
+    let stability = stability.as_ref().filter(|s| s.total_tests > 0);
+    
+    // (metric, limit, measured value, unit, limit is a minimum)
+    let limits = [
+        ("DNS lookup", sla.max_dns_ms, dns.map(|d| d.lookup_time_ms), " ms", false),
+        ("TTFB", sla.max_ttfb_ms, tcp.map(|t| t.ttfb_ms), " ms", false),
+        ("Total time", sla.max_total_ms, tcp.map(|t| t.total_time_ms), " ms", false),
+        ("Success rate", sla.min_success_rate, stability.map(|s| s.success_rate), "%", true),
+        (
+            "Jitter",
+            sla.max_jitter_ms,
+            stability.filter(|s| s.successful_tests > 0).map(|s| s.jitter_ms),
+            " ms",
+            false,
+        ),
+    ];
+    
+    limits
+        .into_iter()
+        .filter_map(|(metric, limit, actual, unit, is_minimum)| {
+            let limit = limit?;
+            // `{}` keeps a fractional limit (99.5) instead of rounding it up
+            let (requirement, passed) = if is_minimum {
+                (format!(">= {}{}", limit, unit), actual.is_some_and(|v| v >= limit))
+            } else {
+                (format!("<= {}{}", limit, unit), actual.is_some_and(|v| v <= limit))
+            };
+            Some(SlaCheck {
+                metric: metric.to_string(),
+                requirement,
+                actual,
+                passed,
+            })
+        })
+        .collect()
+}
+
+/// Classify the link for real-time use cases (VoIP, gaming, video calls)
+/// 
+/// Latency is the TCP handshake round trip (raw connect time in host:port
+/// mode); jitter and loss come from the stability probes.
+pub fn evaluate_realtime_suitability(
+    tcp: &Option<TcpResult>,
+    port_check: &Option<PortCheckResult>,
+    stability: &Option<StabilityResult>,
+) -> Vec<UseCaseVerdict> {
+    let latency_ms = tcp
+        .as_ref()
+        .filter(|t| t.http_code != 0)
+        .map(|t| t.connect_time_ms - t.dns_time_ms)
+        .filter(|ms| *ms > 0.0)
+        .or_else(|| {
+            port_check
+                .as_ref()
+                .filter(|p| p.connected)
+                .map(|p| p.connect_time_ms)
+        });
+    
+    let stability = stability.as_ref().filter(|s| s.total_tests > 0);
+    let jitter_ms = stability.filter(|s| s.successful_tests > 0).map(|s| s.jitter_ms);
+    let loss_percent = stability.map(|s| 100.0 - s.success_rate);
+    
+    // Unknown unless every required metric was measured
+    let all_below = |checks: &[(Option<f64>, f64)]| -> Option<bool> {
+        checks
+            .iter()
+            .map(|(value, limit)| value.map(|v| v < *limit))
+            .collect::<Option<Vec<bool>>>()
+            .map(|results| results.into_iter().all(|ok| ok))
+    };
+    
+    vec![
+        UseCaseVerdict {
+            use_case: RealtimeUseCase::Voip,
+            requirement: format!(
+                "jitter < {:.0} ms, loss < {:.0}%",
+                VOIP_MAX_JITTER_MS, VOIP_MAX_LOSS_PERCENT
+            ),
+            suitable: all_below(&[
+                (jitter_ms, VOIP_MAX_JITTER_MS),
+                (loss_percent, VOIP_MAX_LOSS_PERCENT),
+            ]),
+            latency_ms: None,
+            jitter_ms,
+            loss_percent,
+        },
+        UseCaseVerdict {
+            use_case: RealtimeUseCase::Gaming,
+            requirement: format!("latency < {:.0} ms", GAMING_MAX_LATENCY_MS),
+            suitable: all_below(&[(latency_ms, GAMING_MAX_LATENCY_MS)]),
+            latency_ms,
+            jitter_ms: None,
+            loss_percent: None,
+        },
+        UseCaseVerdict {
+            use_case: RealtimeUseCase::VideoCall,
+            requirement: format!(
+                "latency < {:.0} ms, loss < {:.0}%",
+                VIDEO_CALL_MAX_LATENCY_MS, VIDEO_CALL_MAX_LOSS_PERCENT
+            ),
+            suitable: all_below(&[
+                (latency_ms, VIDEO_CALL_MAX_LATENCY_MS),
+                (loss_percent, VIDEO_CALL_MAX_LOSS_PERCENT),
+            ]),
+            latency_ms,
+            jitter_ms: None,
+            loss_percent,
+        },
+    ]
+}
+
+/// Vietnamese name of a real-time use case
+fn use_case_name(use_case: RealtimeUseCase) -> &'static str {
+    match use_case {
+        RealtimeUseCase::Voip => "Gọi thoại VoIP",
+        RealtimeUseCase::Gaming => "Chơi game online",
+        RealtimeUseCase::VideoCall => "Gọi video",
+    }
+}
+
+/// Format blocklist listings as "ip (list, ...)"
+fn describe_listings(listings: &[&BlocklistListing]) -> String {
+    let mut by_ip: Vec<(String, Vec<String>)> = Vec::new();
+    for listing in listings {
+        match by_ip.iter_mut().find(|(ip, _)| *ip == listing.ip) {
+            Some((_, lists)) => lists.push(listing.blocklist.clone()),
+            None => by_ip.push((listing.ip.clone(), vec![listing.blocklist.clone()])),
+        }
+    }
+    by_ip
+        .iter()
+        .map(|(ip, lists)| format!("{} ({})", ip, lists.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// System clocks before this year are certainly wrong (e.g. reset to 1970)
+const MIN_PLAUSIBLE_YEAR: i32 = 2024;
+
+/// Local clock further than this from the server's `Date` header is wrong
+/// (latency and the header's 1 s resolution stay far below it)
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// Who is at fault for a certificate validity problem
+#[derive(Debug, PartialEq)]
+struct CertClockVerdict {
+    /// The local clock is off: measured skew, implausible year, or a
+    /// certificate that is not valid yet
+    clock_wrong: bool,
+    /// The certificate has expired by the server's clock (or by ours when
+    /// nothing suggests it is wrong)
+    expired: bool,
+}
+
+/// Judge certificate dates against the local clock and the server's `Date`
+fn judge_cert_clock(
+    now: chrono::DateTime<chrono::Utc>,
+    clock_skew_secs: Option<i64>,
+    not_before: Option<chrono::DateTime<chrono::Utc>>,
+    not_after: Option<chrono::DateTime<chrono::Utc>>,
+) -> CertClockVerdict {
+    let skew = clock_skew_secs.filter(|s| s.abs() > MAX_CLOCK_SKEW_SECS);
+    // When our clock is known to be off, the server's clock decides
+    let reference_now = now - chrono::Duration::seconds(skew.unwrap_or(0));
+    let not_yet_valid = not_before.is_some_and(|t| reference_now < t);
+    let expired = not_after.is_some_and(|t| reference_now > t);
+    
+    // Servers practically never present certificates from the future
+    let clock_wrong = skew.is_some() || now.year() < MIN_PLAUSIBLE_YEAR || not_yet_valid;
+    CertClockVerdict {
+        clock_wrong,
+        // A suspect clock without a measured skew can't clear the server
+        expired: expired && (skew.is_some() || !clock_wrong),
+    }
+}
+
+/// "3 ngày" / "2 giờ" / "7 phút" for a clock difference
+fn format_skew(secs: i64) -> String {
+    let secs = secs.abs();
+    if secs >= 86_400 {
+        format!("{} ngày", secs / 86_400)
+    } else if secs >= 3_600 {
+        format!("{} giờ", secs / 3_600)
+    } else {
+        format!("{} phút", secs / 60)
+    }
+}
+
+/// Part of a request that took most of its time
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SlowPart {
+    /// TCP connect + SSL handshake (distance, routing)
+    Network,
+    /// TTFB after the handshake (server processing)
+    Server,
+    /// Body download after the first byte
+    Transfer,
+}
+
+/// Split a request into network / server / transfer time and explain which dominated
+/// 
+/// curl timings are cumulative, so each phase is the difference to the previous one.
+fn timing_breakdown(tcp: &TcpResult) -> (SlowPart, String) {
+    let handshake_done = tcp.ssl_time_ms.max(tcp.connect_time_ms);
+    let network = (handshake_done - tcp.dns_time_ms).max(0.0);
+    let server = (tcp.ttfb_ms - handshake_done).max(0.0);
+    let transfer = (tcp.total_time_ms - tcp.ttfb_ms).max(0.0);
+    
+    let slow_part = if server >= network && server >= transfer {
+        SlowPart::Server
+    } else if network >= transfer {
+        SlowPart::Network
+    } else {
+        SlowPart::Transfer
+    };
+    let attribution = match slow_part {
+        SlowPart::Server => "chủ yếu do server xử lý chậm",
+        SlowPart::Network => "chủ yếu do mạng/khoảng cách đến server",
+        SlowPart::Transfer => "chủ yếu do thời gian tải nội dung",
+    };
+    
+    (
+        slow_part,
+        format!(
+            "Phân tích: kết nối + SSL {:.0}ms, server xử lý {:.0}ms, tải nội dung {:.0}ms, {}",
+            network, server, transfer, attribution
+        ),
+    )
+}
+
+/// Address family label of an IP string
+fn ip_family(ip: &str) -> &'static str {
+    if ip.contains(':') {
+        "IPv6"
+    } else {
+        "IPv4"
+    }
+}
+
+/// Hops scanned for NAT layers (home router, ISP modem, carrier NAT)
+const NAT_SCAN_HOPS: usize = 5;
+
+/// Shared address space used by carrier-grade NAT (RFC 6598, 100.64.0.0/10)
+fn is_cgnat(ip: &std::net::Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    octets[0] == 100 && (octets[1] & 0xC0) == 64
+}
+
+/// Private / CGNAT hops in front of the internet, in order
+/// 
+/// Scans the first `NAT_SCAN_HOPS` hops up to the first public address or our
+/// own public IP; private hops past that are ISP-internal routers, not NAT.
+/// Empty when neither the edge nor a public IP was seen, since an all-private
+/// path may just be an internal network.
+fn nat_hops(hops: &[RouteHop], public_ip: Option<&str>) -> Vec<std::net::Ipv4Addr> {
+    let mut private_hops: Vec<std::net::Ipv4Addr> = Vec::new();
+    let mut reached_edge = public_ip.is_some();
+    for hop in hops.iter().take(NAT_SCAN_HOPS) {
+        if public_ip == Some(hop.ip_address.as_str()) {
+            reached_edge = true;
+            break;
+        }
+        let Ok(ip) = hop.ip_address.parse::<std::net::Ipv4Addr>() else {
+            continue;
+        };
+        if !(ip.is_private() || is_cgnat(&ip)) {
+            reached_edge = true;
+            break;
+        }
+        if !private_hops.contains(&ip) {
+            private_hops.push(ip);
+        }
+    }
+    if reached_edge {
+        private_hops
+    } else {
+        Vec::new()
+    }
 }
 
-/// Run connection stability test
-pub async fn check_stability(domain: &str, num_tests: u32) -> Result<StabilityResult, String> {
-    let url = format!("https://{}", domain);
-    let mut times: Vec<f64> = Vec::new();
-    let mut successful = 0u32;
-    
-    for _ in 0..num_tests {
-        let start = Instant::now();
-        
-        let result = Command::new("curl")
-            .args([
-                "-o", "/dev/null",
-                "-s",
-                "--connect-timeout", "5",
-                "--max-time", "10",
-                "-w", "%{http_code}",
-                &url,
-            ])
-            .output();
-        
-        match result {
-            Ok(output) => {
-                let elapsed = start.elapsed().as_secs_f64() * 1000.0;
-                let code = String::from_utf8_lossy(&output.stdout);
-                
-                if code.starts_with("2") || code.starts_with("3") {
-                    successful += 1;
-                    times.push(elapsed);
-                }
-            }
-            Err(_) => {}
-        }
-        
-        // Small delay between tests
-        tokio::time::sleep(Duration::from_millis(100)).await;
+/// Failed stability probes by cause, e.g. "3 timeout, 1 lỗi kết nối"
+fn stability_failure_breakdown(stability: &StabilityResult) -> String {
+    [
+        (stability.timeouts, "timeout"),
+        (stability.connection_errors, "lỗi kết nối"),
+        (stability.http_errors, "lỗi HTTP"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{} {}", count, label))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Running score (starts at 100) with the reason for every deduction
+struct ScoreCard {
+    value: i32,
+    breakdown: Vec<(String, i32)>,
+}
+
+impl ScoreCard {
+    fn new() -> Self {
+        Self { value: 100, breakdown: Vec::new() }
     }
     
-    let success_rate = (successful as f64 / num_tests as f64) * 100.0;
-    
-    let (min_time, avg_time, max_time, jitter) = if !times.is_empty() {
-        let min = times.iter().cloned().fold(f64::INFINITY, f64::min);
-        let max = times.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        let avg = times.iter().sum::<f64>() / times.len() as f64;
-        
-        // Calculate jitter (average deviation from mean)
-        let jitter = times.iter()
-            .map(|t| (t - avg).abs())
-            .sum::<f64>() / times.len() as f64;
-        
-        (min, avg, max, jitter)
-    } else {
-        (0.0, 0.0, 0.0, 0.0)
-    };
+    fn deduct(&mut self, points: i32, reason: &str) {
+        self.value -= points;
+        self.breakdown.push((reason.to_string(), -points));
+    }
     
-    Ok(StabilityResult {
-        total_tests: num_tests,
-        successful_tests: successful,
-        success_rate,
-        min_time_ms: min_time,
-        avg_time_ms: avg_time,
-        max_time_ms: max_time,
-        jitter_ms: jitter,
-    })
+    /// Deduct points for the issue that was just pushed, named by its title
+    fn deduct_for(&mut self, issues: &[DiagnosticIssue], points: i32) {
+        let reason = issues.last().map_or("", |issue| issue.title.as_str());
+        self.deduct(points, reason);
+    }
+}
+
+/// Outcome of `analyze_results`
+pub struct Analysis {
+    pub issues: Vec<DiagnosticIssue>,
+    pub recommendations: Vec<String>,
+    pub overall_status: OverallStatus,
+    /// Every score deduction as (reason, negative points), in analysis order
+    pub score_breakdown: Vec<(String, i32)>,
+}
+
+/// Check results fed into `analyze_results`
+pub struct AnalysisInput<'a> {
+    pub dns: &'a Option<DnsResult>,
+    pub secure_dns: &'a Option<SecureDnsResult>,
+    pub tcp: &'a Option<TcpResult>,
+    pub per_ip_tcp: &'a Option<BTreeMap<String, TcpResult>>,
+    pub ssl_cert: &'a Option<SslCertInfo>,
+    pub routing: &'a Option<RoutingResult>,
+    pub stability: &'a Option<StabilityResult>,
+    pub latency_by_size: &'a Option<LatencyBySizeResult>,
+    pub blocklist: &'a Option<BlocklistResult>,
+    pub proxy: &'a Option<ProxyDetectionResult>,
+    pub bandwidth: &'a Option<BandwidthResult>,
+    pub port_check: &'a Option<PortCheckResult>,
+    pub thresholds: &'a DiagnosticThresholds,
 }
 
 /// Analyze results and generate issues and recommendations
-pub fn analyze_results(
-    dns: &Option<DnsResult>,
-    tcp: &Option<TcpResult>,
-    routing: &Option<RoutingResult>,
-    stability: &Option<StabilityResult>,
-) -> (Vec<DiagnosticIssue>, Vec<String>, OverallStatus) {
+pub fn analyze_results(input: &AnalysisInput) -> Analysis {
+    let AnalysisInput {
+        dns,
+        secure_dns,
+        tcp,
+        per_ip_tcp,
+        ssl_cert,
+        routing,
+        stability,
+        latency_by_size,
+        blocklist,
+        proxy,
+        bandwidth,
+        port_check,
+        thresholds,
+    } = input;
+    
     let mut issues: Vec<DiagnosticIssue> = Vec::new();
     let mut recommendations: Vec<String> = Vec::new();
-    let mut score = 100i32;
+    let mut score = ScoreCard::new();
     
     // Analyze DNS
     if let Some(dns) = dns {
@@ -298,34 +2756,88 @@ pub fn analyze_results(
                     "Kiểm tra kết nối internet".to_string(),
                 ],
             });
-            score -= 50;
-        } else if dns.lookup_time_ms > 200.0 {
+            score.deduct_for(&issues, 50);
+        } else if let (Some(resolver), Some(local_error)) = (&dns.resolver, &dns.local_resolver_error) {
             issues.push(DiagnosticIssue {
                 category: IssueCategory::Dns,
                 severity: IssueSeverity::Warning,
-                title: "DNS chậm".to_string(),
-                description: format!("Thời gian DNS lookup: {:.0}ms (nên < 200ms)", dns.lookup_time_ms),
+                title: "DNS resolver cục bộ bị lỗi".to_string(),
+                description: format!(
+                    "DNS resolver của mạng/ISP không phân giải được {} ({}), nhưng DNS công cộng {} thì thành công",
+                    dns.domain, local_error, resolver
+                ),
                 possible_causes: vec![
-                    "DNS server xa về mặt địa lý".to_string(),
-                    "DNS server quá tải".to_string(),
-                    "Không có DNS cache".to_string(),
+                    "DNS server của ISP/router bị lỗi hoặc quá tải".to_string(),
+                    "Resolver cục bộ đang lọc/chặn domain này".to_string(),
+                    "Cấu hình DNS trên máy hoặc router sai".to_string(),
                 ],
                 solutions: vec![
-                    "Đổi sang DNS nhanh hơn như Cloudflare (1.1.1.1) hoặc Google (8.8.8.8)".to_string(),
-                    format!("Thêm {} vào file /etc/hosts với IP {}", dns.domain, dns.resolved_ips.first().unwrap_or(&String::new())),
+                    format!("Đổi DNS của máy hoặc router sang {}", resolver),
+                    "Khởi động lại router để làm mới DNS cache".to_string(),
+                    "Liên hệ ISP nếu DNS của họ liên tục lỗi".to_string(),
                 ],
             });
-            score -= 10;
+            score.deduct_for(&issues, 15);
+        } else if dns.lookup_time_ms > thresholds.dns_ms {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Dns,
+                severity: IssueSeverity::Warning,
+                title: "DNS chậm".to_string(),
+                description: format!(
+                    "Thời gian DNS lookup: {:.0}ms (nên < {:.0}ms)",
+                    dns.lookup_time_ms, thresholds.dns_ms
+                ),
+                possible_causes: Metric::DnsLookup.possible_causes(),
+                solutions: {
+                    let mut solutions = Metric::DnsLookup.solutions();
+                    solutions.push(format!("Thêm {} vào file /etc/hosts với IP {}", dns.domain, dns.resolved_ips.first().unwrap_or(&String::new())));
+                    solutions
+                },
+            });
+            score.deduct_for(&issues, 10);
         }
         
-        if dns.using_cdn.is_some() {
+        if let Some(cdn) = &dns.using_cdn {
             recommendations.push(format!(
                 "Website sử dụng CDN {} - đây là dấu hiệu tốt cho hiệu năng",
-                dns.using_cdn.as_ref().unwrap()
+                cdn
             ));
         }
     }
     
+    // Analyze encrypted DNS availability (only meaningful when plain DNS works)
+    let plain_dns_works = dns.as_ref().is_some_and(|d| !d.resolved_ips.is_empty());
+    if let (Some(secure_dns), true) = (secure_dns, plain_dns_works) {
+        let mut blocked: Vec<&str> = Vec::new();
+        if !secure_dns.doh_available {
+            blocked.push("DNS-over-HTTPS");
+        }
+        if secure_dns.dot_available == Some(false) {
+            blocked.push("DNS-over-TLS");
+        }
+        
+        if !blocked.is_empty() {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Dns,
+                severity: IssueSeverity::Info,
+                title: "DNS mã hóa bị chặn".to_string(),
+                description: format!(
+                    "DNS thường hoạt động nhưng {} không truy cập được. Mạng có thể đang lọc hoặc giám sát DNS",
+                    blocked.join(" và ")
+                ),
+                possible_causes: vec![
+                    "Firewall công ty/trường học chặn DoH/DoT để kiểm soát DNS".to_string(),
+                    "ISP chặn port 853 hoặc endpoint DoH".to_string(),
+                    "Phần mềm bảo mật/parental control can thiệp vào DNS".to_string(),
+                ],
+                solutions: vec![
+                    "Nếu cần quyền riêng tư, thử VPN hoặc mạng khác".to_string(),
+                    "Hỏi quản trị mạng về chính sách DNS".to_string(),
+                ],
+            });
+        }
+    }
+    
     // Analyze TCP timing
     if let Some(tcp) = tcp {
         if tcp.http_code == 0 {
@@ -333,7 +2845,10 @@ pub fn analyze_results(
                 category: IssueCategory::Tcp,
                 severity: IssueSeverity::Error,
                 title: "Không thể kết nối TCP".to_string(),
-                description: "Kết nối TCP thất bại hoàn toàn".to_string(),
+                description: match &tcp.failure_reason {
+                    Some(reason) => format!("Kết nối TCP thất bại hoàn toàn: {}", reason),
+                    None => "Kết nối TCP thất bại hoàn toàn".to_string(),
+                },
                 possible_causes: vec![
                     "Website không hoạt động".to_string(),
                     "Port 443 bị chặn".to_string(),
@@ -346,70 +2861,90 @@ pub fn analyze_results(
                     "Liên hệ ISP nếu vấn đề kéo dài".to_string(),
                 ],
             });
-            score -= 50;
+            score.deduct_for(&issues, 50);
         } else {
             // Check connection time
             let connect_only = tcp.connect_time_ms - tcp.dns_time_ms;
-            if connect_only > 500.0 {
+            if connect_only > thresholds.connect_ms {
                 issues.push(DiagnosticIssue {
                     category: IssueCategory::Tcp,
                     severity: IssueSeverity::Warning,
                     title: "TCP Connect chậm".to_string(),
-                    description: format!("Thời gian TCP connect: {:.0}ms (nên < 500ms)", connect_only),
-                    possible_causes: vec![
-                        "Server ở xa (khác châu lục)".to_string(),
-                        "Routing kém từ ISP".to_string(),
-                        "Nghẽn mạng".to_string(),
-                    ],
-                    solutions: vec![
-                        "Vấn đề này thường do khoảng cách địa lý, khó cải thiện".to_string(),
-                        "Thử sử dụng VPN với server gần target hơn".to_string(),
-                    ],
+                    description: format!(
+                        "Thời gian TCP connect: {:.0}ms (nên < {:.0}ms)",
+                        connect_only, thresholds.connect_ms
+                    ),
+                    possible_causes: Metric::TcpConnect.possible_causes(),
+                    solutions: Metric::TcpConnect.solutions(),
                 });
-                score -= 15;
+                score.deduct_for(&issues, 15);
             }
             
             // Check SSL time
             let ssl_only = tcp.ssl_time_ms - tcp.connect_time_ms;
-            if ssl_only > 500.0 {
+            if ssl_only > thresholds.ssl_ms {
                 issues.push(DiagnosticIssue {
                     category: IssueCategory::Ssl,
                     severity: IssueSeverity::Warning,
                     title: "SSL Handshake chậm".to_string(),
-                    description: format!("Thời gian SSL handshake: {:.0}ms (nên < 500ms)", ssl_only),
-                    possible_causes: vec![
-                        "SSL certificate chain dài".to_string(),
-                        "OCSP stapling không được bật".to_string(),
-                        "Latency cao đến server".to_string(),
-                    ],
-                    solutions: vec![
-                        "Đây thường là vấn đề từ phía server".to_string(),
-                        "Kiểm tra xem có đang bị man-in-the-middle không".to_string(),
-                    ],
+                    description: format!(
+                        "Thời gian SSL handshake: {:.0}ms (nên < {:.0}ms)",
+                        ssl_only, thresholds.ssl_ms
+                    ),
+                    possible_causes: Metric::SslHandshake.possible_causes(),
+                    solutions: Metric::SslHandshake.solutions(),
                 });
-                score -= 10;
+                score.deduct_for(&issues, 10);
+            }
+            
+            // Check server response time (only when the profile asks for it)
+            if let Some(ttfb_limit) = thresholds.ttfb_ms {
+                if tcp.ttfb_ms > ttfb_limit {
+                    issues.push(DiagnosticIssue {
+                        category: IssueCategory::Http,
+                        severity: IssueSeverity::Warning,
+                        title: "TTFB chậm".to_string(),
+                        description: format!(
+                            "Time to First Byte: {:.0}ms (nên < {:.0}ms). {}",
+                            tcp.ttfb_ms, ttfb_limit, timing_breakdown(tcp).1
+                        ),
+                        possible_causes: Metric::Ttfb.possible_causes(),
+                        solutions: Metric::Ttfb.solutions(),
+                    });
+                    score.deduct_for(&issues, 10);
+                }
             }
             
             // Check total time
-            if tcp.total_time_ms > 3000.0 {
+            if tcp.total_time_ms > thresholds.total_ms {
+                let (slow_part, breakdown) = timing_breakdown(tcp);
+                let mut solutions = match slow_part {
+                    SlowPart::Server => vec![
+                        "Liên hệ quản trị website: phần lớn thời gian là server xử lý request".to_string(),
+                    ],
+                    SlowPart::Network => vec![
+                        "Thử sử dụng VPN với server gần target hơn".to_string(),
+                    ],
+                    SlowPart::Transfer => vec![
+                        "Kiểm tra băng thông: nội dung tải về chiếm phần lớn thời gian".to_string(),
+                    ],
+                };
+                solutions.extend(Metric::TotalTime.solutions());
+                
                 issues.push(DiagnosticIssue {
                     category: IssueCategory::Http,
                     severity: IssueSeverity::Warning,
                     title: "Tổng thời gian tải chậm".to_string(),
-                    description: format!("Tổng thời gian: {:.0}ms (nên < 3000ms)", tcp.total_time_ms),
-                    possible_causes: vec![
-                        "Server phản hồi chậm".to_string(),
-                        "Kết nối mạng không ổn định".to_string(),
-                        "Nhiều redirect".to_string(),
-                    ],
-                    solutions: vec![
-                        "Kiểm tra tốc độ mạng của bạn".to_string(),
-                        "Thử vào lúc khác trong ngày".to_string(),
-                    ],
+                    description: format!(
+                        "Tổng thời gian: {:.0}ms (nên < {:.0}ms). {}",
+                        tcp.total_time_ms, thresholds.total_ms, breakdown
+                    ),
+                    possible_causes: Metric::TotalTime.possible_causes(),
+                    solutions,
                 });
-                score -= 15;
+                score.deduct_for(&issues, 15);
             } else if tcp.total_time_ms > 1000.0 {
-                score -= 5;
+                score.deduct(5, "Tổng thời gian trên 1s");
             }
             
             // Check HTTP code
@@ -444,83 +2979,552 @@ pub fn analyze_results(
                         "Kiểm tra trang status của dịch vụ".to_string(),
                     ],
                 });
-                score -= 20;
+                score.deduct_for(&issues, 20);
+            }
+        }
+    }
+    
+    // Analyze security headers: HSTS only matters once the site is served over TLS
+    if let Some(tcp) = tcp.as_ref().filter(|t| t.ssl_time_ms > 0.0) {
+        if let Some(headers) = tcp.security_headers.as_ref().filter(|h| h.hsts.is_none()) {
+            let mut missing_others: Vec<&str> = Vec::new();
+            if headers.content_security_policy.is_none() {
+                missing_others.push("Content-Security-Policy");
+            }
+            if headers.x_frame_options.is_none() {
+                missing_others.push("X-Frame-Options");
+            }
+            if headers.x_content_type_options.is_none() {
+                missing_others.push("X-Content-Type-Options");
+            }
+            
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Http,
+                severity: IssueSeverity::Info,
+                title: "Website HTTPS không gửi HSTS".to_string(),
+                description: if missing_others.is_empty() {
+                    "Server không gửi header Strict-Transport-Security, trình duyệt vẫn có thể bị hạ cấp xuống HTTP".to_string()
+                } else {
+                    format!(
+                        "Server không gửi header Strict-Transport-Security, trình duyệt vẫn có thể bị hạ cấp xuống HTTP. Cũng thiếu: {}",
+                        missing_others.join(", ")
+                    )
+                },
+                possible_causes: vec![
+                    "Cấu hình web server/CDN chưa bật HSTS".to_string(),
+                ],
+                solutions: vec![
+                    "Quản trị website nên thêm header Strict-Transport-Security: max-age=31536000".to_string(),
+                    "Luôn truy cập website bằng https://".to_string(),
+                ],
+            });
+        }
+    }
+    
+    // Analyze the redirect chain: an https:// request must not end up on plain HTTP
+    if let Some(tcp) = tcp {
+        let chain = &tcp.redirect_chain;
+        if chain.first().is_some_and(|u| u.starts_with("https://")) {
+            let ends_on_http = chain.last().is_some_and(|u| u.starts_with("http://"));
+            let passes_http = chain.iter().any(|u| u.starts_with("http://"));
+            
+            if ends_on_http || passes_http {
+                issues.push(DiagnosticIssue {
+                    category: IssueCategory::Ssl,
+                    severity: IssueSeverity::Warning,
+                    title: if ends_on_http {
+                        "Redirect hạ cấp từ HTTPS xuống HTTP".to_string()
+                    } else {
+                        "Chuỗi redirect đi qua HTTP không mã hóa".to_string()
+                    },
+                    description: format!(
+                        "{}: {}",
+                        if ends_on_http {
+                            "Trang cuối cùng được tải qua HTTP không mã hóa"
+                        } else {
+                            "Một bước redirect dùng HTTP trước khi quay lại HTTPS"
+                        },
+                        chain.join(" → ")
+                    ),
+                    possible_causes: vec![
+                        "Cấu hình redirect của website/CDN trỏ sang http://".to_string(),
+                        "Load balancer kết thúc TLS rồi tạo URL redirect bằng http://".to_string(),
+                        "Proxy/captive portal trên mạng chặn và chuyển hướng request".to_string(),
+                    ],
+                    solutions: vec![
+                        "Quản trị website nên sửa redirect để luôn dùng https://".to_string(),
+                        "Bật HSTS để trình duyệt không bao giờ dùng HTTP".to_string(),
+                        "Tránh nhập thông tin nhạy cảm trên trang này cho đến khi được sửa".to_string(),
+                    ],
+                });
+                score.deduct_for(&issues, if ends_on_http { 10 } else { 5 });
+            }
+        }
+    }
+    
+    // Analyze per-IP backends: one bad IP among healthy ones
+    if let Some(per_ip) = per_ip_tcp.as_ref().filter(|m| m.len() > 1) {
+        let healthy: Vec<(&String, &TcpResult)> = per_ip.iter().filter(|(_, r)| r.is_healthy()).collect();
+        let failing: Vec<&String> = per_ip
+            .iter()
+            .filter(|(_, r)| !r.is_healthy())
+            .map(|(ip, _)| ip)
+            .collect();
+        
+        if !healthy.is_empty() && !failing.is_empty() {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Tcp,
+                severity: IssueSeverity::Warning,
+                title: "Một số IP backend bị lỗi".to_string(),
+                description: format!(
+                    "{}/{} IP của domain không phản hồi đúng: {}. Người dùng sẽ gặp lỗi ngẫu nhiên tùy IP được chọn",
+                    failing.len(),
+                    per_ip.len(),
+                    failing.iter().map(|ip| ip.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+                possible_causes: vec![
+                    "Một server sau load balancer bị down".to_string(),
+                    "DNS còn trỏ đến IP cũ đã ngừng hoạt động".to_string(),
+                    "Routing đến một dải IP bị lỗi".to_string(),
+                ],
+                solutions: vec![
+                    "Báo cho quản trị website danh sách IP bị lỗi".to_string(),
+                    "Tạm thời thêm IP hoạt động tốt vào file /etc/hosts".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 15);
+        }
+        
+        // Slow outlier: more than twice the fastest healthy IP (and 200ms+ slower)
+        if healthy.len() > 1 {
+            let fastest = healthy
+                .iter()
+                .map(|(_, r)| r.total_time_ms)
+                .fold(f64::INFINITY, f64::min);
+            let slow: Vec<String> = healthy
+                .iter()
+                .filter(|(_, r)| r.total_time_ms > fastest * 2.0 && r.total_time_ms - fastest > 200.0)
+                .map(|(ip, r)| format!("{} ({:.0}ms)", ip, r.total_time_ms))
+                .collect();
+            
+            if !slow.is_empty() {
+                issues.push(DiagnosticIssue {
+                    category: IssueCategory::Tcp,
+                    severity: IssueSeverity::Warning,
+                    title: "Một IP backend chậm hơn đáng kể".to_string(),
+                    description: format!(
+                        "IP chậm: {}, trong khi IP nhanh nhất chỉ {:.0}ms",
+                        slow.join(", "),
+                        fastest
+                    ),
+                    possible_causes: vec![
+                        "Server backend quá tải".to_string(),
+                        "IP nằm ở datacenter xa hơn".to_string(),
+                        "Đường truyền đến một dải IP bị nghẽn".to_string(),
+                    ],
+                    solutions: vec![
+                        "Báo cho quản trị website để kiểm tra server tương ứng".to_string(),
+                        "Kiểm tra routing đến IP chậm bằng traceroute".to_string(),
+                    ],
+                });
+                score.deduct_for(&issues, 5);
             }
         }
     }
     
+    // Analyze SSL certificate chain
+    if let Some(cert) = ssl_cert {
+        if !cert.chain_complete {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Ssl,
+                severity: IssueSeverity::Warning,
+                title: "Certificate chain không đầy đủ".to_string(),
+                description: format!(
+                    "Server không gửi đủ certificate trung gian ({}). Một số client sẽ thất bại khi SSL handshake dù trình duyệt vẫn mở được",
+                    cert.verify_message.as_deref().unwrap_or("thiếu issuer")
+                ),
+                possible_causes: vec![
+                    "Server chỉ cấu hình certificate lá, thiếu intermediate certificate".to_string(),
+                    "Sai thứ tự certificate trong file chain".to_string(),
+                ],
+                solutions: vec![
+                    "Quản trị server cần cấu hình file fullchain (leaf + intermediate)".to_string(),
+                    "Kiểm tra lại bằng SSL Labs hoặc `openssl s_client -showcerts`".to_string(),
+                    "Trình duyệt có thể tự tải intermediate, nhưng curl/app/API client thì không".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 10);
+        }
+    }
+    
+    // Analyze the local clock (against the server's Date header) and certificate validity
+    {
+        let now = chrono::Utc::now();
+        let clock_skew = tcp
+            .as_ref()
+            .and_then(|t| t.clock_skew_secs)
+            .filter(|s| s.abs() > MAX_CLOCK_SKEW_SECS);
+        let verdict = judge_cert_clock(
+            now,
+            clock_skew,
+            ssl_cert.as_ref().and_then(|c| c.not_before),
+            ssl_cert.as_ref().and_then(|c| c.not_after),
+        );
+        let fmt = |t: Option<chrono::DateTime<chrono::Utc>>| {
+            t.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_else(|| "?".to_string())
+        };
+        
+        if verdict.clock_wrong {
+            let clock_text = match clock_skew {
+                Some(skew) => format!(
+                    "Giờ hệ thống là {}, {} giờ server {}",
+                    now.format("%Y-%m-%d %H:%M UTC"),
+                    if skew > 0 { "nhanh hơn" } else { "chậm hơn" },
+                    format_skew(skew)
+                ),
+                None => format!("Giờ hệ thống là {}", now.format("%Y-%m-%d %H:%M UTC")),
+            };
+            let cert_text = match ssl_cert {
+                Some(cert) => format!(
+                    ", certificate có hiệu lực từ {} đến {}. Lỗi SSL nhiều khả năng do đồng hồ máy bạn, không phải do server",
+                    fmt(cert.not_before),
+                    fmt(cert.not_after)
+                ),
+                None => ". Kết nối HTTPS có thể thất bại vì certificate trông như hết hạn hoặc chưa có hiệu lực".to_string(),
+            };
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Ssl,
+                severity: IssueSeverity::Warning,
+                title: "Đồng hồ hệ thống có thể bị sai".to_string(),
+                description: format!("{}{}", clock_text, cert_text),
+                possible_causes: vec![
+                    "Đồng hồ/múi giờ của máy bị đặt sai".to_string(),
+                    "Pin CMOS hết khiến đồng hồ bị reset".to_string(),
+                    "Đồng bộ thời gian (NTP) bị tắt".to_string(),
+                ],
+                solutions: vec![
+                    "Bật tự động đồng bộ ngày giờ trong cài đặt hệ thống".to_string(),
+                    "Kiểm tra lại múi giờ của máy".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 10);
+        }
+        if let Some(cert) = ssl_cert.as_ref().filter(|_| verdict.expired) {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Ssl,
+                severity: IssueSeverity::Error,
+                title: "Certificate đã hết hạn".to_string(),
+                description: format!(
+                    "Certificate của server hết hạn lúc {} (giờ hệ thống: {})",
+                    fmt(cert.not_after),
+                    now.format("%Y-%m-%d %H:%M UTC")
+                ),
+                possible_causes: vec![
+                    "Quản trị website chưa gia hạn certificate".to_string(),
+                    "Tự động gia hạn (ví dụ Let's Encrypt) bị lỗi".to_string(),
+                    "Ít gặp hơn: đồng hồ máy bạn đang chạy nhanh".to_string(),
+                ],
+                solutions: vec![
+                    "Báo cho quản trị website gia hạn certificate".to_string(),
+                    "Kiểm tra ngày giờ hệ thống có đúng không".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 30);
+        }
+    }
+    
+    // Analyze raw TCP port check
+    if let Some(port_check) = port_check {
+        if !port_check.connected {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Tcp,
+                severity: IssueSeverity::Error,
+                title: format!("Không thể kết nối port {}", port_check.port),
+                description: format!(
+                    "Kết nối TCP đến {}:{} thất bại: {}",
+                    port_check.host,
+                    port_check.port,
+                    port_check.error.as_deref().unwrap_or("không rõ lý do")
+                ),
+                possible_causes: vec![
+                    "Dịch vụ không chạy trên port này".to_string(),
+                    format!("Port {} bị firewall hoặc ISP chặn", port_check.port),
+                    "Server không hoạt động".to_string(),
+                ],
+                solutions: vec![
+                    "Kiểm tra lại host và port của dịch vụ".to_string(),
+                    "Thử từ mạng khác để loại trừ việc bị chặn".to_string(),
+                    "Liên hệ quản trị dịch vụ".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 50);
+        } else if port_check.connect_time_ms > thresholds.connect_ms {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Tcp,
+                severity: IssueSeverity::Warning,
+                title: "TCP Connect chậm".to_string(),
+                description: format!(
+                    "Thời gian TCP connect đến port {}: {:.0}ms (nên < {:.0}ms)",
+                    port_check.port, port_check.connect_time_ms, thresholds.connect_ms
+                ),
+                possible_causes: Metric::TcpConnect.possible_causes(),
+                solutions: Metric::TcpConnect.solutions(),
+            });
+            score.deduct_for(&issues, 15);
+        }
+    }
+    
     // Analyze routing
-    if let Some(routing) = routing {
+    if let Some(routing) = routing.as_ref().filter(|r| r.needs_privileges) {
+        let guidance = if cfg!(target_os = "macos") {
+            "Chạy lại với quyền admin (sudo) hoặc kiểm tra quyền của /usr/sbin/traceroute".to_string()
+        } else {
+            "Cấp quyền cho traceroute: sudo setcap cap_net_raw+ep $(which traceroute), hoặc cài gói traceroute thay cho inetutils-traceroute".to_string()
+        };
+        issues.push(DiagnosticIssue {
+            category: IssueCategory::Routing,
+            severity: IssueSeverity::Warning,
+            title: "Traceroute cần quyền quản trị".to_string(),
+            description: format!(
+                "Traceroute đến {} không trả về hop nào, kể cả khi thử lại với giao thức khác. Hệ thống không cho phép gửi gói tin thô khi không có quyền admin",
+                routing.target_ip
+            ),
+            possible_causes: vec![
+                "Traceroute ở chế độ ICMP cần quyền root/admin".to_string(),
+                "Bản traceroute đang cài không hỗ trợ chế độ không cần quyền".to_string(),
+            ],
+            solutions: vec![
+                guidance,
+                "Các kiểm tra khác vẫn hợp lệ, chỉ thiếu thông tin đường đi".to_string(),
+            ],
+        });
+    }
+    if let Some(routing) = routing.as_ref().filter(|r| !r.needs_privileges) {
         let failed_hops = routing.hops.iter()
-            .filter(|h| h.ip_address == "*")
+            .filter(|h| h.rtt_ms_opt.is_none())
             .count();
         
-        let failed_percent = (failed_hops as f64 / routing.hops.len().max(1) as f64) * 100.0;
+        let failed_percent = (failed_hops as f64 / routing.hops.len().max(1) as f64) * 100.0;
+        
+        if let Some(loop_ip) = routing.loop_ips.first() {
+            let hop_numbers: Vec<String> = routing
+                .hop_numbers_of(loop_ip)
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Routing,
+                severity: IssueSeverity::Warning,
+                title: "Có thể có vòng lặp định tuyến".to_string(),
+                description: format!(
+                    "IP {} xuất hiện lại ở nhiều hop (hop {}){}. Gói tin có thể đang quay vòng giữa các router thay vì đi đến {}",
+                    loop_ip,
+                    hop_numbers.join(", "),
+                    if routing.loop_ips.len() > 1 {
+                        format!(", cùng với {}", routing.loop_ips[1..].join(", "))
+                    } else {
+                        String::new()
+                    },
+                    routing.target_ip
+                ),
+                possible_causes: vec![
+                    "Cấu hình định tuyến sai ở router của ISP hoặc nhà mạng trung gian".to_string(),
+                    "Hai router trỏ default route về nhau".to_string(),
+                    "Thay đổi định tuyến (BGP) đang hội tụ".to_string(),
+                ],
+                solutions: vec![
+                    format!("Gửi kết quả traceroute cho ISP, nêu rõ IP lặp lại {}", loop_ip),
+                    "Chạy lại sau vài phút để xem vòng lặp có tự hết không".to_string(),
+                    "Thử qua VPN hoặc mạng khác để tránh đoạn mạng bị lỗi".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 10);
+        } else if routing.destination_reached == Some(false) && routing.hit_hop_limit {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Routing,
+                severity: IssueSeverity::Warning,
+                title: "Traceroute vượt quá số hop tối đa".to_string(),
+                description: format!(
+                    "Sau {} hop traceroute vẫn chưa đến {}. Đường đi chưa đầy đủ: có thể có vòng lặp định tuyến hoặc đích chặn gói traceroute",
+                    MAX_TRACE_HOPS, routing.target_ip
+                ),
+                possible_causes: vec![
+                    "Vòng lặp định tuyến qua các router không phản hồi".to_string(),
+                    "Server hoặc firewall phía đích chặn gói traceroute".to_string(),
+                    "Đường đi dài hơn giới hạn hop".to_string(),
+                ],
+                solutions: vec![
+                    "Nếu website vẫn truy cập được, nhiều khả năng đích chỉ chặn traceroute".to_string(),
+                    "Thử tcptraceroute hoặc mtr để xem các hop cuối".to_string(),
+                    "Nếu website không truy cập được, gửi kết quả traceroute cho ISP".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 5);
+        }
+        
+        if failed_percent > 30.0 {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Routing,
+                severity: IssueSeverity::Warning,
+                title: "Nhiều hop không phản hồi".to_string(),
+                description: format!("{:.0}% các hop trong traceroute không phản hồi", failed_percent),
+                possible_causes: vec![
+                    "Các router chặn ICMP (bình thường)".to_string(),
+                    "Firewall chặn traceroute".to_string(),
+                    "Vấn đề routing".to_string(),
+                ],
+                solutions: vec![
+                    "Điều này có thể bình thường nếu website vẫn hoạt động".to_string(),
+                    "Thử tcptraceroute nếu cần chi tiết hơn".to_string(),
+                ],
+            });
+        }
+    }
+    
+    // Analyze whether the traced IP is the one actually connected to
+    let connected_ip = tcp
+        .as_ref()
+        .and_then(|t| t.remote_ip.clone())
+        .or_else(|| port_check.as_ref().filter(|p| p.connected).map(|p| p.remote_ip.clone()));
+    if let (Some(routing), Some(connected_ip)) = (routing, connected_ip) {
+        if !routing.target_ip.is_empty() && routing.target_ip != connected_ip {
+            let family_differs = ip_family(&routing.target_ip) != ip_family(&connected_ip);
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Routing,
+                severity: IssueSeverity::Info,
+                title: "Traceroute không đi đến IP đã kết nối".to_string(),
+                description: format!(
+                    "Traceroute chạy đến {} ({}) nhưng kết nối thực tế dùng {} ({}). Kết quả traceroute có thể không phản ánh đường đi mà trình duyệt đang dùng",
+                    routing.target_ip,
+                    ip_family(&routing.target_ip),
+                    connected_ip,
+                    ip_family(&connected_ip)
+                ),
+                possible_causes: if family_differs {
+                    vec![
+                        "Domain có cả IPv4 và IPv6, trình duyệt/curl ưu tiên IPv6".to_string(),
+                        "Đường IPv4 và IPv6 của ISP có thể khác nhau hoàn toàn".to_string(),
+                    ]
+                } else {
+                    vec![
+                        "Domain có nhiều IP (load balancing/CDN)".to_string(),
+                        "Website redirect sang host khác".to_string(),
+                    ]
+                },
+                solutions: vec![
+                    format!("Chạy traceroute đến {} để xem đường đi thực tế", connected_ip),
+                    "Nếu chỉ IPv6 gặp vấn đề, thử tắt IPv6 để so sánh".to_string(),
+                ],
+            });
+        }
+    }
+    
+    // Analyze NAT layers: private / CGNAT addresses before the internet
+    if let Some(routing) = routing {
+        let private_hops = nat_hops(&routing.hops, routing.public_ip.as_deref());
+        
+        let public_ip = routing
+            .public_ip
+            .as_ref()
+            .map(|ip| format!(". IP công cộng nhìn từ internet: {}", ip))
+            .unwrap_or_default();
         
-        if failed_percent > 30.0 {
+        if let Some(cgnat) = private_hops.iter().find(|ip| is_cgnat(ip)) {
             issues.push(DiagnosticIssue {
                 category: IssueCategory::Routing,
                 severity: IssueSeverity::Warning,
-                title: "Nhiều hop không phản hồi".to_string(),
-                description: format!("{:.0}% các hop trong traceroute không phản hồi", failed_percent),
+                title: "Mạng đang dùng CGNAT".to_string(),
+                description: format!(
+                    "Hop {} thuộc dải 100.64.0.0/10: ISP dùng chung một IP công cộng cho nhiều thuê bao{}. Không thể mở port từ internet, game có thể bị NAT strict, P2P/VoIP dễ lỗi kết nối",
+                    cgnat, public_ip
+                ),
                 possible_causes: vec![
-                    "Các router chặn ICMP (bình thường)".to_string(),
-                    "Firewall chặn traceroute".to_string(),
-                    "Vấn đề routing".to_string(),
+                    "ISP thiếu địa chỉ IPv4 nên dùng Carrier-grade NAT".to_string(),
+                    "Gói cước/mạng di động mặc định dùng CGNAT".to_string(),
                 ],
                 solutions: vec![
-                    "Điều này có thể bình thường nếu website vẫn hoạt động".to_string(),
-                    "Thử tcptraceroute nếu cần chi tiết hơn".to_string(),
+                    "Yêu cầu ISP cấp IP công cộng (hoặc IP tĩnh)".to_string(),
+                    "Dùng IPv6 nếu ISP hỗ trợ".to_string(),
+                    "Dùng VPN có hỗ trợ port forwarding nếu cần mở port".to_string(),
                 ],
             });
-        }
-        
-        if routing.total_hops > 20 {
+            score.deduct_for(&issues, 5);
+        } else if private_hops.len() > 1 {
             issues.push(DiagnosticIssue {
                 category: IssueCategory::Routing,
                 severity: IssueSeverity::Info,
-                title: "Nhiều hop".to_string(),
-                description: format!("Có {} hop đến đích (nhiều hơn bình thường)", routing.total_hops),
+                title: "Phát hiện Double NAT".to_string(),
+                description: format!(
+                    "Có {} router dùng IP nội bộ trước khi ra internet ({}){}. Port forwarding và UPnP thường không hoạt động qua double NAT",
+                    private_hops.len(),
+                    private_hops.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(" → "),
+                    public_ip
+                ),
                 possible_causes: vec![
-                    "Server ở xa".to_string(),
-                    "Routing không tối ưu".to_string(),
+                    "Cả modem của ISP và router riêng đều đang NAT".to_string(),
+                    "Router phụ/mesh đang chạy ở chế độ router thay vì access point".to_string(),
                 ],
                 solutions: vec![
-                    "Sử dụng VPN có thể giúp tối ưu routing".to_string(),
+                    "Chuyển modem của ISP sang chế độ bridge".to_string(),
+                    "Hoặc chuyển router phụ sang chế độ access point".to_string(),
+                    "Nếu cần mở port, cấu hình DMZ trên modem trỏ về router".to_string(),
                 ],
             });
-            score -= 5;
         }
     }
     
     // Analyze stability
     if let Some(stability) = stability {
-        if stability.success_rate < 100.0 {
-            if stability.success_rate < 80.0 {
+        let breakdown = stability_failure_breakdown(stability);
+        let network_failures = stability.timeouts + stability.connection_errors;
+        
+        if stability.success_rate < 100.0 && stability.http_errors > network_failures {
+            // The path works; the server itself answers with errors
+            let unstable = stability.success_rate < UNSTABLE_SUCCESS_RATE;
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Http,
+                severity: if unstable { IssueSeverity::Error } else { IssueSeverity::Warning },
+                title: "Server trả về lỗi".to_string(),
+                description: format!(
+                    "Tỉ lệ thành công: {:.0}% ({}). Kết nối mạng vẫn thông, lỗi đến từ phía server",
+                    stability.success_rate, breakdown
+                ),
+                possible_causes: vec![
+                    "Server/backend quá tải hoặc đang gặp sự cố".to_string(),
+                    "Server giới hạn số request (rate limiting)".to_string(),
+                    "Firewall/WAF phía server chặn request lặp lại".to_string(),
+                ],
+                solutions: vec![
+                    "Kiểm tra trang trạng thái hoặc log của server".to_string(),
+                    "Liên hệ quản trị viên của website/API".to_string(),
+                    "Giảm tần suất request (tăng khoảng nghỉ giữa các lần test)".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, if unstable { 30 } else { 10 });
+        } else if stability.success_rate < 100.0 {
+            if stability.success_rate < UNSTABLE_SUCCESS_RATE {
                 issues.push(DiagnosticIssue {
                     category: IssueCategory::Stability,
                     severity: IssueSeverity::Error,
                     title: "Kết nối không ổn định".to_string(),
-                    description: format!("Chỉ {:.0}% request thành công", stability.success_rate),
-                    possible_causes: vec![
-                        "Mạng không ổn định".to_string(),
-                        "Tín hiệu WiFi yếu".to_string(),
-                        "ISP có vấn đề".to_string(),
-                        "Server bị quá tải".to_string(),
-                    ],
-                    solutions: vec![
-                        "Di chuyển gần router WiFi hơn hoặc dùng cáp LAN".to_string(),
-                        "Khởi động lại modem/router".to_string(),
-                        "Liên hệ ISP nếu vấn đề kéo dài".to_string(),
-                    ],
+                    description: format!(
+                        "Chỉ {:.0}% request thành công ({})",
+                        stability.success_rate, breakdown
+                    ),
+                    possible_causes: Metric::SuccessRate.possible_causes(),
+                    solutions: Metric::SuccessRate.solutions(),
                 });
-                score -= 30;
+                score.deduct_for(&issues, 30);
             } else {
                 issues.push(DiagnosticIssue {
                     category: IssueCategory::Stability,
                     severity: IssueSeverity::Warning,
                     title: "Có packet loss".to_string(),
-                    description: format!("Tỉ lệ thành công: {:.0}%", stability.success_rate),
+                    description: format!(
+                        "Tỉ lệ thành công: {:.0}% ({})",
+                        stability.success_rate, breakdown
+                    ),
                     possible_causes: vec![
                         "Nghẽn mạng tạm thời".to_string(),
                         "Tín hiệu WiFi không ổn định".to_string(),
@@ -530,27 +3534,273 @@ pub fn analyze_results(
                         "Thử lại sau vài phút".to_string(),
                     ],
                 });
-                score -= 10;
+                score.deduct_for(&issues, 10);
+            }
+        }
+        
+        // DNS failover mid-test: failures may come from a dead backend
+        if stability.ip_changes > 0 {
+            let inconsistent = stability.success_rate < 100.0;
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Dns,
+                severity: if inconsistent { IssueSeverity::Warning } else { IssueSeverity::Info },
+                title: "IP của domain thay đổi trong lúc test".to_string(),
+                description: format!(
+                    "DNS trả về IP khác {} lần qua {} lần thử ({})",
+                    stability.ip_changes,
+                    stability.total_tests,
+                    stability.seen_ips.join(", ")
+                ),
+                possible_causes: vec![
+                    "Site dùng DNS failover/load balancing theo DNS".to_string(),
+                    "TTL của bản ghi DNS rất ngắn".to_string(),
+                    "Một backend đang lỗi nên DNS chuyển sang IP khác".to_string(),
+                ],
+                solutions: vec![
+                    "So sánh kết quả theo từng IP để tìm backend lỗi".to_string(),
+                    "Báo cho quản trị viên website nếu request thất bại trùng lúc đổi IP".to_string(),
+                ],
+            });
+            if inconsistent {
+                score.deduct_for(&issues, 5);
             }
         }
         
         // Check jitter
-        if stability.jitter_ms > 100.0 {
+        if stability.jitter_ms > thresholds.jitter_ms {
             issues.push(DiagnosticIssue {
                 category: IssueCategory::Stability,
                 severity: IssueSeverity::Warning,
                 title: "Jitter cao".to_string(),
-                description: format!("Độ biến thiên thời gian phản hồi: {:.0}ms", stability.jitter_ms),
+                description: format!(
+                    "Độ biến thiên thời gian phản hồi: {:.0}ms (nên < {:.0}ms)",
+                    stability.jitter_ms, thresholds.jitter_ms
+                ),
+                possible_causes: Metric::Jitter.possible_causes(),
+                solutions: Metric::Jitter.solutions(),
+            });
+            score.deduct_for(&issues, 5);
+        }
+    }
+    
+    // Analyze latency growth with packet size
+    if let Some(sweep) = latency_by_size {
+        let smallest = sweep.samples.first();
+        let largest = sweep.samples.last().filter(|_| sweep.samples.len() > 1);
+        
+        if full_mtu_blocked(sweep) {
+            let probe = sweep.mtu_probe.as_ref();
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Routing,
+                severity: IssueSeverity::Warning,
+                title: "Gói tin 1500 bytes không đi qua được (MTU < 1500)".to_string(),
+                description: if probe.is_some_and(|p| p.too_large) {
+                    format!(
+                        "Ping {} bytes không phân mảnh bị từ chối: message too long",
+                        FULL_MTU_PAYLOAD
+                    )
+                } else {
+                    format!(
+                        "Ping {} bytes không phân mảnh mất 100% trong khi gói nhỏ vẫn có phản hồi",
+                        FULL_MTU_PAYLOAD
+                    )
+                },
+                possible_causes: vec![
+                    "Đường hầm VPN/PPPoE làm giảm MTU".to_string(),
+                    "Thiết bị trên đường truyền chặn ICMP \"Fragmentation Needed\" (PMTUD blackhole)".to_string(),
+                ],
+                solutions: vec![
+                    "Thử giảm MTU trên router (ví dụ 1492 cho PPPoE)".to_string(),
+                    "Bật MSS clamping trên router".to_string(),
+                    "Kiểm tra cấu hình VPN".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 10);
+        } else if let (Some(small), Some(large)) = (smallest, largest) {
+            let small_ok = small.avg_rtt_ms.is_some();
+            // Serialization of 1400 bytes costs only a few ms on any usable link
+            let jump = match (small.avg_rtt_ms, large.avg_rtt_ms) {
+                (Some(s), Some(l)) => l > s * 2.0 && l - s > 30.0,
+                _ => false,
+            };
+            let large_lost = small_ok && large.packet_loss_percent - small.packet_loss_percent >= 50.0;
+            
+            if jump || large_lost {
+                issues.push(DiagnosticIssue {
+                    category: IssueCategory::Routing,
+                    severity: IssueSeverity::Warning,
+                    title: "Độ trễ tăng mạnh với gói tin lớn".to_string(),
+                    description: if large_lost {
+                        format!(
+                            "Gói {} bytes mất {:.0}% trong khi gói {} bytes mất {:.0}%",
+                            large.payload_bytes,
+                            large.packet_loss_percent,
+                            small.payload_bytes,
+                            small.packet_loss_percent
+                        )
+                    } else {
+                        format!(
+                            "RTT gói {} bytes: {:.0}ms, gói {} bytes: {:.0}ms",
+                            small.payload_bytes,
+                            small.avg_rtt_ms.unwrap_or(0.0),
+                            large.payload_bytes,
+                            large.avg_rtt_ms.unwrap_or(0.0)
+                        )
+                    },
+                    possible_causes: vec![
+                        "MTU trên đường truyền nhỏ hơn 1500, gói tin bị phân mảnh".to_string(),
+                        "ISP hoặc thiết bị mạng giới hạn băng thông (traffic shaping)".to_string(),
+                        "Đường hầm VPN/PPPoE làm giảm MTU".to_string(),
+                    ],
+                    solutions: vec![
+                        "Thử giảm MTU trên router (ví dụ 1492 cho PPPoE)".to_string(),
+                        "Kiểm tra cấu hình VPN".to_string(),
+                        "Liên hệ ISP nếu vấn đề chỉ xảy ra với gói tin lớn".to_string(),
+                    ],
+                });
+                score.deduct_for(&issues, 10);
+            }
+        }
+    }
+    
+    // Real-time suitability (informational, does not affect the score)
+    let unsuitable: Vec<String> = evaluate_realtime_suitability(tcp, port_check, stability)
+        .into_iter()
+        .filter(|v| v.suitable == Some(false))
+        .map(|v| format!("{} ({})", use_case_name(v.use_case), v.requirement))
+        .collect();
+    
+    if !unsuitable.is_empty() {
+        issues.push(DiagnosticIssue {
+            category: IssueCategory::Stability,
+            severity: IssueSeverity::Info,
+            title: "Kết nối chưa phù hợp cho ứng dụng thời gian thực".to_string(),
+            description: format!(
+                "Không đạt yêu cầu cho: {}",
+                unsuitable.join(", ")
+            ),
+            possible_causes: vec![
+                "Độ trễ cao do khoảng cách đến server".to_string(),
+                "Jitter/mất gói do WiFi hoặc mạng nghẽn".to_string(),
+            ],
+            solutions: vec![
+                "Sử dụng cáp LAN thay vì WiFi".to_string(),
+                "Chọn server gần hơn về mặt địa lý".to_string(),
+                "Bật QoS trên router cho ứng dụng thời gian thực".to_string(),
+            ],
+        });
+    }
+    
+    // Analyze IP reputation
+    if let Some(blocklist) = blocklist {
+        let (own, destination): (Vec<&BlocklistListing>, Vec<&BlocklistListing>) =
+            blocklist.listings.iter().partition(|l| l.is_own_ip);
+        
+        if !destination.is_empty() {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Dns,
+                severity: IssueSeverity::Warning,
+                title: "IP đích nằm trong blocklist".to_string(),
+                description: format!(
+                    "IP của server bị liệt kê: {}",
+                    describe_listings(&destination)
+                ),
+                possible_causes: vec![
+                    "Server từng gửi spam hoặc bị chiếm quyền".to_string(),
+                    "IP dùng chung với dịch vụ có uy tín kém".to_string(),
+                ],
+                solutions: vec![
+                    "Mạng/firewall của bạn có thể chặn IP này - thử mạng khác".to_string(),
+                    "Báo cho quản trị website để gỡ khỏi blocklist".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 10);
+        }
+        
+        if !own.is_empty() {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Dns,
+                severity: IssueSeverity::Warning,
+                title: "IP của bạn nằm trong blocklist".to_string(),
+                description: format!(
+                    "IP public của bạn bị liệt kê: {}",
+                    describe_listings(&own)
+                ),
+                possible_causes: vec![
+                    "IP động của ISP nằm trong dải bị chặn gửi mail".to_string(),
+                    "Thiết bị trong mạng bị nhiễm malware gửi spam".to_string(),
+                    "IP dùng chung (CGNAT) với người dùng khác".to_string(),
+                ],
+                solutions: vec![
+                    "Một số server (đặc biệt là mail server) có thể từ chối kết nối từ bạn".to_string(),
+                    "Quét malware các thiết bị trong mạng".to_string(),
+                    "Liên hệ ISP hoặc yêu cầu gỡ IP trên trang của blocklist".to_string(),
+                ],
+            });
+            score.deduct_for(&issues, 10);
+        }
+    }
+    
+    // Analyze intermediary proxies
+    if let Some(proxy) = proxy.as_ref().filter(|p| p.proxy_detected) {
+        let mut evidence: Vec<String> = Vec::new();
+        if proxy.content_modified {
+            evidence.push(format!(
+                "nội dung kiểm tra bị thay đổi (HTTP {}, mong đợi {})",
+                proxy.http_code, PROXY_CONTROL_STATUS
+            ));
+        }
+        if !proxy.proxy_headers.is_empty() {
+            evidence.push(format!("header proxy: {}", proxy.proxy_headers.join("; ")));
+        }
+        
+        issues.push(DiagnosticIssue {
+            category: IssueCategory::Http,
+            severity: IssueSeverity::Warning,
+            title: "Phát hiện proxy trung gian".to_string(),
+            description: format!(
+                "Lưu lượng HTTP đi qua proxy trong suốt ({}). Kết quả thời gian và nội dung có thể bị ảnh hưởng bởi thiết bị trung gian",
+                evidence.join(", ")
+            ),
+            possible_causes: vec![
+                "Mạng công ty/trường học dùng proxy lọc nội dung".to_string(),
+                "Captive portal (WiFi khách sạn, quán cafe) chưa đăng nhập".to_string(),
+                "ISP chèn quảng cáo hoặc cache lưu lượng HTTP".to_string(),
+            ],
+            solutions: vec![
+                "Đăng nhập captive portal nếu đang dùng WiFi công cộng".to_string(),
+                "Ưu tiên HTTPS để tránh nội dung bị sửa đổi".to_string(),
+                "Thử lại trên mạng khác hoặc qua VPN để so sánh kết quả".to_string(),
+            ],
+        });
+        score.deduct_for(&issues, 5);
+    }
+    
+    // Analyze upload vs download asymmetry
+    if let Some(bandwidth) = bandwidth {
+        if let Some(ratio) = bandwidth.upload_ratio.filter(|r| *r < ASYMMETRIC_UPLOAD_RATIO) {
+            issues.push(DiagnosticIssue {
+                category: IssueCategory::Tcp,
+                severity: IssueSeverity::Warning,
+                title: "Tốc độ upload thấp hơn nhiều so với download".to_string(),
+                description: format!(
+                    "Upload {:.2} KB/s chỉ bằng {:.1}% download {:.2} KB/s. Tốc độ internet có vẻ tốt nhưng gọi video, chơi game, chia sẻ màn hình và sao lưu lên cloud có thể bị giật, trễ",
+                    bandwidth.upload_kbps,
+                    ratio * 100.0,
+                    bandwidth.download_kbps
+                ),
                 possible_causes: vec![
-                    "Mạng không ổn định".to_string(),
-                    "Có thiết bị khác đang dùng băng thông".to_string(),
+                    "Gói cước ADSL/cáp bất đối xứng với upload rất thấp".to_string(),
+                    "Thiết bị khác trong mạng đang upload (sao lưu, camera, torrent)".to_string(),
+                    "Sóng WiFi yếu ở phía thiết bị gửi".to_string(),
                 ],
                 solutions: vec![
-                    "Giảm số thiết bị sử dụng mạng cùng lúc".to_string(),
-                    "Sử dụng cáp LAN thay vì WiFi".to_string(),
+                    "Tạm dừng sao lưu/đồng bộ cloud khi gọi video hoặc chơi game".to_string(),
+                    "Bật QoS trên router để ưu tiên lưu lượng thời gian thực".to_string(),
+                    "Nâng cấp gói cước hoặc chuyển sang cáp quang có upload đối xứng".to_string(),
                 ],
             });
-            score -= 5;
+            score.deduct_for(&issues, 5);
         }
     }
     
@@ -570,23 +3820,651 @@ pub fn analyze_results(
             recommendations.push("Kiểm tra tín hiệu WiFi và cân nhắc sử dụng cáp LAN.".to_string());
         }
         
-        if score < 50 {
+        if score.value < 50 {
             recommendations.push("Kết nối có nhiều vấn đề - cân nhắc sử dụng VPN hoặc liên hệ ISP.".to_string());
         }
     }
     
-    // Determine overall status
-    let overall_status = if score >= 90 {
+    Analysis {
+        issues,
+        recommendations,
+        overall_status: status_for_score(score.value),
+        score_breakdown: score.breakdown,
+    }
+}
+
+/// Highest score still rated Poor
+const POOR_MAX_SCORE: i32 = 49;
+
+/// Overall status for a score out of 100
+fn status_for_score(score: i32) -> OverallStatus {
+    if score >= 90 {
         OverallStatus::Excellent
     } else if score >= 75 {
         OverallStatus::Good
-    } else if score >= 50 {
+    } else if score > POOR_MAX_SCORE {
         OverallStatus::Acceptable
     } else if score >= 25 {
         OverallStatus::Poor
     } else {
         OverallStatus::Failed
-    };
+    }
+}
+
+/// Strict mode: any Warning (or Error) issue downgrades the status to at least Poor
+/// 
+/// Applied on top of `analyze_results`. The cap is added to the breakdown as
+/// the deduction down to the top of the Poor range, so the breakdown still
+/// adds up to the status shown.
+pub fn apply_strict_mode(analysis: &mut Analysis) {
+    let has_warning = analysis
+        .issues
+        .iter()
+        .any(|i| matches!(i.severity, IssueSeverity::Warning | IssueSeverity::Error));
+    let score = 100 + analysis.score_breakdown.iter().map(|(_, points)| points).sum::<i32>();
     
-    (issues, recommendations, overall_status)
+    if has_warning && score > POOR_MAX_SCORE {
+        analysis.score_breakdown.push((
+            "Chế độ nghiêm ngặt: có cảnh báo nên xếp loại tối đa là POOR".to_string(),
+            POOR_MAX_SCORE - score,
+        ));
+        analysis.overall_status = OverallStatus::Poor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stability(avg_time_ms: f64) -> Option<StabilityResult> {
+        serde_json::from_value(serde_json::json!({
+            "total_tests": 10,
+            "successful_tests": 10,
+            "success_rate": 100.0,
+            "min_time_ms": avg_time_ms,
+            "avg_time_ms": avg_time_ms,
+            "max_time_ms": avg_time_ms,
+            "jitter_ms": 0.0,
+        }))
+        .ok()
+    }
+
+    #[test]
+    fn quality_grades_stability_round_trip() {
+        let quality = summarize_quality(&None, &stability(50.0), &None).unwrap();
+
+        assert_eq!(quality.rpm, Some(1200));
+        assert_eq!(quality.grade, Some(QualityGrade::A));
+    }
+
+    #[test]
+    fn quality_uses_bandwidth_throughput_and_loaded_latency() {
+        let bandwidth = Some(BandwidthResult {
+            server: SPEED_TEST_SERVER.to_string(),
+            download_kbps: 12_000.0,
+            upload_kbps: 3_000.0,
+            upload_ratio: Some(0.25),
+            loaded_latency_ms: Some(120.0),
+        });
+        let quality = summarize_quality(&None, &stability(50.0), &bandwidth).unwrap();
+
+        assert_eq!(quality.loaded_latency_ms, Some(120.0));
+        assert_eq!(quality.throughput_kbps, Some(12_000.0));
+        assert_eq!(quality.rpm, Some(500));
+        assert_eq!(quality.grade, Some(QualityGrade::C));
+    }
+
+    #[test]
+    fn quality_without_measurements_is_none() {
+        assert!(summarize_quality(&None, &None, &None).is_none());
+    }
+
+    #[test]
+    fn curl_error_with_zeroed_timing_is_a_failed_result() {
+        let stdout = r#"{"dns":0.000000,"connect":0.000000,"ssl":0.000000,"ttfb":0.000000,"total":0.000000,"http_code":"000","speed":0}"#;
+        let result = parse_curl_timing(Some(7), stdout);
+
+        assert_eq!(result.failure_reason, Some(curl_error_reason(7)));
+        assert_eq!(result.http_code, 0);
+        assert_eq!(result.total_time_ms, 0.0);
+    }
+
+    #[test]
+    fn curl_error_with_truncated_timing_is_a_failed_result() {
+        let result = parse_curl_timing(Some(7), r#"{"dns":0.001,"connect":"#);
+
+        assert_eq!(result.failure_reason, Some(curl_error_reason(7)));
+        assert_eq!(result.http_code, 0);
+        assert_eq!(result.total_time_ms, 0.0);
+    }
+
+    #[test]
+    fn unreadable_timing_after_success_is_a_failed_result() {
+        let result = parse_curl_timing(Some(0), "<html>garbage</html>");
+
+        assert!(result.failure_reason.is_some_and(|r| !r.contains("parse")));
+        assert_eq!(result.http_code, 0);
+        assert_eq!(result.total_time_ms, 0.0);
+    }
+
+    #[test]
+    fn connect_to_only_applies_to_the_presented_host() {
+        let target = TargetOverride::from_config(Some("203.0.113.7"), None, "example.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.curl_args(), vec!["--connect-to", "example.com::203.0.113.7:"]);
+
+        let target = TargetOverride::from_config(Some("2001:db8::7"), Some("origin.example.com"), "example.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.curl_args(), vec!["--connect-to", "origin.example.com::[2001:db8::7]:"]);
+    }
+
+    fn utc(s: &str) -> chrono::DateTime<chrono::Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn cert_dates_parse_openssl_output() {
+        let output = "notBefore=Jan  5 00:00:00 2026 GMT\nnotAfter=Apr  5 23:59:59 2026 GMT\n";
+        assert_eq!(
+            parse_cert_dates(output),
+            Some((utc("2026-01-05T00:00:00Z"), utc("2026-04-05T23:59:59Z")))
+        );
+        assert_eq!(parse_cert_dates("notBefore=garbage\n"), None);
+    }
+
+    #[test]
+    fn date_header_comes_from_the_final_response() {
+        let dump = "HTTP/1.1 301 Moved Permanently\r\nDate: Mon, 01 Jan 2024 00:00:00 GMT\r\n\r\nHTTP/2 200\r\ndate: Thu, 15 Oct 2026 06:00:00 GMT\r\n\r\n";
+        assert_eq!(parse_date_header(dump), Some(utc("2026-10-15T06:00:00Z")));
+    }
+
+    #[test]
+    fn clock_behind_is_blamed_on_the_clock() {
+        // Cert valid from 2026-01-05, local clock a year behind the server
+        let verdict = judge_cert_clock(
+            utc("2025-10-15T06:00:00Z"),
+            Some(-365 * 86_400),
+            Some(utc("2026-01-05T00:00:00Z")),
+            Some(utc("2026-12-05T00:00:00Z")),
+        );
+        assert_eq!(verdict, CertClockVerdict { clock_wrong: true, expired: false });
+    }
+
+    #[test]
+    fn clock_ahead_is_blamed_on_the_clock() {
+        // Local clock two years ahead: the valid cert looks expired locally
+        let verdict = judge_cert_clock(
+            utc("2028-10-15T06:00:00Z"),
+            Some(2 * 365 * 86_400 + 86_400),
+            Some(utc("2026-01-05T00:00:00Z")),
+            Some(utc("2026-12-05T00:00:00Z")),
+        );
+        assert_eq!(verdict, CertClockVerdict { clock_wrong: true, expired: false });
+    }
+
+    #[test]
+    fn expired_cert_with_a_correct_clock_is_blamed_on_the_server() {
+        let verdict = judge_cert_clock(
+            utc("2026-10-15T06:00:00Z"),
+            Some(2),
+            Some(utc("2025-01-05T00:00:00Z")),
+            Some(utc("2026-04-05T00:00:00Z")),
+        );
+        assert_eq!(verdict, CertClockVerdict { clock_wrong: false, expired: true });
+    }
+
+    #[test]
+    fn expired_cert_is_reported_even_with_a_skewed_clock() {
+        // Expired by the server's clock too
+        let verdict = judge_cert_clock(
+            utc("2026-10-20T06:00:00Z"),
+            Some(5 * 86_400),
+            Some(utc("2025-01-05T00:00:00Z")),
+            Some(utc("2026-04-05T00:00:00Z")),
+        );
+        assert_eq!(verdict, CertClockVerdict { clock_wrong: true, expired: true });
+    }
+
+    fn trace(ips: &[&str]) -> Vec<RouteHop> {
+        ips.iter()
+            .enumerate()
+            .map(|(i, ip)| {
+                let rtt_ms_opt = (*ip != "*").then_some(10.0 + i as f64);
+                RouteHop {
+                    hop_number: i as u32 + 1,
+                    ip_address: ip.to_string(),
+                    hostname: None,
+                    rtt_ms: rtt_ms_opt.unwrap_or(0.0),
+                    rtt_ms_opt,
+                    packet_loss_percent: if rtt_ms_opt.is_none() { 100.0 } else { 0.0 },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn routing_loop_is_cut_at_the_first_repeat() {
+        let cycle = ["10.0.0.1", "10.1.1.1", "10.1.1.2"];
+        let ips: Vec<&str> = std::iter::once("192.168.1.1")
+            .chain(cycle.iter().copied().cycle().take(MAX_TRACE_HOPS as usize - 1))
+            .collect();
+        let routing = routing_from_hops("93.184.216.34", trace(&ips), 15_000.0, false);
+
+        assert_eq!(routing.loop_ips, vec!["10.0.0.1", "10.1.1.1", "10.1.1.2"]);
+        assert_eq!(routing.total_hops, 5);
+        assert_eq!(routing.hops.last().map(|h| h.hop_number), Some(5));
+        assert_eq!(routing.rtt_series.len(), 5);
+        assert!(routing.hit_hop_limit);
+        assert_eq!(routing.destination_reached, Some(false));
+    }
+
+    #[test]
+    fn repeats_on_a_trace_that_arrived_are_not_a_loop() {
+        let ips = ["192.168.1.1", "10.0.0.1", "10.1.1.1", "10.0.0.1", "93.184.216.34"];
+        let routing = routing_from_hops("93.184.216.34", trace(&ips), 900.0, false);
+
+        assert!(routing.loop_ips.is_empty());
+        assert_eq!(routing.total_hops, 5);
+        assert_eq!(routing.destination_reached, Some(true));
+        assert!(!routing.hit_hop_limit);
+    }
+
+    #[test]
+    fn back_to_back_answers_are_not_a_loop() {
+        let ips = ["192.168.1.1", "10.0.0.1", "10.0.0.1", "10.1.1.1", "*", "*"];
+        let routing = routing_from_hops("93.184.216.34", trace(&ips), 6_000.0, false);
+
+        assert!(routing.loop_ips.is_empty());
+        assert_eq!(routing.total_hops, 6);
+        assert_eq!(routing.destination_reached, Some(false));
+        assert!(!routing.hit_hop_limit);
+    }
+
+    #[test]
+    fn all_timeout_path_has_no_hop_latency() {
+        let routing = routing_from_hops("93.184.216.34", trace(&["*", "*", "*"]), 4_500.0, false);
+
+        assert_eq!(routing.total_hops, 3);
+        assert_eq!(routing.rtt_series, vec![None, None, None]);
+        assert_eq!(routing.avg_hop_latency_ms, None);
+        assert_eq!(routing.max_hop_latency_ms, None);
+    }
+
+    fn hop_rtts(hops: &[RouteHop]) -> Vec<Option<f64>> {
+        hops.iter().map(|h| h.rtt_ms_opt).collect()
+    }
+
+    #[test]
+    fn traceroute_skips_localized_header() {
+        let stdout = "traceroute vers 93.184.216.34 (93.184.216.34), 15 sauts max., paquets de 60 octets\n 1  192.168.1.1  1.204 ms\n 2  *\n 3  93.184.216.34  24.310 ms\n";
+        let hops = parse_traceroute_hops(stdout);
+
+        assert_eq!(hops.len(), 3);
+        assert_eq!(hop_rtts(&hops), vec![Some(1.204), None, Some(24.31)]);
+        assert_eq!(hops[1].ip_address, "*");
+    }
+
+    #[test]
+    fn traceroute_reads_decimal_comma_rtt() {
+        let stdout = " 1  192.168.1.1  0,842 ms\n 2  10.0.0.1  12,5 ms\n";
+        let hops = parse_traceroute_hops(stdout);
+
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hop_rtts(&hops), vec![Some(0.842), Some(12.5)]);
+    }
+
+    #[test]
+    fn traceroute_survives_non_utf8_output() {
+        let mut bytes = b"traceroute \xe0 93.184.216.34 (93.184.216.34), 15 hops max\n".to_vec();
+        bytes.extend_from_slice(b" 1  192.168.1.1  2.100 ms\n 2  10.0.0.1  9.870 ms \xff\n");
+        let hops = parse_traceroute_hops(&String::from_utf8_lossy(&bytes));
+
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hop_rtts(&hops), vec![Some(2.1), Some(9.87)]);
+    }
+
+    #[test]
+    fn traceroute_keeps_first_hop_without_header() {
+        // macOS prints the header on stderr, so stdout starts at hop 1
+        let stdout = " 1  192.168.1.1  3.001 ms\n 2  2001:db8::1  15.020 ms\n";
+        let hops = parse_traceroute_hops(stdout);
+
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0].hop_number, 1);
+        assert_eq!(hop_rtts(&hops), vec![Some(3.001), Some(15.02)]);
+    }
+
+    #[test]
+    fn answer_ttl_skips_dig_header() {
+        let output = "; <<>> DiG 9.18.18 <<>> example.com\n;; global options: +cmd\n;; Got answer:\n\n;; ANSWER SECTION:\nexample.com.\t\t3600\tIN\tA\t93.184.216.34\n";
+        assert_eq!(parse_answer_ttl(output), Some(3600));
+    }
+
+    #[test]
+    fn answer_ttl_reads_bare_answer_section() {
+        let output = "example.com.\t\t299\tIN\tA\t93.184.216.34\nexample.com.\t\t299\tIN\tA\t93.184.216.35\n";
+        assert_eq!(parse_answer_ttl(output), Some(299));
+    }
+
+    #[test]
+    fn answer_ttl_survives_non_utf8_output() {
+        let bytes = b";; comment \xe9\xff\nexample.com. 60 IN A 93.184.216.34\n";
+        assert_eq!(parse_answer_ttl(&String::from_utf8_lossy(bytes)), Some(60));
+    }
+
+    #[test]
+    fn answer_ttl_without_answer_is_none() {
+        assert_eq!(parse_answer_ttl(";; ANSWER SECTION:\n"), None);
+    }
+
+    fn dns(resolved_ips: &[&str], lookup_time_ms: f64) -> Option<DnsResult> {
+        serde_json::from_value(serde_json::json!({
+            "domain": "example.com",
+            "resolved_ips": resolved_ips,
+            "lookup_time_ms": lookup_time_ms,
+            "ttl": null,
+            "nameservers": null,
+            "using_cdn": null,
+            "backend": null,
+            "resolver": null,
+            "local_resolver_error": null,
+        }))
+        .ok()
+    }
+
+    #[test]
+    fn sla_fails_a_lookup_that_resolved_nothing() {
+        let sla = SlaConfig { max_dns_ms: Some(100.0), ..Default::default() };
+
+        let checks = evaluate_sla(&sla, &dns(&[], 5.0), &None, &None);
+        assert_eq!(checks.len(), 1);
+        assert!(!checks[0].passed);
+        assert_eq!(checks[0].actual, None);
+
+        let checks = evaluate_sla(&sla, &dns(&["93.184.216.34"], 5.0), &None, &None);
+        assert!(checks[0].passed);
+    }
+
+    #[test]
+    fn sla_requirements_keep_the_configured_limit() {
+        let sla = SlaConfig {
+            max_dns_ms: Some(800.0),
+            min_success_rate: Some(99.5),
+            ..Default::default()
+        };
+        let checks = evaluate_sla(&sla, &dns(&["93.184.216.34"], 5.0), &None, &stability(50.0));
+
+        assert_eq!(checks[0].requirement, "<= 800 ms");
+        assert_eq!(checks[1].requirement, ">= 99.5%");
+        assert!(checks.iter().all(|c| c.passed));
+    }
+
+    #[test]
+    fn sla_fails_unmeasured_metrics() {
+        let sla = SlaConfig { max_ttfb_ms: Some(500.0), max_jitter_ms: Some(30.0), ..Default::default() };
+        let checks = evaluate_sla(&sla, &None, &Some(TcpResult::failed("timeout".to_string())), &None);
+
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|c| !c.passed && c.actual.is_none()));
+    }
+
+    #[test]
+    fn sla_without_limits_has_no_checks() {
+        let checks = evaluate_sla(&SlaConfig::default(), &dns(&["93.184.216.34"], 5.0), &None, &stability(50.0));
+        assert!(checks.is_empty());
+    }
+
+    #[test]
+    fn ping_parses_linux_summary() {
+        let stdout = "--- 93.184.216.34 ping statistics ---\n4 packets transmitted, 3 received, 25% packet loss, time 1503ms\nrtt min/avg/max/mdev = 10.100/12.500/15.900/2.100 ms\n";
+        let sample = parse_ping_output(stdout, "", 512).unwrap();
+
+        assert_eq!(sample.packet_loss_percent, 25.0);
+        assert_eq!(sample.min_rtt_ms, Some(10.1));
+        assert_eq!(sample.avg_rtt_ms, Some(12.5));
+        assert_eq!(sample.max_rtt_ms, Some(15.9));
+        assert!(!sample.too_large);
+    }
+
+    #[test]
+    fn ping_parses_macos_summary() {
+        let stdout = "--- 93.184.216.34 ping statistics ---\n4 packets transmitted, 4 packets received, 0.0% packet loss\nround-trip min/avg/max/stddev = 9.800/11.000/12.200/0.900 ms\n";
+        let sample = parse_ping_output(stdout, "", 64).unwrap();
+
+        assert_eq!(sample.packet_loss_percent, 0.0);
+        assert_eq!(sample.avg_rtt_ms, Some(11.0));
+    }
+
+    #[test]
+    fn ping_message_too_long_is_total_loss() {
+        let stdout = "ping: local error: message too long, mtu=1400\n--- 93.184.216.34 ping statistics ---\n4 packets transmitted, 0 received, +4 errors, 100% packet loss, time 3066ms\n";
+        let sample = parse_ping_output(stdout, "", 1472).unwrap();
+        assert!(sample.too_large);
+        assert_eq!(sample.packet_loss_percent, 100.0);
+        assert_eq!(sample.avg_rtt_ms, None);
+
+        let sample = parse_ping_output("", "ping: sendto: Message too long\n", 1472).unwrap();
+        assert!(sample.too_large);
+        assert_eq!(sample.packet_loss_percent, 100.0);
+    }
+
+    #[test]
+    fn ping_without_summary_is_an_error() {
+        assert!(parse_ping_output("", "ping: invalid option -- 'M'\n", 1472).is_err());
+    }
+
+    fn ping_sample(payload_bytes: u32, packet_loss_percent: f64, too_large: bool) -> PingSizeSample {
+        PingSizeSample {
+            payload_bytes,
+            min_rtt_ms: None,
+            avg_rtt_ms: (packet_loss_percent < 100.0).then_some(20.0),
+            max_rtt_ms: None,
+            packet_loss_percent,
+            dont_fragment: payload_bytes == FULL_MTU_PAYLOAD,
+            too_large,
+        }
+    }
+
+    #[test]
+    fn full_mtu_blocked_needs_small_pings_to_get_through() {
+        let sweep = |small_loss: f64, probe: Option<PingSizeSample>| LatencyBySizeResult {
+            target_ip: "93.184.216.34".to_string(),
+            samples: vec![ping_sample(64, small_loss, false), ping_sample(1400, small_loss, false)],
+            mtu_probe: probe,
+        };
+
+        assert!(full_mtu_blocked(&sweep(0.0, Some(ping_sample(FULL_MTU_PAYLOAD, 100.0, false)))));
+        assert!(full_mtu_blocked(&sweep(0.0, Some(ping_sample(FULL_MTU_PAYLOAD, 100.0, true)))));
+        assert!(!full_mtu_blocked(&sweep(0.0, Some(ping_sample(FULL_MTU_PAYLOAD, 0.0, false)))));
+        assert!(!full_mtu_blocked(&sweep(100.0, Some(ping_sample(FULL_MTU_PAYLOAD, 100.0, false)))));
+        assert!(!full_mtu_blocked(&sweep(0.0, None)));
+    }
+
+    fn showcerts(verify: &str) -> String {
+        format!(
+            "CONNECTED(00000003)\n---\nCertificate chain\n 0 s:CN = example.com\n   i:C = US, O = DigiCert Inc, CN = DigiCert Global G2 TLS RSA SHA256 2020 CA1\n 1 s:C = US, O = DigiCert Inc, CN = DigiCert Global G2 TLS RSA SHA256 2020 CA1\n   i:C = US, O = DigiCert Inc, OU = www.digicert.com, CN = DigiCert Global Root G2\n---\n    Verify return code: {}\n",
+            verify
+        )
+    }
+
+    #[test]
+    fn ssl_chain_without_ca_store_is_still_complete() {
+        let cert = parse_ssl_chain(&showcerts("20 (unable to get local issuer certificate)")).unwrap();
+        assert!(cert.chain_complete);
+        assert_eq!(cert.verify_code, Some(20));
+
+        assert!(parse_ssl_chain(&showcerts("0 (ok)")).unwrap().chain_complete);
+    }
+
+    #[test]
+    fn ssl_chain_missing_issuer_is_incomplete() {
+        let cert = parse_ssl_chain(&showcerts("21 (unable to verify the first certificate)")).unwrap();
+        assert!(!cert.chain_complete);
+        assert_eq!(cert.subject, "CN = example.com");
+
+        let broken = showcerts("0 (ok)").replace(" 1 s:C = US, O = DigiCert Inc,", " 1 s:C = US, O = Other CA,");
+        assert!(!parse_ssl_chain(&broken).unwrap().chain_complete);
+    }
+
+    #[test]
+    fn cgnat_range_is_100_64_slash_10() {
+        let cgnat = |ip: &str| is_cgnat(&ip.parse().unwrap());
+        assert!(cgnat("100.64.0.1"));
+        assert!(cgnat("100.127.255.254"));
+        assert!(!cgnat("100.63.255.255"));
+        assert!(!cgnat("100.128.0.1"));
+        assert!(!cgnat("10.0.0.1"));
+    }
+
+    fn ipv4s(ips: &[&str]) -> Vec<std::net::Ipv4Addr> {
+        ips.iter().map(|ip| ip.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn nat_scan_stops_at_the_first_public_hop() {
+        let hops = trace(&["192.168.1.1", "*", "203.0.113.1", "10.10.0.1", "100.64.3.1"]);
+        assert_eq!(nat_hops(&hops, None), ipv4s(&["192.168.1.1"]));
+
+        let hops = trace(&["192.168.1.1", "100.64.0.1", "198.51.100.9"]);
+        assert_eq!(nat_hops(&hops, None), ipv4s(&["192.168.1.1", "100.64.0.1"]));
+    }
+
+    #[test]
+    fn nat_scan_stops_at_our_public_ip() {
+        let hops = trace(&["192.168.1.1", "198.51.100.7", "10.0.0.1"]);
+        assert_eq!(nat_hops(&hops, Some("198.51.100.7")), ipv4s(&["192.168.1.1"]));
+    }
+
+    #[test]
+    fn all_private_path_needs_a_public_ip() {
+        let hops = trace(&["192.168.1.1", "10.0.0.1", "10.0.1.1", "10.0.2.1", "10.0.3.1"]);
+        assert!(nat_hops(&hops, None).is_empty());
+        assert_eq!(nat_hops(&hops, Some("198.51.100.7")).len(), 5);
+    }
+
+    #[test]
+    fn privilege_errors_are_recognized() {
+        assert!(is_privilege_error("traceroute: socket: Operation not permitted\n"));
+        assert!(is_privilege_error("You do not have enough privileges to use this traceroute method.\n"));
+        assert!(!is_privilege_error("traceroute: invalid option -- 'I'\n"));
+        assert!(!is_privilege_error("connect: Network is unreachable\n"));
+        assert!(!is_privilege_error(""));
+    }
+
+    #[test]
+    fn source_binding_rejects_interface_with_source_ip() {
+        assert!(SourceBinding::from_config(Some("lo"), Some("127.0.0.1")).is_err());
+
+        let binding = SourceBinding::from_config(Some(" "), Some("127.0.0.1")).unwrap();
+        assert_eq!(binding.curl_args(), vec!["--interface", "127.0.0.1"]);
+    }
+
+    fn analysis(severity: IssueSeverity, breakdown: &[i32]) -> Analysis {
+        let score = 100 + breakdown.iter().sum::<i32>();
+        Analysis {
+            issues: vec![DiagnosticIssue {
+                category: IssueCategory::Tcp,
+                severity,
+                title: "Kết nối TCP chậm".to_string(),
+                description: String::new(),
+                possible_causes: Vec::new(),
+                solutions: Vec::new(),
+            }],
+            recommendations: Vec::new(),
+            overall_status: status_for_score(score),
+            score_breakdown: breakdown.iter().map(|points| ("Kết nối TCP chậm".to_string(), *points)).collect(),
+        }
+    }
+
+    #[test]
+    fn strict_mode_cap_is_explained_by_the_breakdown() {
+        let mut capped = analysis(IssueSeverity::Warning, &[-10]);
+        assert_eq!(capped.overall_status, OverallStatus::Excellent);
+        apply_strict_mode(&mut capped);
+
+        let score = 100 + capped.score_breakdown.iter().map(|(_, points)| points).sum::<i32>();
+        assert_eq!(capped.overall_status, OverallStatus::Poor);
+        assert_eq!(status_for_score(score), OverallStatus::Poor);
+        assert_eq!(capped.score_breakdown.len(), 2);
+    }
+
+    #[test]
+    fn strict_mode_leaves_info_and_poor_results_alone() {
+        let mut info = analysis(IssueSeverity::Info, &[-10]);
+        apply_strict_mode(&mut info);
+        assert_eq!(info.overall_status, OverallStatus::Excellent);
+        assert_eq!(info.score_breakdown.len(), 1);
+
+        let mut failed = analysis(IssueSeverity::Error, &[-40, -40]);
+        apply_strict_mode(&mut failed);
+        assert_eq!(failed.overall_status, OverallStatus::Failed);
+        assert_eq!(failed.score_breakdown.len(), 2);
+    }
+
+    #[test]
+    fn redirect_chain_resolves_relative_locations() {
+        let dump = "HTTP/1.1 301 Moved Permanently\r\nLocation: https://example.com/\r\n\r\nHTTP/2 302\r\nlocation: /login?next=%2F\r\n\r\nHTTP/2 200\r\ncontent-type: text/html\r\n\r\n";
+        assert_eq!(
+            parse_redirect_chain("http://example.com/", dump),
+            vec!["http://example.com/", "https://example.com/", "https://example.com/login?next=%2F"]
+        );
+    }
+
+    #[test]
+    fn redirect_chain_ignores_continue_and_final_blocks() {
+        let dump = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nLocation: https://elsewhere.example/\r\n\r\n";
+        assert_eq!(parse_redirect_chain("https://example.com/", dump), vec!["https://example.com/"]);
+    }
+
+    #[test]
+    fn redirect_without_location_is_skipped() {
+        let dump = "HTTP/2 301\r\nserver: nginx\r\n\r\nHTTP/2 200\r\n\r\n";
+        assert_eq!(parse_redirect_chain("https://example.com/", dump), vec!["https://example.com/"]);
+    }
+
+    #[test]
+    fn http_probes_are_classified_by_exit_and_status() {
+        assert_eq!(classify_http_probe(Some(0), "200"), ProbeOutcome::Success);
+        assert_eq!(classify_http_probe(Some(0), "301"), ProbeOutcome::Success);
+        assert_eq!(classify_http_probe(Some(0), "503"), ProbeOutcome::HttpError);
+        assert_eq!(classify_http_probe(Some(0), "404"), ProbeOutcome::HttpError);
+        assert_eq!(classify_http_probe(Some(28), "000"), ProbeOutcome::Timeout);
+        assert_eq!(classify_http_probe(Some(7), "000"), ProbeOutcome::ConnectionError);
+        assert_eq!(classify_http_probe(Some(35), "000"), ProbeOutcome::ConnectionError);
+        assert_eq!(classify_http_probe(None, ""), ProbeOutcome::ConnectionError);
+    }
+
+    #[test]
+    fn stability_counts_each_failure_kind() {
+        let outcomes = [
+            ProbeOutcome::Success,
+            ProbeOutcome::Success,
+            ProbeOutcome::Timeout,
+            ProbeOutcome::HttpError,
+            ProbeOutcome::ConnectionError,
+            ProbeOutcome::Timeout,
+            ProbeOutcome::Success,
+            ProbeOutcome::Success,
+        ];
+        let result = stability_from_probes(&outcomes, &[10.0, 20.0, 30.0, 20.0]);
+
+        assert_eq!(result.total_tests, 8);
+        assert_eq!(result.successful_tests, 4);
+        assert_eq!(result.success_rate, 50.0);
+        assert_eq!((result.timeouts, result.http_errors, result.connection_errors), (2, 1, 1));
+        assert_eq!((result.min_time_ms, result.avg_time_ms, result.max_time_ms), (10.0, 20.0, 30.0));
+        assert_eq!(result.jitter_ms, 5.0);
+        assert_eq!(stability_failure_breakdown(&result), "2 timeout, 1 lỗi kết nối, 1 lỗi HTTP");
+    }
+
+    #[test]
+    fn stability_without_successes_has_zero_timings() {
+        let result = stability_from_probes(&[ProbeOutcome::Timeout, ProbeOutcome::Timeout], &[]);
+
+        assert_eq!(result.success_rate, 0.0);
+        assert_eq!(result.timeouts, 2);
+        assert_eq!(result.avg_time_ms, 0.0);
+    }
 }
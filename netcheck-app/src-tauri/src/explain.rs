@@ -0,0 +1,208 @@
+//! Plain-language explanations of diagnostic metrics
+//!
+//! Holds the meaning, likely causes and fixes for each graded metric. The
+//! analysis builds its issues from the same knowledge, so tooltips and the
+//! issue list never drift apart.
+
+use crate::config::DiagnosticThresholds;
+
+/// TTFB reference when the profile doesn't grade it (explanations only)
+const TTFB_REFERENCE_MS: f64 = 800.0;
+
+/// Success rate below which the connection is considered unstable
+pub const UNSTABLE_SUCCESS_RATE: f64 = 80.0;
+
+/// A graded metric of the diagnostic report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    DnsLookup,
+    TcpConnect,
+    SslHandshake,
+    Ttfb,
+    TotalTime,
+    Jitter,
+    SuccessRate,
+}
+
+impl Metric {
+    /// Look up a metric by name, accepting both short names and report field names
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "dns" | "dns_time_ms" | "lookup_time_ms" => Some(Metric::DnsLookup),
+            "connect" | "tcp" | "connect_time_ms" => Some(Metric::TcpConnect),
+            "ssl" | "tls" | "ssl_time_ms" => Some(Metric::SslHandshake),
+            "ttfb" | "ttfb_ms" => Some(Metric::Ttfb),
+            "total" | "total_time_ms" => Some(Metric::TotalTime),
+            "jitter" | "jitter_ms" => Some(Metric::Jitter),
+            "success_rate" | "stability" => Some(Metric::SuccessRate),
+            _ => None,
+        }
+    }
+
+    /// Short display name
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::DnsLookup => "DNS lookup",
+            Metric::TcpConnect => "TCP connect",
+            Metric::SslHandshake => "SSL handshake",
+            Metric::Ttfb => "TTFB",
+            Metric::TotalTime => "Tổng thời gian",
+            Metric::Jitter => "Jitter",
+            Metric::SuccessRate => "Tỉ lệ thành công",
+        }
+    }
+
+    /// What the metric measures, for non-technical users
+    fn meaning(self) -> &'static str {
+        match self {
+            Metric::DnsLookup => "thời gian đổi tên miền thành địa chỉ IP",
+            Metric::TcpConnect => "thời gian thiết lập kết nối đến server, phản ánh độ trễ đường truyền",
+            Metric::SslHandshake => "thời gian bắt tay mã hóa HTTPS sau khi đã kết nối",
+            Metric::Ttfb => "thời gian chờ server xử lý và gửi về byte dữ liệu đầu tiên",
+            Metric::TotalTime => "tổng thời gian tải, gồm DNS, kết nối, SSL và tải nội dung",
+            Metric::Jitter => "độ dao động thời gian phản hồi giữa các lần thử, jitter cao gây giật lag khi gọi video/chơi game",
+            Metric::SuccessRate => "tỉ lệ request thành công trong bài test ổn định",
+        }
+    }
+
+    /// Limit the metric is graded against (a floor for success rate)
+    fn limit(self, thresholds: &DiagnosticThresholds) -> f64 {
+        match self {
+            Metric::DnsLookup => thresholds.dns_ms,
+            Metric::TcpConnect => thresholds.connect_ms,
+            Metric::SslHandshake => thresholds.ssl_ms,
+            Metric::Ttfb => thresholds.ttfb_ms.unwrap_or(TTFB_REFERENCE_MS),
+            Metric::TotalTime => thresholds.total_ms,
+            Metric::Jitter => thresholds.jitter_ms,
+            Metric::SuccessRate => 100.0,
+        }
+    }
+
+    /// Likely causes when the metric is out of range
+    pub fn possible_causes(self) -> Vec<String> {
+        let causes: &[&str] = match self {
+            Metric::DnsLookup => &[
+                "DNS server xa về mặt địa lý",
+                "DNS server quá tải",
+                "Không có DNS cache",
+            ],
+            Metric::TcpConnect => &[
+                "Server ở xa (khác châu lục)",
+                "Routing kém từ ISP",
+                "Nghẽn mạng",
+            ],
+            Metric::SslHandshake => &[
+                "SSL certificate chain dài",
+                "OCSP stapling không được bật",
+                "Latency cao đến server",
+            ],
+            Metric::Ttfb => &[
+                "Server xử lý request chậm",
+                "Backend/database quá tải",
+                "Latency cao đến server",
+            ],
+            Metric::TotalTime => &[
+                "Server phản hồi chậm",
+                "Kết nối mạng không ổn định",
+                "Nhiều redirect",
+            ],
+            Metric::Jitter => &[
+                "Mạng không ổn định",
+                "Có thiết bị khác đang dùng băng thông",
+            ],
+            Metric::SuccessRate => &[
+                "Mạng không ổn định",
+                "Tín hiệu WiFi yếu",
+                "ISP có vấn đề",
+                "Server bị quá tải",
+            ],
+        };
+        causes.iter().map(|c| c.to_string()).collect()
+    }
+
+    /// Suggested fixes when the metric is out of range
+    pub fn solutions(self) -> Vec<String> {
+        let solutions: &[&str] = match self {
+            Metric::DnsLookup => &[
+                "Đổi sang DNS nhanh hơn như Cloudflare (1.1.1.1) hoặc Google (8.8.8.8)",
+            ],
+            Metric::TcpConnect => &[
+                "Vấn đề này thường do khoảng cách địa lý, khó cải thiện",
+                "Thử sử dụng VPN với server gần target hơn",
+            ],
+            Metric::SslHandshake => &[
+                "Đây thường là vấn đề từ phía server",
+                "Kiểm tra xem có đang bị man-in-the-middle không",
+            ],
+            Metric::Ttfb => &[
+                "Kiểm tra hiệu năng phía server của API",
+                "Bật cache cho các response thường dùng",
+            ],
+            Metric::TotalTime => &[
+                "Kiểm tra tốc độ mạng của bạn",
+                "Thử vào lúc khác trong ngày",
+            ],
+            Metric::Jitter => &[
+                "Giảm số thiết bị sử dụng mạng cùng lúc",
+                "Sử dụng cáp LAN thay vì WiFi",
+            ],
+            Metric::SuccessRate => &[
+                "Di chuyển gần router WiFi hơn hoặc dùng cáp LAN",
+                "Khởi động lại modem/router",
+                "Liên hệ ISP nếu vấn đề kéo dài",
+            ],
+        };
+        solutions.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+/// Explain a metric value in plain language (meaning, verdict, likely causes)
+///
+/// `context` supplies the thresholds the value is judged against, so the
+/// verdict matches what the analysis reported for the same profile.
+pub fn explain_metric(metric_name: &str, value: f64, context: &DiagnosticThresholds) -> String {
+    let Some(metric) = Metric::from_name(metric_name) else {
+        return format!("Không có giải thích cho chỉ số \"{}\"", metric_name);
+    };
+
+    let limit = metric.limit(context);
+    let (reading, verdict, out_of_range) = if metric == Metric::SuccessRate {
+        (
+            format!("{:.0}%", value),
+            if value < UNSTABLE_SUCCESS_RATE {
+                "Kết nối không ổn định".to_string()
+            } else if value < limit {
+                "Có request thất bại".to_string()
+            } else {
+                "Ổn định".to_string()
+            },
+            value < limit,
+        )
+    } else {
+        (
+            format!("{:.0}ms", value),
+            if value > limit {
+                format!("Chậm (nên < {:.0}ms)", limit)
+            } else {
+                format!("Bình thường (nên < {:.0}ms)", limit)
+            },
+            value > limit,
+        )
+    };
+
+    let mut explanation = format!(
+        "{} {}: {}. {}.",
+        metric.label(),
+        reading,
+        metric.meaning(),
+        verdict
+    );
+    if out_of_range {
+        explanation.push_str(&format!(
+            " Nguyên nhân thường gặp: {}. Cách khắc phục: {}.",
+            metric.possible_causes().join("; "),
+            metric.solutions().join("; ")
+        ));
+    }
+    explanation
+}
@@ -0,0 +1,50 @@
+//! Issues-only JSON export for ticketing integrations
+//!
+//! A compact array with one record per issue, each carrying a stable
+//! `issue_id` so a bot can deduplicate the same recurring issue across runs.
+
+use crate::report_generator::category_text;
+use crate::types::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// One issue as exported for ticketing
+#[derive(Debug, Serialize)]
+pub struct IssueRecord<'a> {
+    /// Stable across runs: category plus a hash of category and title, e.g. "dns-3fa9c2e1b0d4"
+    pub issue_id: String,
+    pub target: &'a str,
+    pub timestamp: &'a str,
+    pub category: &'a IssueCategory,
+    pub severity: &'a IssueSeverity,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub solutions: &'a [String],
+}
+
+/// Stable id for an issue, derived from its category and title only
+pub fn issue_id(issue: &DiagnosticIssue) -> String {
+    let category = category_text(&issue.category).to_lowercase();
+    let digest = Sha256::digest(format!("{}:{}", category, issue.title).as_bytes());
+    let hash: String = digest.iter().take(6).map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}", category, hash)
+}
+
+/// Serialize the report's issues as a compact JSON array
+pub fn issues_json(report: &DiagnosticReport) -> Result<String, serde_json::Error> {
+    let records: Vec<IssueRecord> = report
+        .issues
+        .iter()
+        .map(|issue| IssueRecord {
+            issue_id: issue_id(issue),
+            target: &report.target_url,
+            timestamp: &report.timestamp,
+            category: &issue.category,
+            severity: &issue.severity,
+            title: &issue.title,
+            description: &issue.description,
+            solutions: &issue.solutions,
+        })
+        .collect();
+    serde_json::to_string(&records)
+}
@@ -3,16 +3,32 @@
 //! A Tauri-based desktop application for comprehensive network diagnostics.
 //! Runs multiple diagnostic checks in parallel for faster results.
 
+mod checks;
+mod config;
+mod delta;
 mod diagnostic;
+mod explain;
+mod issues_export;
+mod mailer;
+mod raw_log;
 mod report_generator;
+mod suite;
+mod text_report;
 mod types;
+mod validate;
+mod webhook;
 
+use crate::checks::{CheckContext, CheckRegistry};
+use crate::config::{DiagnosticConfig, DiagnosticProfile, DiagnosticThresholds};
+use crate::delta::{describe_delta, DeltaSnapshot, PreviousReports};
 use crate::diagnostic::*;
-use crate::report_generator::{generate_report, ExportRequest};
+use crate::mailer::{send_report_email, SmtpConfig};
+use crate::report_generator::{generate_report, summary_line, ExportRequest, TraceLogEntry};
+use crate::text_report::generate_text_report;
 use crate::types::*;
 use chrono::Utc;
 use std::path::PathBuf;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::time::{timeout, Duration};
 
 /// Emit progress event to frontend
@@ -27,238 +43,411 @@ fn emit_progress(app: &AppHandle, step: &str, status: DiagnosticStatus, message:
     let _ = app.emit("diagnostic-progress", &event);
 }
 
+/// Emit progress event with structured data to frontend
+fn emit_progress_data(
+    app: &AppHandle,
+    step: &str,
+    status: DiagnosticStatus,
+    message: &str,
+    data: serde_json::Value,
+) {
+    let event = ProgressEvent {
+        step: step.to_string(),
+        status,
+        message: message.to_string(),
+        data: Some(data),
+    };
+    
+    let _ = app.emit("diagnostic-progress", &event);
+}
+
+/// Target as the checks see it
+struct PreparedTarget {
+    url: String,
+    domain: String,
+    /// Raw TCP mode target (host, port); `None` for web targets
+    raw_target: Option<(String, u16)>,
+    target_override: Option<TargetOverride>,
+}
+
+/// Parse the target and apply raw TCP mode and the connect/Host override
+fn prepare_target(target_url: &str, config: &DiagnosticConfig) -> Result<PreparedTarget, String> {
+    let domain = parse_domain(target_url)?;
+    let url = normalize_url(target_url);
+    
+    // Raw TCP mode for non-web services: "host:port" without scheme, or forced
+    let raw_target: Option<(String, u16)> = match parse_host_port(target_url) {
+        Some(host_port) => Some(host_port),
+        None if config.raw_tcp => {
+            let port = url::Url::parse(&url)
+                .ok()
+                .and_then(|u| u.port_or_known_default())
+                .unwrap_or(443);
+            Some((domain.clone(), port))
+        }
+        None => None,
+    };
+    
+    // Test a specific origin: connect to `connect_ip` while presenting `host_override`
+    let target_override = TargetOverride::from_config(
+        config.connect_ip.as_deref(),
+        config.host_override.as_deref(),
+        &domain,
+    )?;
+    let (url, domain) = match &target_override {
+        Some(_) if raw_target.is_some() => {
+            return Err("connect_ip / host_override only apply to HTTP(S) targets".to_string());
+        }
+        Some(target_override) => (target_override.apply_to_url(&url)?, target_override.host.clone()),
+        None => (url, domain),
+    };
+    
+    Ok(PreparedTarget { url, domain, raw_target, target_override })
+}
+
 /// Main diagnostic command - runs all checks in parallel
 #[tauri::command]
-async fn run_diagnostic(app: AppHandle, target_url: String) -> Result<DiagnosticReport, String> {
-    let domain = parse_domain(&target_url)?;
-    let url = if target_url.starts_with("http") {
-        target_url.clone()
-    } else {
-        format!("https://{}", target_url)
-    };
+async fn run_diagnostic(
+    app: AppHandle,
+    target_url: String,
+    profile: Option<DiagnosticProfile>,
+    config: Option<DiagnosticConfig>,
+) -> Result<DiagnosticReport, String> {
+    let config = DiagnosticConfig::effective(profile, config);
+    
+    // Fail fast on a mistyped interface / source IP instead of silently using the default route
+    let binding = SourceBinding::from_config(config.interface.as_deref(), config.source_ip.as_deref())?;
     
-    // Emit start status for all steps
+    let PreparedTarget { url, domain, raw_target, target_override } = prepare_target(&target_url, &config)?;
+    
+    // Emit start status for the dependent steps (stability starts with DNS and reports itself)
     emit_progress(&app, "dns", DiagnosticStatus::Running, "Đang phân giải DNS...");
     emit_progress(&app, "tcp", DiagnosticStatus::Pending, "Chờ DNS...");
     emit_progress(&app, "ssl", DiagnosticStatus::Pending, "Chờ TCP...");
     emit_progress(&app, "http", DiagnosticStatus::Pending, "Chờ SSL...");
     emit_progress(&app, "routing", DiagnosticStatus::Pending, "Chờ DNS...");
-    emit_progress(&app, "stability", DiagnosticStatus::Pending, "Chờ TCP...");
+    if raw_target.is_some() {
+        emit_progress(&app, "ssl", DiagnosticStatus::Success, "Bỏ qua (chế độ TCP thô)");
+        emit_progress(&app, "http", DiagnosticStatus::Success, "Bỏ qua (chế độ TCP thô)");
+    }
     
-    // Phase 1: DNS Resolution (required for other checks)
-    let dns_result = match timeout(Duration::from_secs(10), check_dns(&domain)).await {
-        Ok(Ok(result)) => {
-            let status = if result.resolved_ips.is_empty() {
-                DiagnosticStatus::Error
-            } else if result.lookup_time_ms > 200.0 {
-                DiagnosticStatus::Warning
-            } else {
-                DiagnosticStatus::Success
-            };
-            
-            emit_progress(
-                &app, 
-                "dns", 
-                status,
-                &format!(
-                    "Tìm thấy {} IP, lookup {:.0}ms", 
-                    result.resolved_ips.len(), 
-                    result.lookup_time_ms
-                )
-            );
-            Some(result)
-        }
-        Ok(Err(e)) => {
-            emit_progress(&app, "dns", DiagnosticStatus::Error, &format!("Lỗi: {}", e));
-            None
-        }
-        Err(_) => {
-            emit_progress(&app, "dns", DiagnosticStatus::Error, "Timeout sau 10 giây");
-            None
-        }
-    };
+    // Run all enabled checks, each as soon as its dependencies are done
+    let capture_raw = config.capture_raw;
+    let progress_app = app.clone();
+    let ctx = CheckContext::new(
+        Box::new(move |event| {
+            let _ = progress_app.emit("diagnostic-progress", &event);
+        }),
+        config,
+        binding,
+        target_override,
+        url,
+        domain,
+        raw_target,
+    );
+    let ((), raw_output) =
+        raw_log::capture(capture_raw, CheckRegistry::with_default_checks().run(&ctx)).await;
     
-    // Get target IP for routing check
-    let target_ip = dns_result
-        .as_ref()
-        .and_then(|d| d.resolved_ips.first())
-        .cloned()
-        .unwrap_or_default();
+    let outputs = ctx.take_outputs();
+    let dns_result = outputs.dns().cloned();
+    let secure_dns_result = outputs.secure_dns().cloned();
+    let tcp_result = outputs.tcp().cloned();
+    let per_ip_result = outputs.per_ip_tcp().cloned();
+    let ssl_cert_result = outputs.ssl_cert().cloned();
+    let port_result = outputs.port_check().cloned();
+    let routing_result = outputs.routing().cloned();
+    let stability_result = outputs.stability().cloned();
+    let latency_by_size_result = outputs.latency_by_size().cloned();
+    let blocklist_result = outputs.blocklist().cloned();
+    let proxy_result = outputs.proxy().cloned();
+    let bandwidth_result = outputs.bandwidth().cloned();
+    
+    let CheckContext { config, binding, target_override, url, raw_target, .. } = ctx;
+    let thresholds = &config.thresholds;
     
-    // Phase 2: Run TCP timing, routing, and stability checks in parallel
-    emit_progress(&app, "tcp", DiagnosticStatus::Running, "Đang kiểm tra kết nối TCP...");
-    emit_progress(&app, "routing", DiagnosticStatus::Running, "Đang chạy traceroute...");
-    emit_progress(&app, "stability", DiagnosticStatus::Running, "Đang kiểm tra độ ổn định...");
+    // Analyze all results
+    let mut analysis = analyze_results(&AnalysisInput {
+        dns: &dns_result,
+        secure_dns: &secure_dns_result,
+        tcp: &tcp_result,
+        per_ip_tcp: &per_ip_result,
+        ssl_cert: &ssl_cert_result,
+        routing: &routing_result,
+        stability: &stability_result,
+        latency_by_size: &latency_by_size_result,
+        blocklist: &blocklist_result,
+        proxy: &proxy_result,
+        bandwidth: &bandwidth_result,
+        port_check: &port_result,
+        thresholds,
+    });
     
-    let domain_clone = domain.clone();
-    let url_clone = url.clone();
-    let target_ip_clone = target_ip.clone();
+    if config.strict {
+        apply_strict_mode(&mut analysis);
+    }
+    let Analysis {
+        issues,
+        recommendations,
+        overall_status,
+        score_breakdown,
+    } = analysis;
     
-    // Create futures for parallel execution
-    let tcp_future = async {
-        timeout(Duration::from_secs(30), check_tcp_timing(&url_clone)).await
-    };
+    let network_quality = summarize_quality(&tcp_result, &stability_result, &bandwidth_result);
+    let realtime_suitability =
+        evaluate_realtime_suitability(&tcp_result, &port_result, &stability_result);
     
-    let routing_future = async {
-        timeout(Duration::from_secs(30), check_routing(&domain_clone, &target_ip_clone)).await
+    let sla_checks = config
+        .sla
+        .as_ref()
+        .map(|sla| evaluate_sla(sla, &dns_result, &tcp_result, &stability_result))
+        .unwrap_or_default();
+    // An SLA without any limit has nothing to meet
+    let sla_met = (!sla_checks.is_empty()).then(|| sla_checks.iter().all(|c| c.passed));
+    
+    // Raw TCP reports describe the service, not a synthesized https URL
+    let target_url = match &raw_target {
+        Some((host, port)) => format!("{}:{}", host, port),
+        None => url,
     };
     
-    let stability_future = async {
-        timeout(Duration::from_secs(30), check_stability(&domain, 10)).await
+    let mut report = DiagnosticReport {
+        target_url,
+        timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        dns: dns_result,
+        secure_dns: secure_dns_result,
+        tcp: tcp_result,
+        per_ip_tcp: per_ip_result,
+        ssl_cert: ssl_cert_result,
+        port_check: port_result,
+        routing: routing_result,
+        stability: stability_result,
+        latency_by_size: latency_by_size_result,
+        blocklist: blocklist_result,
+        proxy: proxy_result,
+        bandwidth: bandwidth_result,
+        network_quality,
+        realtime_suitability,
+        overall_status,
+        sla_met,
+        sla_checks,
+        score_breakdown,
+        issues,
+        recommendations,
+        profile: config.profile,
+        thresholds: config.thresholds.clone(),
+        strict: config.strict,
+        network_source: binding.describe(),
+        target_override: target_override.as_ref().map(TargetOverride::describe),
+        delta_summary: None,
+        summary_line: String::new(),
+        raw_output,
     };
+    report.summary_line = summary_line(&report);
     
-    // Run all in parallel
-    let (tcp_res, routing_res, stability_res) = tokio::join!(
-        tcp_future,
-        routing_future,
-        stability_future
-    );
+    // Compare with the previous run for this target in this session
+    if let Some(previous) = app.state::<PreviousReports>().replace(&report) {
+        report.delta_summary = Some(describe_delta(&previous, &DeltaSnapshot::of(&report)));
+    }
     
-    // Process TCP result
-    let tcp_result = match tcp_res {
-        Ok(Ok(result)) => {
-            // Update SSL and HTTP status based on TCP result
-            let ssl_only = result.ssl_time_ms - result.connect_time_ms;
-            let ssl_status = if result.ssl_time_ms == 0.0 {
-                DiagnosticStatus::Error
-            } else if ssl_only > 500.0 {
-                DiagnosticStatus::Warning
-            } else {
-                DiagnosticStatus::Success
-            };
-            
-            emit_progress(
-                &app,
-                "ssl",
-                ssl_status,
-                &format!("SSL handshake: {:.0}ms", ssl_only)
-            );
-            
-            let http_status = if result.http_code >= 200 && result.http_code < 400 {
-                DiagnosticStatus::Success
-            } else if result.http_code >= 400 {
-                DiagnosticStatus::Warning
-            } else {
-                DiagnosticStatus::Error
-            };
-            
-            emit_progress(
-                &app,
-                "http",
-                http_status,
-                &format!("HTTP {}, tổng thời gian: {:.0}ms", result.http_code, result.total_time_ms)
-            );
-            
-            let tcp_status = if result.total_time_ms > 3000.0 {
-                DiagnosticStatus::Warning
-            } else {
-                DiagnosticStatus::Success
-            };
-            
-            emit_progress(
-                &app,
-                "tcp",
-                tcp_status,
-                &format!("Connect: {:.0}ms, TTFB: {:.0}ms", result.connect_time_ms, result.ttfb_ms)
-            );
-            
-            Some(result)
-        }
-        Ok(Err(e)) => {
-            emit_progress(&app, "tcp", DiagnosticStatus::Error, &format!("Lỗi: {}", e));
-            emit_progress(&app, "ssl", DiagnosticStatus::Error, "Không thể kiểm tra SSL");
-            emit_progress(&app, "http", DiagnosticStatus::Error, "Không thể kiểm tra HTTP");
-            None
-        }
-        Err(_) => {
-            emit_progress(&app, "tcp", DiagnosticStatus::Error, "Timeout sau 30 giây");
-            emit_progress(&app, "ssl", DiagnosticStatus::Error, "Timeout");
-            emit_progress(&app, "http", DiagnosticStatus::Error, "Timeout");
-            None
+    // Deliver to webhook; failures are reported but never fail the run
+    if let Some(webhook_config) = &config.webhook {
+        if webhook::should_post(webhook_config, &report.overall_status) {
+            match webhook::post_report(webhook_config, &report).await {
+                Ok(code) => emit_progress(
+                    &app,
+                    "webhook",
+                    DiagnosticStatus::Success,
+                    &format!("Đã gửi báo cáo đến webhook (HTTP {})", code),
+                ),
+                Err(e) => emit_progress(
+                    &app,
+                    "webhook",
+                    DiagnosticStatus::Warning,
+                    &format!("Không gửi được webhook: {}", e),
+                ),
+            }
         }
-    };
+    }
     
-    // Process routing result
-    let routing_result = match routing_res {
-        Ok(Ok(result)) => {
-            let failed_hops = result.hops.iter().filter(|h| h.ip_address == "*").count();
-            let status = if failed_hops as f64 / result.hops.len().max(1) as f64 > 0.5 {
-                DiagnosticStatus::Warning
-            } else {
-                DiagnosticStatus::Success
-            };
-            
-            emit_progress(
-                &app,
-                "routing",
-                status,
-                &format!("{} hop, {:.0}ms", result.total_hops, result.total_time_ms)
-            );
-            Some(result)
-        }
-        Ok(Err(e)) => {
-            emit_progress(&app, "routing", DiagnosticStatus::Warning, &format!("Lỗi: {}", e));
-            None
-        }
-        Err(_) => {
-            emit_progress(&app, "routing", DiagnosticStatus::Warning, "Timeout sau 30 giây");
-            None
+    Ok(report)
+}
+
+/// Run every target of a TOML/JSON suite file in turn
+/// 
+/// The whole file, and each target's config and required tools, are
+/// validated before anything runs. Targets run one at a time so their
+/// measurements don't interfere with each other.
+#[tauri::command]
+async fn run_from_config_file(app: AppHandle, path: String) -> Result<Vec<DiagnosticReport>, String> {
+    let targets = suite::load_suite(&PathBuf::from(&path))?;
+    for (i, target) in targets.iter().enumerate() {
+        let config = DiagnosticConfig::effective(target.profile, target.config.clone());
+        let validation = validate::validate(&target.url, &config);
+        if let Some(problem) = validation
+            .problems
+            .iter()
+            .find(|p| matches!(p.severity, IssueSeverity::Error))
+        {
+            return Err(format!(
+                "Target #{} ({}): {}: {}",
+                i + 1,
+                target.url,
+                problem.field,
+                problem.message
+            ));
         }
+    }
+    let total = targets.len();
+    let mut reports = Vec::with_capacity(total);
+    
+    for (i, target) in targets.into_iter().enumerate() {
+        emit_progress(
+            &app,
+            "suite",
+            DiagnosticStatus::Running,
+            &format!("Đang chạy mục tiêu {}/{}: {}", i + 1, total, target.url),
+        );
+        let report = run_diagnostic(app.clone(), target.url.clone(), target.profile, target.config)
+            .await
+            .map_err(|e| format!("Target #{} ({}): {}", i + 1, target.url, e))?;
+        reports.push(report);
+    }
+    
+    emit_progress(
+        &app,
+        "suite",
+        DiagnosticStatus::Success,
+        &format!("Hoàn tất {} mục tiêu", reports.len()),
+    );
+    Ok(reports)
+}
+
+/// Download a specific asset from the target and measure real throughput
+#[tauri::command]
+async fn check_asset_download(app: AppHandle, url: String) -> Result<AssetDownloadResult, String> {
+    parse_domain(&url)?;
+    let url = normalize_url(&url);
+    
+    emit_progress(&app, "asset_download", DiagnosticStatus::Running, "Đang tải file...");
+    
+    let on_progress = |bytes: u64, total: Option<u64>| {
+        let percent = total
+            .filter(|t| *t > 0)
+            .map(|t| (bytes as f64 / t as f64 * 100.0).min(100.0));
+        let message = match percent {
+            Some(p) => format!("Đã tải {:.0}% ({} bytes)", p, bytes),
+            None => format!("Đã tải {} bytes", bytes),
+        };
+        emit_progress_data(
+            &app,
+            "asset_download",
+            DiagnosticStatus::Running,
+            &message,
+            serde_json::json!({
+                "bytes": bytes,
+                "total_bytes": total,
+                "percent": percent,
+            }),
+        );
     };
     
-    // Process stability result
-    let stability_result = match stability_res {
+    match timeout(Duration::from_secs(130), measure_asset_download(&url, on_progress)).await {
         Ok(Ok(result)) => {
-            let status = if result.success_rate >= 100.0 {
+            let status = if result.http_code >= 200 && result.http_code < 400 {
                 DiagnosticStatus::Success
-            } else if result.success_rate >= 80.0 {
-                DiagnosticStatus::Warning
             } else {
                 DiagnosticStatus::Error
             };
-            
             emit_progress(
                 &app,
-                "stability",
+                "asset_download",
                 status,
                 &format!(
-                    "{:.0}% thành công, avg {:.0}ms, jitter {:.0}ms",
-                    result.success_rate,
-                    result.avg_time_ms,
-                    result.jitter_ms
-                )
+                    "HTTP {}, {} bytes trong {:.0}ms, {:.2} KB/s",
+                    result.http_code, result.bytes_downloaded, result.elapsed_ms, result.throughput_kbps
+                ),
             );
-            Some(result)
+            Ok(result)
         }
         Ok(Err(e)) => {
-            emit_progress(&app, "stability", DiagnosticStatus::Warning, &format!("Lỗi: {}", e));
-            None
+            emit_progress(&app, "asset_download", DiagnosticStatus::Error, &format!("Lỗi: {}", e));
+            Err(e)
         }
         Err(_) => {
-            emit_progress(&app, "stability", DiagnosticStatus::Warning, "Timeout sau 30 giây");
-            None
+            emit_progress(&app, "asset_download", DiagnosticStatus::Error, "Timeout sau 130 giây");
+            Err("Asset download timed out".to_string())
         }
-    };
+    }
+}
+
+/// Scan common outbound ports (80, 443, 25, 3389, ...) for firewall restrictions
+/// 
+/// Runs against `PORT_SCAN_HOST` unless another host that answers on every
+/// port is given.
+#[tauri::command]
+async fn scan_common_ports(app: AppHandle, host: Option<String>) -> Result<PortScanResult, String> {
+    let host = host
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| PORT_SCAN_HOST.to_string());
     
-    // Analyze all results
-    let (issues, recommendations, overall_status) = analyze_results(
-        &dns_result,
-        &tcp_result,
-        &routing_result,
-        &stability_result,
+    emit_progress(
+        &app,
+        "port_scan",
+        DiagnosticStatus::Running,
+        &format!("Đang quét port phổ biến qua {}...", host),
     );
     
-    Ok(DiagnosticReport {
-        target_url: url,
-        timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
-        dns: dns_result,
-        tcp: tcp_result,
-        routing: routing_result,
-        stability: stability_result,
-        overall_status,
-        issues,
-        recommendations,
-    })
+    match check_common_ports(&host, &SourceBinding::default()).await {
+        Ok(result) => {
+            let all_open = result.ports.iter().all(|p| p.status == PortStatus::Open);
+            let status = if all_open { DiagnosticStatus::Success } else { DiagnosticStatus::Warning };
+            emit_progress(&app, "port_scan", status, &result.summary);
+            Ok(result)
+        }
+        Err(e) => {
+            emit_progress(&app, "port_scan", DiagnosticStatus::Error, &format!("Lỗi: {}", e));
+            Err(e)
+        }
+    }
+}
+
+/// Check the target, config and required tools without running any probe
+/// 
+/// Takes the same arguments as `run_diagnostic` and reports which checks
+/// would run and every problem that would stop or degrade the run.
+#[tauri::command]
+fn validate_diagnostic(
+    target_url: String,
+    profile: Option<DiagnosticProfile>,
+    config: Option<DiagnosticConfig>,
+) -> ValidationReport {
+    validate::validate(&target_url, &DiagnosticConfig::effective(profile, config))
+}
+
+/// Names of the available checks (for `DiagnosticConfig::disabled_checks`)
+#[tauri::command]
+fn list_checks() -> Vec<String> {
+    CheckRegistry::with_default_checks()
+        .names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Plain-language explanation of a metric value (UI tooltips)
+///
+/// Judged against the given thresholds, else the profile's, else the defaults.
+#[tauri::command]
+fn explain_metric(
+    metric_name: String,
+    value: f64,
+    profile: Option<DiagnosticProfile>,
+    thresholds: Option<DiagnosticThresholds>,
+) -> String {
+    let thresholds = thresholds
+        .unwrap_or_else(|| profile.map(|p| p.config()).unwrap_or_default().thresholds);
+    explain::explain_metric(&metric_name, value, &thresholds)
 }
 
 /// Export diagnostic report to DOCX file
@@ -272,12 +461,63 @@ async fn export_docx_report(request: ExportRequest, save_path: String) -> Result
     Ok(format!("Report saved successfully to: {}", save_path))
 }
 
+/// Export diagnostic report to plain-text (.txt) file
+#[tauri::command]
+async fn export_report_txt(request: ExportRequest, save_path: String) -> Result<String, String> {
+    let text = generate_text_report(&request.report, &request.logs);
+
+    std::fs::write(&save_path, text)
+        .map_err(|e| format!("Failed to write report: {}", e))?;
+
+    Ok(format!("Report saved successfully to: {}", save_path))
+}
+
+/// Export only the issues as compact JSON (ticketing integrations)
+#[tauri::command]
+async fn export_issues_json(report: DiagnosticReport, save_path: String) -> Result<String, String> {
+    let json = issues_export::issues_json(&report)
+        .map_err(|e| format!("Failed to serialize issues: {}", e))?;
+
+    std::fs::write(&save_path, json)
+        .map_err(|e| format!("Failed to write issues: {}", e))?;
+
+    Ok(format!("Issues saved successfully to: {}", save_path))
+}
+
+/// Email diagnostic report (DOCX attachment) via SMTP
+#[tauri::command]
+async fn email_report(
+    report: DiagnosticReport,
+    logs: Vec<TraceLogEntry>,
+    smtp_config: SmtpConfig,
+    recipients: Vec<String>,
+) -> Result<String, String> {
+    send_report_email(&report, &logs, &smtp_config, &recipients)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(format!("Report sent successfully to: {}", recipients.join(", ")))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![run_diagnostic, export_docx_report])
+        .manage(PreviousReports::default())
+        .invoke_handler(tauri::generate_handler![
+            run_diagnostic,
+            run_from_config_file,
+            validate_diagnostic,
+            check_asset_download,
+            scan_common_ports,
+            explain_metric,
+            list_checks,
+            export_docx_report,
+            export_report_txt,
+            export_issues_json,
+            email_report
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,345 @@
+//! Email delivery for NetCheck Diagnostic Reports
+//!
+//! Generates the DOCX report to a temporary file and sends it as an attachment
+//! over SMTP, so support workflows don't need a manual export-then-attach step.
+
+use crate::report_generator::{generate_report, ReportError, TraceLogEntry};
+use crate::types::*;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Connection security used when talking to the SMTP server
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection, no encryption (only for trusted local relays)
+    None,
+    /// Plain connection upgraded with STARTTLS (usually port 587)
+    StartTls,
+    /// Implicit TLS from the first byte (usually port 465)
+    Tls,
+}
+
+/// SMTP settings received from frontend
+#[derive(Clone, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+// Credentials must never end up in logs, so Debug is implemented by hand
+impl std::fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("security", &self.security)
+            .field("username", &self.username.as_ref().map(|_| "<redacted>"))
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("from", &self.from)
+            .finish()
+    }
+}
+
+/// Custom error type for email delivery
+#[derive(Debug)]
+pub enum EmailError {
+    Report(ReportError),
+    Io(io::Error),
+    InvalidAddress(String),
+    InvalidConfig(String),
+    Connection(String),
+    Authentication(String),
+    Send(String),
+}
+
+impl std::fmt::Display for EmailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmailError::Report(e) => write!(f, "Report error: {}", e),
+            EmailError::Io(e) => write!(f, "IO error: {}", e),
+            EmailError::InvalidAddress(e) => write!(f, "Invalid email address: {}", e),
+            EmailError::InvalidConfig(e) => write!(f, "Invalid SMTP configuration: {}", e),
+            EmailError::Connection(e) => write!(f, "SMTP connection failed: {}", e),
+            EmailError::Authentication(e) => write!(f, "SMTP authentication failed: {}", e),
+            EmailError::Send(e) => write!(f, "Failed to send email: {}", e),
+        }
+    }
+}
+
+impl From<ReportError> for EmailError {
+    fn from(err: ReportError) -> Self {
+        EmailError::Report(err)
+    }
+}
+
+impl From<io::Error> for EmailError {
+    fn from(err: io::Error) -> Self {
+        EmailError::Io(err)
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for EmailError {
+    fn from(err: lettre::transport::smtp::Error) -> Self {
+        // 530/534/535 are the SMTP reply codes for rejected authentication
+        let is_auth = err
+            .status()
+            .map(|code| matches!(code.to_string().as_str(), "530" | "534" | "535"))
+            .unwrap_or(false);
+
+        if is_auth {
+            EmailError::Authentication(err.to_string())
+        } else if err.is_permanent() || err.is_transient() {
+            EmailError::Send(err.to_string())
+        } else {
+            EmailError::Connection(err.to_string())
+        }
+    }
+}
+
+/// Generate the DOCX report and email it to all recipients
+pub async fn send_report_email(
+    report: &DiagnosticReport,
+    logs: &[TraceLogEntry],
+    smtp_config: &SmtpConfig,
+    recipients: &[String],
+) -> Result<(), EmailError> {
+    if recipients.is_empty() {
+        return Err(EmailError::InvalidAddress("No recipients given".to_string()));
+    }
+
+    // Reject a bad SMTP config before spending time on the report
+    let mailer = build_transport(smtp_config)?;
+
+    let attachment_name = report_file_name(report);
+    let attachment_bytes = render_report_to_bytes(report, logs, &attachment_name)?;
+
+    let email = build_message(report, &smtp_config.from, recipients, attachment_name, attachment_bytes)?;
+    mailer.send(email).await?;
+
+    Ok(())
+}
+
+/// Assemble the email: summary body plus the DOCX report as an attachment
+fn build_message(
+    report: &DiagnosticReport,
+    from: &str,
+    recipients: &[String],
+    attachment_name: String,
+    attachment_bytes: Vec<u8>,
+) -> Result<Message, EmailError> {
+    let from: Mailbox = from
+        .parse()
+        .map_err(|e| EmailError::InvalidAddress(format!("{}: {}", from, e)))?;
+
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(format!("Network Diagnostic Report - {}", report.target_url));
+
+    for recipient in recipients {
+        let mailbox: Mailbox = recipient
+            .parse()
+            .map_err(|e| EmailError::InvalidAddress(format!("{}: {}", recipient, e)))?;
+        builder = builder.to(mailbox);
+    }
+
+    let docx_type = ContentType::parse(
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    )
+    .expect("valid DOCX content type");
+
+    builder
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(email_body(report)))
+                .singlepart(Attachment::new(attachment_name).body(attachment_bytes, docx_type)),
+        )
+        .map_err(|e| EmailError::Send(e.to_string()))
+}
+
+/// Build the SMTP transport for the configured security mode
+fn build_transport(
+    smtp_config: &SmtpConfig,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, EmailError> {
+    let mut builder = match smtp_config.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp_config.host)?,
+        SmtpSecurity::StartTls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp_config.host)?
+        }
+        SmtpSecurity::None => {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp_config.host)
+        }
+    }
+    .port(smtp_config.port);
+
+    // Half a credential pair would silently send unauthenticated
+    let username = smtp_config.username.as_deref().filter(|u| !u.is_empty());
+    let password = smtp_config.password.as_deref().filter(|p| !p.is_empty());
+    match (username, password) {
+        (Some(username), Some(password)) => {
+            builder = builder.credentials(Credentials::new(username.to_string(), password.to_string()));
+        }
+        (Some(_), None) => {
+            return Err(EmailError::InvalidConfig(
+                "username is set but password is missing".to_string(),
+            ));
+        }
+        (None, Some(_)) => {
+            return Err(EmailError::InvalidConfig(
+                "password is set but username is missing".to_string(),
+            ));
+        }
+        (None, None) => {}
+    }
+
+    Ok(builder.build())
+}
+
+/// Write the report to a temp file, read it back, and clean up
+fn render_report_to_bytes(
+    report: &DiagnosticReport,
+    logs: &[TraceLogEntry],
+    file_name: &str,
+) -> Result<Vec<u8>, EmailError> {
+    let temp_path: PathBuf = std::env::temp_dir()
+        .join(format!("{}-{}", std::process::id(), file_name));
+
+    let result = generate_report(report, logs, &temp_path)
+        .map_err(EmailError::from)
+        .and_then(|_| fs::read(&temp_path).map_err(EmailError::from));
+
+    let _ = fs::remove_file(&temp_path);
+    result
+}
+
+/// Attachment file name derived from the report timestamp
+fn report_file_name(report: &DiagnosticReport) -> String {
+    let stamp: String = report
+        .timestamp
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("netcheck-report-{}.docx", stamp.trim_matches('-'))
+}
+
+/// Short plain-text summary placed in the email body
+fn email_body(report: &DiagnosticReport) -> String {
+    let status = match report.overall_status {
+        OverallStatus::Excellent => "EXCELLENT",
+        OverallStatus::Good => "GOOD",
+        OverallStatus::Acceptable => "ACCEPTABLE",
+        OverallStatus::Poor => "POOR",
+        OverallStatus::Failed => "FAILED",
+    };
+
+    let mut body = format!(
+        "Network diagnostic report for {}\nGenerated: {}\nOverall Status: {}\nIssues Found: {}\n",
+        report.target_url,
+        report.timestamp,
+        status,
+        report.issues.len()
+    );
+
+    for issue in &report.issues {
+        body.push_str(&format!("  - {}\n", issue.title));
+    }
+
+    body.push_str("\nThe full report is attached.\n\nGenerated by NetCheck v1.0.0\n");
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DiagnosticReport {
+        serde_json::from_value(serde_json::json!({
+            "target_url": "https://example.com",
+            "timestamp": "2026-10-15T06:00:00Z",
+            "overall_status": "poor",
+            "issues": [{
+                "category": "dns",
+                "severity": "warning",
+                "title": "DNS lookup chậm",
+                "description": "",
+                "possible_causes": [],
+                "solutions": [],
+            }],
+            "recommendations": [],
+        }))
+        .unwrap()
+    }
+
+    fn smtp(username: Option<&str>, password: Option<&str>) -> SmtpConfig {
+        SmtpConfig {
+            host: "smtp.example.com".to_string(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: username.map(str::to_string),
+            password: password.map(str::to_string),
+            from: "netcheck@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn half_a_credential_pair_is_rejected() {
+        assert!(matches!(build_transport(&smtp(Some("user"), None)), Err(EmailError::InvalidConfig(_))));
+        assert!(matches!(build_transport(&smtp(Some("user"), Some(""))), Err(EmailError::InvalidConfig(_))));
+        assert!(matches!(build_transport(&smtp(None, Some("secret"))), Err(EmailError::InvalidConfig(_))));
+        assert!(build_transport(&smtp(Some("user"), Some("secret"))).is_ok());
+        assert!(build_transport(&smtp(None, None)).is_ok());
+    }
+
+    #[test]
+    fn debug_output_redacts_credentials() {
+        let debug = format!("{:?}", smtp(Some("user"), Some("secret")));
+        assert!(!debug.contains("user\""));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[test]
+    fn message_carries_summary_and_attachment() {
+        let report = report();
+        let name = report_file_name(&report);
+        assert_eq!(name, "netcheck-report-2026-10-15T06-00-00Z.docx");
+
+        let message = build_message(
+            &report,
+            "netcheck@example.com",
+            &["a@example.com".to_string(), "b@example.com".to_string()],
+            name.clone(),
+            b"docx bytes".to_vec(),
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+
+        assert!(raw.contains("Subject: Network Diagnostic Report - https://example.com"));
+        assert!(raw.contains("a@example.com"));
+        assert!(raw.contains("b@example.com"));
+        assert!(raw.contains(&name));
+        assert!(raw.contains("application/vnd.openxmlformats-officedocument.wordprocessingml.document"));
+    }
+
+    #[test]
+    fn invalid_recipient_is_an_address_error() {
+        let result = build_message(&report(), "netcheck@example.com", &["not an address".to_string()], String::new(), Vec::new());
+        assert!(matches!(result, Err(EmailError::InvalidAddress(_))));
+    }
+
+    #[test]
+    fn body_lists_status_and_issue_titles() {
+        let body = email_body(&report());
+        assert!(body.contains("Overall Status: POOR"));
+        assert!(body.contains("  - DNS lookup chậm\n"));
+    }
+}
@@ -0,0 +1,75 @@
+//! Raw output of the external tools a diagnostic runs
+//!
+//! When `capture_raw` is set, every dig/curl/traceroute/ping/openssl call made
+//! while the checks run is recorded with its exit code, stdout and stderr, so
+//! the exact command output can be attached to the report for support staff.
+//! Recording is scoped to the diagnostic task; outside a capture it is a no-op.
+
+use crate::types::RawCommandOutput;
+use chrono::Utc;
+use std::future::Future;
+use std::io;
+use std::process::{Command, Output};
+use std::sync::Mutex;
+
+/// Per-stream cap so a chatty tool can't bloat the report
+const MAX_STREAM_BYTES: usize = 16 * 1024;
+
+tokio::task_local! {
+    static RAW_OUTPUT: Mutex<Vec<RawCommandOutput>>;
+}
+
+/// Run `future`, collecting the raw output of the commands it runs if `enabled`
+pub async fn capture<F: Future>(enabled: bool, future: F) -> (F::Output, Vec<RawCommandOutput>) {
+    if !enabled {
+        return (future.await, Vec::new());
+    }
+
+    RAW_OUTPUT
+        .scope(Mutex::new(Vec::new()), async move {
+            let output = future.await;
+            let recorded = RAW_OUTPUT.with(|raw| std::mem::take(&mut *raw.lock().unwrap()));
+            (output, recorded)
+        })
+        .await
+}
+
+/// Run an async command to completion, recording its output
+pub async fn output_async(command: &mut tokio::process::Command) -> io::Result<Output> {
+    let output = command.output().await?;
+    record(command.as_std(), &output);
+    Ok(output)
+}
+
+/// Append one command's output to the current capture, if any
+///
+/// For commands that need stdin and so can't go through `output_async`.
+pub fn record(command: &Command, output: &Output) {
+    let _ = RAW_OUTPUT.try_with(|raw| {
+        let command_line = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        raw.lock().unwrap().push(RawCommandOutput {
+            timestamp: Utc::now().format("%H:%M:%S%.3f").to_string(),
+            command: command_line,
+            exit_code: output.status.code(),
+            stdout: truncate_stream(&output.stdout),
+            stderr: truncate_stream(&output.stderr),
+        });
+    });
+}
+
+/// Decode a captured stream, cutting it at `MAX_STREAM_BYTES`
+fn truncate_stream(bytes: &[u8]) -> String {
+    if bytes.len() <= MAX_STREAM_BYTES {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    format!(
+        "{}\n... (truncated, {} bytes total)",
+        String::from_utf8_lossy(&bytes[..MAX_STREAM_BYTES]),
+        bytes.len()
+    )
+}
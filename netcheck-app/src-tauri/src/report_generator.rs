@@ -3,9 +3,11 @@
 //! Generates professional Word documents containing all diagnostic data,
 //! issues, recommendations, and trace logs for sharing with ISPs and infrastructure teams.
 
+use crate::diagnostic::{parse_domain, ASYMMETRIC_UPLOAD_RATIO};
 use crate::types::*;
 use docx_rs::*;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io;
 use std::path::Path;
@@ -27,6 +29,15 @@ pub struct ExportRequest {
     pub logs: Vec<TraceLogEntry>,
 }
 
+/// Maximum hops listed in the routing table
+pub(crate) const MAX_REPORT_HOPS: usize = 30;
+
+/// Maximum trace log entries included in the appendix
+pub(crate) const MAX_REPORT_LOGS: usize = 500;
+
+/// Column headers for the per-IP timing table
+pub(crate) const PER_IP_HEADERS: [&str; 5] = ["IP Address", "Connect (ms)", "TTFB (ms)", "Total (ms)", "HTTP"];
+
 /// Custom error type for report generation
 #[derive(Debug)]
 pub enum ReportError {
@@ -57,7 +68,7 @@ impl From<DocxError> for ReportError {
 
 impl From<zip::result::ZipError> for ReportError {
     fn from(err: zip::result::ZipError) -> Self {
-        ReportError::Io(io::Error::new(io::ErrorKind::Other, err.to_string()))
+        ReportError::Io(io::Error::other(err.to_string()))
     }
 }
 
@@ -78,9 +89,11 @@ pub fn generate_report(
     docx = add_tcp_section(docx, report);
     docx = add_routing_section(docx, report);
     docx = add_stability_section(docx, report);
+    docx = add_bandwidth_section(docx, report);
     docx = add_issues_section(docx, report);
     docx = add_recommendations_section(docx, report);
     docx = add_trace_logs_section(docx, logs);
+    docx = add_raw_output_section(docx, report);
     docx = add_footer(docx);
 
     docx.build().pack(file)?;
@@ -120,6 +133,26 @@ fn add_header(mut docx: Docx, report: &DiagnosticReport) -> Docx {
             .align(AlignmentType::Center),
     );
 
+    // Multi-homed runs: which path the probes took
+    if let Some(source) = &report.network_source {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Network Source: ").bold())
+                .add_run(Run::new().add_text(source))
+                .align(AlignmentType::Center),
+        );
+    }
+
+    // Origin testing: results describe the chosen server, not what DNS points to
+    if let Some(target_override) = &report.target_override {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Target Override: ").bold())
+                .add_run(Run::new().add_text(target_override_text(target_override)).bold())
+                .align(AlignmentType::Center),
+        );
+    }
+
     // Tool info
     docx = docx.add_paragraph(
         Paragraph::new()
@@ -142,25 +175,7 @@ fn add_header(mut docx: Docx, report: &DiagnosticReport) -> Docx {
 
 /// Add executive summary section
 fn add_executive_summary(mut docx: Docx, report: &DiagnosticReport) -> Docx {
-    let (status_text, status_description) = match report.overall_status {
-        OverallStatus::Excellent => (
-            "EXCELLENT",
-            "No issues detected. Network connection is optimal.",
-        ),
-        OverallStatus::Good => ("GOOD", "Minor observations only. Connection is stable."),
-        OverallStatus::Acceptable => (
-            "ACCEPTABLE",
-            "Some areas need attention but connection works.",
-        ),
-        OverallStatus::Poor => (
-            "POOR",
-            "Significant issues detected. Performance is degraded.",
-        ),
-        OverallStatus::Failed => (
-            "FAILED",
-            "Critical problems require immediate action.",
-        ),
-    };
+    let (status_text, status_description) = overall_status_text(&report.overall_status);
 
     // Section title
     docx = docx.add_paragraph(
@@ -183,6 +198,20 @@ fn add_executive_summary(mut docx: Docx, report: &DiagnosticReport) -> Docx {
             .add_run(Run::new().add_text(format!(" - {}", status_description))),
     );
 
+    // Contractual verdict, independent of the status above
+    if let Some((verdict, checks)) = sla_text(report) {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("SLA: ").bold())
+                .add_run(Run::new().add_text(verdict).bold()),
+        );
+        for check in checks {
+            docx = docx.add_paragraph(
+                Paragraph::new().add_run(Run::new().add_text(format!("  {}", check))),
+            );
+        }
+    }
+
     // Issue count
     docx = docx.add_paragraph(
         Paragraph::new()
@@ -221,10 +250,185 @@ fn add_executive_summary(mut docx: Docx, report: &DiagnosticReport) -> Docx {
         );
     }
 
+    if !report.score_breakdown.is_empty() {
+        docx = docx.add_paragraph(
+            Paragraph::new().add_run(Run::new().add_text("Score Breakdown:").bold()),
+        );
+        for line in score_breakdown_lines(report) {
+            docx = docx.add_paragraph(
+                Paragraph::new().add_run(Run::new().add_text(format!("  {}", line))),
+            );
+        }
+    }
+
+    if !report.summary_line.is_empty() {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Summary: ").bold())
+                .add_run(Run::new().add_text(&report.summary_line)),
+        );
+    }
+
+    if let Some(delta) = &report.delta_summary {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Since Previous Run: ").bold())
+                .add_run(Run::new().add_text(delta)),
+        );
+    }
+
+    // Headline responsiveness summary
+    if let Some(quality) = &report.network_quality {
+        let (grade_line, metrics_line) = network_quality_text(quality);
+
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Network Quality: ").bold())
+                .add_run(Run::new().add_text(grade_line).bold()),
+        );
+        docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(metrics_line)));
+    }
+
+    // Profile and thresholds the analysis graded against
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Diagnostic Profile: ").bold())
+            .add_run(Run::new().add_text(
+                report.profile.map(|p| p.label()).unwrap_or("Default"),
+            ))
+            .add_run(Run::new().add_text(if report.strict {
+                " (strict mode: warnings count as failures)"
+            } else {
+                ""
+            })),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Thresholds: ").bold())
+            .add_run(Run::new().add_text(thresholds_text(report))),
+    );
+
     docx = docx.add_paragraph(Paragraph::new());
     docx
 }
 
+/// Score deductions as "-15 reason" lines, ending with the resulting score
+pub(crate) fn score_breakdown_lines(report: &DiagnosticReport) -> Vec<String> {
+    let mut lines: Vec<String> = report
+        .score_breakdown
+        .iter()
+        .map(|(reason, points)| format!("{:>4} {}", points, reason))
+        .collect();
+    let score = 100 + report.score_breakdown.iter().map(|(_, points)| points).sum::<i32>();
+    lines.push(format!("= {}/100", score));
+    lines
+}
+
+/// SLA verdict line and one line per checked metric, `None` without an SLA
+pub(crate) fn sla_text(report: &DiagnosticReport) -> Option<(String, Vec<String>)> {
+    let met = report.sla_met?;
+    let failed = report.sla_checks.iter().filter(|c| !c.passed).count();
+    let verdict = if met {
+        "MET".to_string()
+    } else {
+        format!("NOT MET ({} of {} limits failed)", failed, report.sla_checks.len())
+    };
+    let checks = report
+        .sla_checks
+        .iter()
+        .map(|check| {
+            format!(
+                "[{}] {}: {} (required {})",
+                if check.passed { "PASS" } else { "FAIL" },
+                check.metric,
+                check.actual.map_or("not measured".to_string(), |v| format!("{:.1}", v)),
+                check.requirement
+            )
+        })
+        .collect();
+    Some((verdict, checks))
+}
+
+/// Header note for a connect address / Host+SNI override
+pub(crate) fn target_override_text(target_override: &str) -> String {
+    format!("{} (results reflect this server, not public DNS)", target_override)
+}
+
+/// Status label and one-line description for the overall status
+pub(crate) fn overall_status_text(status: &OverallStatus) -> (&'static str, &'static str) {
+    match status {
+        OverallStatus::Excellent => (
+            "EXCELLENT",
+            "No issues detected. Network connection is optimal.",
+        ),
+        OverallStatus::Good => ("GOOD", "Minor observations only. Connection is stable."),
+        OverallStatus::Acceptable => (
+            "ACCEPTABLE",
+            "Some areas need attention but connection works.",
+        ),
+        OverallStatus::Poor => (
+            "POOR",
+            "Significant issues detected. Performance is degraded.",
+        ),
+        OverallStatus::Failed => (
+            "FAILED",
+            "Critical problems require immediate action.",
+        ),
+    }
+}
+
+/// Network quality as (grade line, latency/throughput line)
+pub(crate) fn network_quality_text(quality: &NetworkQuality) -> (String, String) {
+    let grade_text = match quality.grade {
+        Some(QualityGrade::A) => "A",
+        Some(QualityGrade::B) => "B",
+        Some(QualityGrade::C) => "C",
+        Some(QualityGrade::D) => "D",
+        Some(QualityGrade::F) => "F",
+        None => "N/A",
+    };
+    let rpm_text = quality
+        .rpm
+        .map(|rpm| format!(" ({} RPM)", rpm))
+        .unwrap_or_default();
+    let format_ms = |value: Option<f64>| {
+        value
+            .map(|ms| format!("{:.0} ms", ms))
+            .unwrap_or_else(|| "not measured".to_string())
+    };
+
+    (
+        format!("Grade {}{}", grade_text, rpm_text),
+        format!(
+            "Idle Latency: {} | Loaded Latency: {} | Throughput: {}",
+            format_ms(quality.idle_latency_ms),
+            format_ms(quality.loaded_latency_ms),
+            quality
+                .throughput_kbps
+                .map(|kbps| format!("{:.2} KB/s", kbps))
+                .unwrap_or_else(|| "not measured".to_string())
+        ),
+    )
+}
+
+/// Thresholds the analysis graded against, on one line
+pub(crate) fn thresholds_text(report: &DiagnosticReport) -> String {
+    let thresholds = &report.thresholds;
+    let mut text = format!(
+        "DNS < {:.0} ms | Connect < {:.0} ms | SSL < {:.0} ms | Total < {:.0} ms | Jitter < {:.0} ms",
+        thresholds.dns_ms,
+        thresholds.connect_ms,
+        thresholds.ssl_ms,
+        thresholds.total_ms,
+        thresholds.jitter_ms
+    );
+    if let Some(ttfb_ms) = thresholds.ttfb_ms {
+        text.push_str(&format!(" | TTFB < {:.0} ms", ttfb_ms));
+    }
+    text
+}
+
 /// Add DNS resolution section
 fn add_dns_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx = docx.add_paragraph(
@@ -254,6 +458,15 @@ fn add_dns_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                     })),
             );
 
+            // IPv6 addresses
+            if !dns.resolved_ipv6.is_empty() {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("IPv6 Addresses: ").bold())
+                        .add_run(Run::new().add_text(dns.resolved_ipv6.join(", "))),
+                );
+            }
+
             // Lookup time
             docx = docx.add_paragraph(
                 Paragraph::new()
@@ -281,6 +494,30 @@ fn add_dns_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                 }
             }
 
+            // Resolver backend
+            if let Some(backend) = &dns.backend {
+                let backend_text = match backend {
+                    DnsBackend::Native => "System resolver",
+                    DnsBackend::Dig => "dig",
+                    DnsBackend::Nslookup => "nslookup",
+                    DnsBackend::Host => "host",
+                };
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Resolved Via: ").bold())
+                        .add_run(Run::new().add_text(backend_text)),
+                );
+            }
+
+            // Public resolver fallback
+            if let Some(resolver) = &dns.resolver {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Resolver: ").bold())
+                        .add_run(Run::new().add_text(public_resolver_text(resolver, dns))),
+                );
+            }
+
             // CDN detection
             if let Some(cdn) = &dns.using_cdn {
                 docx = docx.add_paragraph(
@@ -289,6 +526,36 @@ fn add_dns_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                         .add_run(Run::new().add_text(cdn)),
                 );
             }
+
+            // Encrypted DNS
+            if let Some(secure_dns) = &report.secure_dns {
+                docx = add_secure_dns_details(docx, secure_dns);
+            }
+
+            // IP reputation
+            if let Some(blocklist) = &report.blocklist {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Blocklist Check: ").bold())
+                        .add_run(Run::new().add_text(format!(
+                            "{} IP(s) against {} list(s), {} listing(s)",
+                            blocklist.checked_ips.len(),
+                            blocklist.blocklists.len(),
+                            blocklist.listings.len()
+                        ))),
+                );
+                for listing in &blocklist.listings {
+                    docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(
+                        format!(
+                            "  - {}{} listed on {} ({})",
+                            listing.ip,
+                            if listing.is_own_ip { " (your IP)" } else { "" },
+                            listing.blocklist,
+                            listing.return_code
+                        ),
+                    )));
+                }
+            }
         }
         None => {
             docx = docx.add_paragraph(
@@ -302,8 +569,59 @@ fn add_dns_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx
 }
 
+/// Describe a public resolver fallback, including why the local one failed
+pub(crate) fn public_resolver_text(resolver: &str, dns: &DnsResult) -> String {
+    format!(
+        "Public resolver {} (local resolver failed: {})",
+        resolver,
+        dns.local_resolver_error.as_deref().unwrap_or("unknown error")
+    )
+}
+
+/// Add DNS-over-HTTPS / DNS-over-TLS probe results
+fn add_secure_dns_details(mut docx: Docx, secure_dns: &SecureDnsResult) -> Docx {
+    let dot_text = match secure_dns.dot_available {
+        Some(true) => "Available",
+        Some(false) => "Blocked",
+        None => "Not tested (kdig not installed)",
+    };
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Encrypted DNS: ").bold())
+            .add_run(Run::new().add_text(format!(
+                "DoH {}, DoT {}",
+                if secure_dns.doh_available { "Available" } else { "Blocked" },
+                dot_text
+            ))),
+    );
+
+    for probe in &secure_dns.probes {
+        let protocol = match probe.protocol {
+            SecureDnsProtocol::Doh => "DoH",
+            SecureDnsProtocol::Dot => "DoT",
+        };
+        let outcome = if probe.success {
+            format!("OK in {:.0} ms", probe.time_ms)
+        } else {
+            format!("failed ({})", probe.error.as_deref().unwrap_or("unknown error"))
+        };
+        docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(format!(
+            "  - {} {} ({}): {}",
+            probe.provider, protocol, probe.endpoint, outcome
+        ))));
+    }
+
+    docx
+}
+
 /// Add TCP connection timing section
 fn add_tcp_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
+    // Raw host:port targets have no HTTP/SSL timings to show
+    if let Some(port_check) = &report.port_check {
+        return add_port_check_section(docx, port_check);
+    }
+
     docx = docx.add_paragraph(
         Paragraph::new()
             .add_run(
@@ -411,12 +729,62 @@ fn add_tcp_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                     .add_run(Run::new().add_text(tcp.http_code.to_string())),
             );
 
+            if let Some(ip) = &tcp.remote_ip {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Connected IP: ").bold())
+                        .add_run(Run::new().add_text(ip)),
+                );
+            }
+
+            if !tcp.redirect_chain.is_empty() {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Redirects: ").bold())
+                        .add_run(Run::new().add_text(tcp.redirect_chain.join(" -> "))),
+                );
+            }
+
+            if let Some(reason) = &tcp.failure_reason {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Failure Reason: ").bold())
+                        .add_run(Run::new().add_text(reason)),
+                );
+            }
+
             // Download speed
             docx = docx.add_paragraph(
                 Paragraph::new()
                     .add_run(Run::new().add_text("Download Speed: ").bold())
                     .add_run(Run::new().add_text(format!("{:.2} Kbps", tcp.download_speed_kbps))),
             );
+
+            if let Some(headers) = &tcp.security_headers {
+                docx = docx.add_paragraph(Paragraph::new());
+                docx = docx.add_paragraph(
+                    Paragraph::new().add_run(Run::new().add_text("Security Headers:").bold()),
+                );
+                for line in security_header_lines(headers) {
+                    docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(line)));
+                }
+            }
+
+            if let Some(per_ip) = &report.per_ip_tcp {
+                docx = add_per_ip_timing(docx, per_ip);
+            }
+
+            if let Some(cert) = &report.ssl_cert {
+                docx = add_ssl_certificate_details(docx, cert);
+            }
+
+            if let Some(proxy) = &report.proxy {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Transparent Proxy: ").bold())
+                        .add_run(Run::new().add_text(proxy_detection_text(proxy))),
+                );
+            }
         }
         None => {
             docx = docx.add_paragraph(
@@ -430,8 +798,276 @@ fn add_tcp_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx
 }
 
+/// Security header lines, "  - Name: value" or "  - Name: (missing)"
+pub(crate) fn security_header_lines(headers: &SecurityHeaders) -> Vec<String> {
+    [
+        ("Strict-Transport-Security", &headers.hsts),
+        ("Content-Security-Policy", &headers.content_security_policy),
+        ("X-Frame-Options", &headers.x_frame_options),
+        ("X-Content-Type-Options", &headers.x_content_type_options),
+    ]
+    .iter()
+    .map(|(name, value)| format!("  - {}: {}", name, value.as_deref().unwrap_or("(missing)")))
+    .collect()
+}
+
+/// Per-IP timing row: IP, connect, TTFB, total, HTTP code / failure
+pub(crate) fn per_ip_timing_row(ip: &str, tcp: &TcpResult) -> Vec<String> {
+    let outcome = match &tcp.failure_reason {
+        Some(reason) => reason.clone(),
+        None => tcp.http_code.to_string(),
+    };
+    vec![
+        ip.to_string(),
+        format!("{:.2}", tcp.connect_time_ms),
+        format!("{:.2}", tcp.ttfb_ms),
+        format!("{:.2}", tcp.total_time_ms),
+        outcome,
+    ]
+}
+
+/// Add TCP timing per resolved IP (load-balanced targets)
+fn add_per_ip_timing(mut docx: Docx, per_ip: &BTreeMap<String, TcpResult>) -> Docx {
+    docx = docx.add_paragraph(Paragraph::new());
+
+    docx = docx.add_paragraph(
+        Paragraph::new().add_run(Run::new().add_text("Per-IP Timing:").bold()),
+    );
+
+    let mut rows = vec![TableRow::new(
+        PER_IP_HEADERS
+            .iter()
+            .map(|header| {
+                TableCell::new()
+                    .add_paragraph(Paragraph::new().add_run(Run::new().add_text(*header).bold()))
+            })
+            .collect(),
+    )];
+
+    for (ip, tcp) in per_ip {
+        rows.push(TableRow::new(
+            per_ip_timing_row(ip, tcp)
+                .into_iter()
+                .map(|cell| {
+                    TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(cell)))
+                })
+                .collect(),
+        ));
+    }
+
+    docx.add_table(Table::new(rows))
+}
+
+/// Add SSL certificate chain details
+fn add_ssl_certificate_details(mut docx: Docx, cert: &SslCertInfo) -> Docx {
+    docx = docx.add_paragraph(Paragraph::new());
+
+    docx = docx.add_paragraph(
+        Paragraph::new().add_run(Run::new().add_text("SSL Certificate:").bold()),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("  - Subject: "))
+            .add_run(Run::new().add_text(&cert.subject)),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("  - Issuer: "))
+            .add_run(Run::new().add_text(&cert.issuer)),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("  - Chain Complete: "))
+            .add_run(Run::new().add_text(if cert.chain_complete {
+                "Yes"
+            } else {
+                "No - some clients will fail the handshake"
+            })),
+    );
+
+    if let Some(message) = &cert.verify_message {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("  - Verification: "))
+                .add_run(Run::new().add_text(message)),
+        );
+    }
+
+    if let Some(validity) = cert_validity_text(cert) {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("  - Valid: "))
+                .add_run(Run::new().add_text(validity)),
+        );
+    }
+
+    docx = docx.add_paragraph(
+        Paragraph::new().add_run(Run::new().add_text("Certificate Chain:").bold()),
+    );
+
+    for (idx, subject) in cert.chain_subjects.iter().enumerate() {
+        docx = docx.add_paragraph(
+            Paragraph::new().add_run(Run::new().add_text(format!("  {}. {}", idx, subject))),
+        );
+    }
+
+    docx
+}
+
+/// One-line verdict from whichever phases produced results
+///
+/// e.g. "example.com: GOOD — DNS 45ms, connect 120ms, 100% stable, 8 hops"
+pub(crate) fn summary_line(report: &DiagnosticReport) -> String {
+    let host = parse_domain(&report.target_url).unwrap_or_else(|_| report.target_url.clone());
+    let mut parts: Vec<String> = Vec::new();
+
+    if let Some(dns) = &report.dns {
+        parts.push(if dns.resolved_ips.is_empty() {
+            "DNS failed".to_string()
+        } else {
+            format!("DNS {:.0}ms", dns.lookup_time_ms)
+        });
+    }
+
+    if let Some(tcp) = &report.tcp {
+        parts.push(if tcp.http_code == 0 {
+            "connection failed".to_string()
+        } else {
+            format!(
+                "connect {:.0}ms, HTTP {}",
+                tcp.connect_time_ms - tcp.dns_time_ms,
+                tcp.http_code
+            )
+        });
+    } else if let Some(port) = &report.port_check {
+        parts.push(if port.connected {
+            format!("port {} open, connect {:.0}ms", port.port, port.connect_time_ms)
+        } else {
+            format!("port {} unreachable", port.port)
+        });
+    }
+
+    if let Some(stability) = &report.stability {
+        parts.push(format!("{:.0}% stable", stability.success_rate));
+    }
+
+    if let Some(routing) = &report.routing {
+        parts.push(format!("{} hops", routing.total_hops));
+    }
+
+    let (status, _) = overall_status_text(&report.overall_status);
+    if parts.is_empty() {
+        format!("{}: {} — no measurements", host, status)
+    } else {
+        format!("{}: {} — {}", host, status, parts.join(", "))
+    }
+}
+
+/// Leaf certificate validity window, `None` when the dates weren't read
+pub(crate) fn cert_validity_text(cert: &SslCertInfo) -> Option<String> {
+    let (not_before, not_after) = (cert.not_before?, cert.not_after?);
+    Some(format!(
+        "{} to {}",
+        not_before.format("%Y-%m-%d %H:%M UTC"),
+        not_after.format("%Y-%m-%d %H:%M UTC")
+    ))
+}
+
+/// One-line summary of the transparent proxy check
+pub(crate) fn proxy_detection_text(proxy: &ProxyDetectionResult) -> String {
+    if !proxy.proxy_detected {
+        return "Not detected".to_string();
+    }
+
+    let mut evidence: Vec<String> = Vec::new();
+    if proxy.content_modified {
+        evidence.push(format!("control response modified (HTTP {})", proxy.http_code));
+    }
+    if !proxy.proxy_headers.is_empty() {
+        evidence.push(format!("headers: {}", proxy.proxy_headers.join("; ")));
+    }
+    format!(
+        "Detected - {}. Timing and content results may be affected",
+        evidence.join(", ")
+    )
+}
+
+/// Add raw TCP connect section for host:port targets
+fn add_port_check_section(mut docx: Docx, port_check: &PortCheckResult) -> Docx {
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(
+                Run::new()
+                    .add_text("3. TCP CONNECTION (RAW PORT)")
+                    .bold()
+                    .size(32),
+            ),
+    );
+
+    docx = docx.add_paragraph(Paragraph::new());
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Service: ").bold())
+            .add_run(Run::new().add_text(format!(
+                "{}:{} ({})",
+                port_check.host, port_check.port, port_check.remote_ip
+            ))),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Port Status: ").bold())
+            .add_run(Run::new().add_text(if port_check.connected {
+                "Open"
+            } else {
+                "Closed / Unreachable"
+            })),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Connect Time: ").bold())
+            .add_run(Run::new().add_text(format!(
+                "{:.2} ms ({})",
+                port_check.connect_time_ms,
+                get_timing_status(port_check.connect_time_ms, 200.0, 500.0)
+            ))),
+    );
+
+    if let Some(banner) = &port_check.banner {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Service Banner: ").bold())
+                .add_run(Run::new().add_text(banner)),
+        );
+    }
+
+    if let Some(error) = &port_check.error {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("Error: ").bold())
+                .add_run(Run::new().add_text(error)),
+        );
+    }
+
+    docx = docx.add_paragraph(
+        Paragraph::new().add_run(
+            Run::new()
+                .add_text("(Raw TCP mode: SSL and HTTP checks were skipped)")
+                .italic(),
+        ),
+    );
+
+    docx = docx.add_paragraph(Paragraph::new());
+    docx
+}
+
 /// Get status text based on timing thresholds
-fn get_timing_status(value: f64, good_threshold: f64, warning_threshold: f64) -> &'static str {
+pub(crate) fn get_timing_status(value: f64, good_threshold: f64, warning_threshold: f64) -> &'static str {
     if value <= good_threshold {
         "Good"
     } else if value <= warning_threshold {
@@ -441,6 +1077,14 @@ fn get_timing_status(value: f64, good_threshold: f64, warning_threshold: f64) ->
     }
 }
 
+/// Format an aggregate hop latency, "N/A" when no hop responded
+pub(crate) fn format_hop_latency(value: Option<f64>) -> String {
+    match value {
+        Some(ms) => format!("{:.2} ms", ms),
+        None => "N/A (no hop responded)".to_string(),
+    }
+}
+
 /// Add network routing (traceroute) section
 fn add_routing_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx = docx.add_paragraph(
@@ -473,6 +1117,32 @@ fn add_routing_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                     .add_run(Run::new().add_text(format!("{:.2} ms", routing.total_time_ms))),
             );
 
+            // Timeout hops are excluded so they don't read as 0ms
+            docx = docx.add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Avg Hop RTT: ").bold())
+                    .add_run(Run::new().add_text(format_hop_latency(routing.avg_hop_latency_ms)))
+                    .add_run(Run::new().add_text("  |  "))
+                    .add_run(Run::new().add_text("Max Hop RTT: ").bold())
+                    .add_run(Run::new().add_text(format_hop_latency(routing.max_hop_latency_ms))),
+            );
+
+            if let Some(destination) = destination_text(routing) {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Destination: ").bold())
+                        .add_run(Run::new().add_text(destination)),
+                );
+            }
+
+            if let Some(routing_loop) = routing_loop_text(routing) {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Routing Loop: ").bold())
+                        .add_run(Run::new().add_text(routing_loop)),
+                );
+            }
+
             docx = docx.add_paragraph(Paragraph::new());
 
             // Create hop table
@@ -496,8 +1166,8 @@ fn add_routing_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                 ]),
             ];
 
-            // Add hop rows (limit to 30 to avoid very long tables)
-            for hop in routing.hops.iter().take(30) {
+            // Add hop rows (limited to avoid very long tables)
+            for hop in routing.hops.iter().take(MAX_REPORT_HOPS) {
                 rows.push(TableRow::new(vec![
                     TableCell::new().add_paragraph(
                         Paragraph::new().add_run(Run::new().add_text(hop.hop_number.to_string())),
@@ -511,10 +1181,9 @@ fn add_routing_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                         )),
                     ),
                     TableCell::new().add_paragraph(
-                        Paragraph::new().add_run(Run::new().add_text(if hop.ip_address == "*" {
-                            "*".to_string()
-                        } else {
-                            format!("{:.2}", hop.rtt_ms)
+                        Paragraph::new().add_run(Run::new().add_text(match hop.rtt_ms_opt {
+                            Some(rtt) => format!("{:.2}", rtt),
+                            None => "*".to_string(),
                         })),
                     ),
                     TableCell::new().add_paragraph(
@@ -524,12 +1193,13 @@ fn add_routing_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                 ]));
             }
 
-            if routing.hops.len() > 30 {
+            if routing.hops.len() > MAX_REPORT_HOPS {
                 docx = docx.add_paragraph(
                     Paragraph::new().add_run(
                         Run::new()
                             .add_text(format!(
-                                "(Showing 30 of {} hops)",
+                                "(Showing {} of {} hops)",
+                                MAX_REPORT_HOPS,
                                 routing.hops.len()
                             ))
                             .italic(),
@@ -548,10 +1218,100 @@ fn add_routing_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
         }
     }
 
+    if let Some(sweep) = &report.latency_by_size {
+        docx = add_latency_by_size(docx, sweep);
+    }
+
     docx = docx.add_paragraph(Paragraph::new());
     docx
 }
 
+/// Whether the trace reached the target, `None` when unknown
+pub(crate) fn destination_text(routing: &RoutingResult) -> Option<&'static str> {
+    routing.destination_reached.map(|reached| {
+        if reached {
+            "Reached"
+        } else {
+            "Not reached (incomplete path)"
+        }
+    })
+}
+
+/// Repeating IPs with the hops they answered at, `None` without a loop
+pub(crate) fn routing_loop_text(routing: &RoutingResult) -> Option<String> {
+    if routing.loop_ips.is_empty() {
+        return None;
+    }
+    let ips: Vec<String> = routing
+        .loop_ips
+        .iter()
+        .map(|ip| {
+            let hops: Vec<String> = routing.hop_numbers_of(ip).iter().map(|n| n.to_string()).collect();
+            format!("{} (hops {})", ip, hops.join(", "))
+        })
+        .collect();
+    let cut_at = routing.hops.last().map(|h| h.hop_number).unwrap_or_default();
+    Some(format!("{}; trace cut at hop {}", ips.join(", "), cut_at))
+}
+
+/// Column headers for the latency-by-size table
+pub(crate) const LATENCY_BY_SIZE_HEADERS: [&str; 5] =
+    ["Payload (bytes)", "Min (ms)", "Avg (ms)", "Max (ms)", "Packet Loss"];
+
+/// Latency-by-size row, "*" for sizes that got no reply, "DF" marks the
+/// don't-fragment probe
+pub(crate) fn latency_by_size_row(sample: &PingSizeSample) -> Vec<String> {
+    let format_rtt = |value: Option<f64>| match value {
+        Some(ms) => format!("{:.2}", ms),
+        None => "*".to_string(),
+    };
+    vec![
+        if sample.dont_fragment {
+            format!("{} DF", sample.payload_bytes)
+        } else {
+            sample.payload_bytes.to_string()
+        },
+        format_rtt(sample.min_rtt_ms),
+        format_rtt(sample.avg_rtt_ms),
+        format_rtt(sample.max_rtt_ms),
+        format!("{:.1}%", sample.packet_loss_percent),
+    ]
+}
+
+/// Add ping latency per payload size
+fn add_latency_by_size(mut docx: Docx, sweep: &LatencyBySizeResult) -> Docx {
+    docx = docx.add_paragraph(Paragraph::new());
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("Latency by Packet Size: ").bold())
+            .add_run(Run::new().add_text(format!("ping {}", sweep.target_ip))),
+    );
+
+    let mut rows = vec![TableRow::new(
+        LATENCY_BY_SIZE_HEADERS
+            .iter()
+            .map(|header| {
+                TableCell::new()
+                    .add_paragraph(Paragraph::new().add_run(Run::new().add_text(*header).bold()))
+            })
+            .collect(),
+    )];
+
+    for sample in sweep.samples.iter().chain(&sweep.mtu_probe) {
+        rows.push(TableRow::new(
+            latency_by_size_row(sample)
+                .into_iter()
+                .map(|cell| {
+                    TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(cell)))
+                })
+                .collect(),
+        ));
+    }
+
+    docx.add_table(Table::new(rows))
+}
+
 /// Add connection stability section
 fn add_stability_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx = docx.add_paragraph(
@@ -581,6 +1341,22 @@ fn add_stability_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
                     .add_run(Run::new().add_text(format!("{:.1}%", stability.success_rate))),
             );
 
+            if let Some(failures) = stability_failures_text(stability) {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Failures: ").bold())
+                        .add_run(Run::new().add_text(failures)),
+                );
+            }
+
+            if let Some(ip_changes) = ip_changes_text(stability) {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Resolved IPs: ").bold())
+                        .add_run(Run::new().add_text(ip_changes)),
+                );
+            }
+
             docx = docx.add_paragraph(Paragraph::new());
 
             // Response time metrics
@@ -615,21 +1391,15 @@ fn add_stability_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
 
             // Add interpretation
             docx = docx.add_paragraph(Paragraph::new());
-            let jitter_status = if stability.jitter_ms < 30.0 {
-                "Excellent - Very stable connection"
-            } else if stability.jitter_ms < 50.0 {
-                "Good - Stable connection suitable for most applications"
-            } else if stability.jitter_ms < 100.0 {
-                "Acceptable - Some variation, may affect real-time applications"
-            } else {
-                "Poor - High variation, may cause issues with video calls and gaming"
-            };
-
             docx = docx.add_paragraph(
                 Paragraph::new()
                     .add_run(Run::new().add_text("Jitter Assessment: ").bold())
-                    .add_run(Run::new().add_text(jitter_status)),
+                    .add_run(Run::new().add_text(jitter_assessment(stability.jitter_ms))),
             );
+
+            if !report.realtime_suitability.is_empty() {
+                docx = add_realtime_suitability(docx, &report.realtime_suitability);
+            }
         }
         None => {
             docx = docx.add_paragraph(
@@ -643,11 +1413,171 @@ fn add_stability_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx
 }
 
+/// Add download vs upload bandwidth section
+fn add_bandwidth_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(
+                Run::new()
+                    .add_text("6. BANDWIDTH (DOWNLOAD VS UPLOAD)")
+                    .bold()
+                    .size(32),
+            ),
+    );
+
+    docx = docx.add_paragraph(Paragraph::new());
+
+    match &report.bandwidth {
+        Some(bandwidth) => {
+            for (label, value) in bandwidth_lines(bandwidth) {
+                docx = docx.add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text(format!("{}: ", label)).bold())
+                        .add_run(Run::new().add_text(value)),
+                );
+            }
+        }
+        None => {
+            docx = docx.add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Bandwidth test failed or was not performed.")),
+            );
+        }
+    }
+
+    docx = docx.add_paragraph(Paragraph::new());
+    docx
+}
+
+/// Bandwidth fields as (label, value): server, both directions, and the ratio
+pub(crate) fn bandwidth_lines(bandwidth: &BandwidthResult) -> Vec<(&'static str, String)> {
+    let ratio = match bandwidth.upload_ratio {
+        Some(ratio) if ratio < ASYMMETRIC_UPLOAD_RATIO => {
+            format!("{:.1}% (strongly asymmetric: real-time apps may suffer)", ratio * 100.0)
+        }
+        Some(ratio) => format!("{:.1}%", ratio * 100.0),
+        None => "N/A".to_string(),
+    };
+    vec![
+        ("Server", bandwidth.server.clone()),
+        ("Download", format!("{:.2} KB/s", bandwidth.download_kbps)),
+        ("Upload", format!("{:.2} KB/s", bandwidth.upload_kbps)),
+        ("Upload/Download Ratio", ratio),
+    ]
+}
+
+/// Column headers for the real-time suitability table
+pub(crate) const REALTIME_HEADERS: [&str; 4] = ["Use Case", "Requirement", "Measured", "Verdict"];
+
+/// Real-time suitability row: use case, requirement, measured values, verdict
+pub(crate) fn realtime_verdict_row(verdict: &UseCaseVerdict) -> Vec<String> {
+    let use_case = match verdict.use_case {
+        RealtimeUseCase::Voip => "VoIP",
+        RealtimeUseCase::Gaming => "Gaming",
+        RealtimeUseCase::VideoCall => "Video Calls",
+    };
+
+    let mut measured: Vec<String> = Vec::new();
+    if let Some(latency) = verdict.latency_ms {
+        measured.push(format!("latency {:.0} ms", latency));
+    }
+    if let Some(jitter) = verdict.jitter_ms {
+        measured.push(format!("jitter {:.0} ms", jitter));
+    }
+    if let Some(loss) = verdict.loss_percent {
+        measured.push(format!("loss {:.1}%", loss));
+    }
+
+    let verdict_text = match verdict.suitable {
+        Some(true) => "Suitable",
+        Some(false) => "Not suitable",
+        None => "Not measured",
+    };
+
+    vec![
+        use_case.to_string(),
+        verdict.requirement.clone(),
+        if measured.is_empty() {
+            "-".to_string()
+        } else {
+            measured.join(", ")
+        },
+        verdict_text.to_string(),
+    ]
+}
+
+/// Add per-use-case real-time suitability table
+fn add_realtime_suitability(mut docx: Docx, verdicts: &[UseCaseVerdict]) -> Docx {
+    docx = docx.add_paragraph(Paragraph::new());
+
+    docx = docx.add_paragraph(
+        Paragraph::new().add_run(Run::new().add_text("Real-time Suitability:").bold()),
+    );
+
+    let mut rows = vec![TableRow::new(
+        REALTIME_HEADERS
+            .iter()
+            .map(|header| {
+                TableCell::new()
+                    .add_paragraph(Paragraph::new().add_run(Run::new().add_text(*header).bold()))
+            })
+            .collect(),
+    )];
+
+    for verdict in verdicts {
+        rows.push(TableRow::new(
+            realtime_verdict_row(verdict)
+                .into_iter()
+                .map(|cell| {
+                    TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(cell)))
+                })
+                .collect(),
+        ));
+    }
+
+    docx.add_table(Table::new(rows))
+}
+
+/// Failed probes by cause, `None` when every probe succeeded
+pub(crate) fn stability_failures_text(stability: &StabilityResult) -> Option<String> {
+    let failures = stability.timeouts + stability.connection_errors + stability.http_errors;
+    (failures > 0).then(|| {
+        format!(
+            "{} timeouts, {} connection errors, {} HTTP errors",
+            stability.timeouts, stability.connection_errors, stability.http_errors
+        )
+    })
+}
+
+/// Resolved IPs seen while re-resolving between probes, `None` if not enabled
+pub(crate) fn ip_changes_text(stability: &StabilityResult) -> Option<String> {
+    (!stability.seen_ips.is_empty()).then(|| {
+        format!(
+            "{} ({} changes during the test)",
+            stability.seen_ips.join(", "),
+            stability.ip_changes
+        )
+    })
+}
+
+/// Interpretation of a jitter value
+pub(crate) fn jitter_assessment(jitter_ms: f64) -> &'static str {
+    if jitter_ms < 30.0 {
+        "Excellent - Very stable connection"
+    } else if jitter_ms < 50.0 {
+        "Good - Stable connection suitable for most applications"
+    } else if jitter_ms < 100.0 {
+        "Acceptable - Some variation, may affect real-time applications"
+    } else {
+        "Poor - High variation, may cause issues with video calls and gaming"
+    }
+}
+
 /// Add detected issues section
 fn add_issues_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx = docx.add_paragraph(
         Paragraph::new()
-            .add_run(Run::new().add_text("6. DETECTED ISSUES").bold().size(32)),
+            .add_run(Run::new().add_text("7. DETECTED ISSUES").bold().size(32)),
     );
 
     docx = docx.add_paragraph(Paragraph::new());
@@ -660,20 +1590,8 @@ fn add_issues_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     } else {
         for (idx, issue) in report.issues.iter().enumerate() {
             // Issue header with severity icon
-            let severity_marker = match issue.severity {
-                IssueSeverity::Error => "[ERROR]",
-                IssueSeverity::Warning => "[WARNING]",
-                IssueSeverity::Info => "[INFO]",
-            };
-
-            let category_text = match issue.category {
-                IssueCategory::Dns => "DNS",
-                IssueCategory::Tcp => "TCP",
-                IssueCategory::Ssl => "SSL",
-                IssueCategory::Routing => "Routing",
-                IssueCategory::Stability => "Stability",
-                IssueCategory::Http => "HTTP",
-            };
+            let severity_marker = severity_marker(&issue.severity);
+            let category_text = category_text(&issue.category);
 
             docx = docx.add_paragraph(
                 Paragraph::new()
@@ -726,11 +1644,32 @@ fn add_issues_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx
 }
 
+/// Bracketed severity marker, e.g. "[WARNING]"
+pub(crate) fn severity_marker(severity: &IssueSeverity) -> &'static str {
+    match severity {
+        IssueSeverity::Error => "[ERROR]",
+        IssueSeverity::Warning => "[WARNING]",
+        IssueSeverity::Info => "[INFO]",
+    }
+}
+
+/// Display name of an issue category
+pub(crate) fn category_text(category: &IssueCategory) -> &'static str {
+    match category {
+        IssueCategory::Dns => "DNS",
+        IssueCategory::Tcp => "TCP",
+        IssueCategory::Ssl => "SSL",
+        IssueCategory::Routing => "Routing",
+        IssueCategory::Stability => "Stability",
+        IssueCategory::Http => "HTTP",
+    }
+}
+
 /// Add recommendations section
 fn add_recommendations_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
     docx = docx.add_paragraph(
         Paragraph::new()
-            .add_run(Run::new().add_text("7. RECOMMENDATIONS").bold().size(32)),
+            .add_run(Run::new().add_text("8. RECOMMENDATIONS").bold().size(32)),
     );
 
     docx = docx.add_paragraph(Paragraph::new());
@@ -778,20 +1717,15 @@ fn add_trace_logs_section(mut docx: Docx, logs: &[TraceLogEntry]) -> Docx {
         );
     } else {
         // Limit logs to avoid extremely large documents
-        let max_logs = 500;
-        let display_logs = if logs.len() > max_logs {
-            &logs[..max_logs]
-        } else {
-            logs
-        };
+        let display_logs = &logs[..logs.len().min(MAX_REPORT_LOGS)];
 
-        if logs.len() > max_logs {
+        if logs.len() > MAX_REPORT_LOGS {
             docx = docx.add_paragraph(
                 Paragraph::new().add_run(
                     Run::new()
                         .add_text(format!(
                             "(Showing {} of {} log entries)",
-                            max_logs,
+                            MAX_REPORT_LOGS,
                             logs.len()
                         ))
                         .italic(),
@@ -801,25 +1735,9 @@ fn add_trace_logs_section(mut docx: Docx, logs: &[TraceLogEntry]) -> Docx {
         }
 
         for log in display_logs {
-            let level_marker = match log.level.to_lowercase().as_str() {
-                "success" => "[OK   ]",
-                "warning" => "[WARN ]",
-                "error" => "[ERROR]",
-                "debug" => "[DEBUG]",
-                _ => "[INFO ]",
-            };
-
-            let log_line = format!(
-                "[{}] {} [{}] {}",
-                log.timestamp,
-                level_marker,
-                log.category.to_uppercase(),
-                log.message
-            );
-
             docx = docx.add_paragraph(
                 Paragraph::new()
-                    .add_run(Run::new().add_text(&log_line).size(18)), // Smaller font for logs
+                    .add_run(Run::new().add_text(format_log_line(log)).size(18)), // Smaller font for logs
             );
 
             // Include raw data if present
@@ -838,6 +1756,77 @@ fn add_trace_logs_section(mut docx: Docx, logs: &[TraceLogEntry]) -> Docx {
     docx
 }
 
+/// Add raw command output section (appendix, only when captured)
+fn add_raw_output_section(mut docx: Docx, report: &DiagnosticReport) -> Docx {
+    if report.raw_output.is_empty() {
+        return docx;
+    }
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text("═".repeat(80)))
+            .align(AlignmentType::Center),
+    );
+
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .add_run(
+                Run::new()
+                    .add_text("APPENDIX: RAW COMMAND OUTPUT")
+                    .bold()
+                    .size(32),
+            ),
+    );
+
+    docx = docx.add_paragraph(Paragraph::new());
+
+    for entry in &report.raw_output {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text(raw_command_header(entry)).bold().size(18)),
+        );
+
+        for line in entry.stdout.lines().chain(entry.stderr.lines()) {
+            docx = docx.add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text(format!("    {}", line)).size(16)),
+            );
+        }
+
+        docx = docx.add_paragraph(Paragraph::new());
+    }
+
+    docx
+}
+
+/// Format a raw output entry header as "[time] $ command (exit N)"
+pub(crate) fn raw_command_header(entry: &RawCommandOutput) -> String {
+    let exit = match entry.exit_code {
+        Some(code) => format!("exit {}", code),
+        None => "killed".to_string(),
+    };
+    format!("[{}] $ {} ({})", entry.timestamp, entry.command, exit)
+}
+
+/// Format a trace log entry as "[time] [LEVEL] [CATEGORY] message"
+pub(crate) fn format_log_line(log: &TraceLogEntry) -> String {
+    let level_marker = match log.level.to_lowercase().as_str() {
+        "success" => "[OK   ]",
+        "warning" => "[WARN ]",
+        "error" => "[ERROR]",
+        "debug" => "[DEBUG]",
+        _ => "[INFO ]",
+    };
+
+    format!(
+        "[{}] {} [{}] {}",
+        log.timestamp,
+        level_marker,
+        log.category.to_uppercase(),
+        log.message
+    )
+}
+
 /// Add footer with tool information
 fn add_footer(mut docx: Docx) -> Docx {
     docx = docx.add_paragraph(
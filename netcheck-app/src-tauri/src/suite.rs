@@ -0,0 +1,139 @@
+//! Test suites loaded from a TOML/JSON file
+//!
+//! A suite lists targets with an optional profile and/or full config each,
+//! so the same set of checks can be rerun as lightweight synthetic monitoring.
+//!
+//! ```toml
+//! [[targets]]
+//! url = "https://example.com"
+//! profile = "api"
+//!
+//! [[targets]]
+//! url = "game.example.com:27015"
+//! [targets.config]
+//! stability_tests = 30
+//! ```
+
+use crate::config::{DiagnosticConfig, DiagnosticProfile};
+use crate::diagnostic::parse_domain;
+use serde::Deserialize;
+use std::path::Path;
+
+/// One target of a suite
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuiteTarget {
+    pub url: String,
+    pub profile: Option<DiagnosticProfile>,
+    /// Full config; wins over `profile` like in `run_diagnostic`
+    pub config: Option<DiagnosticConfig>,
+}
+
+/// Load and validate a suite file (`.json`, anything else is read as TOML)
+pub fn load_suite(path: &Path) -> Result<Vec<SuiteTarget>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let document: serde_json::Value = if is_json {
+        serde_json::from_str(&text).map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?
+    } else {
+        toml::from_str(&text).map_err(|e| format!("Invalid TOML in {}: {}", path.display(), e))?
+    };
+
+    let entries = document
+        .get("targets")
+        .and_then(|t| t.as_array())
+        .ok_or_else(|| format!("{} has no `targets` list", path.display()))?;
+    if entries.is_empty() {
+        return Err(format!("{} has an empty `targets` list", path.display()));
+    }
+
+    // Parse targets one by one so the error names the offending entry
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let label = match entry.get("url").and_then(|u| u.as_str()) {
+                Some(url) => format!("Target #{} ({})", i + 1, url),
+                None => format!("Target #{}", i + 1),
+            };
+            let target: SuiteTarget = serde_json::from_value(entry.clone())
+                .map_err(|e| format!("{}: {}", label, e))?;
+            parse_domain(&target.url).map_err(|e| format!("{}: {}", label, e))?;
+            Ok(target)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Load `text` from a temp file called `name`
+    fn load(name: &str, text: &str) -> Result<Vec<SuiteTarget>, String> {
+        let path = std::env::temp_dir().join(format!("netcheck-suite-{}-{}", std::process::id(), name));
+        std::fs::write(&path, text).unwrap();
+        let result = load_suite(&path);
+        let _ = std::fs::remove_file(&path);
+        result
+    }
+
+    #[test]
+    fn toml_and_json_suites_load_alike() {
+        let toml = load(
+            "suite.toml",
+            "[[targets]]\nurl = \"https://example.com\"\nprofile = \"api\"\n\n[[targets]]\nurl = \"game.example.com:27015\"\n[targets.config]\nstability_tests = 30\n",
+        )
+        .unwrap();
+        let json = load(
+            "suite.json",
+            r#"{"targets": [{"url": "https://example.com", "profile": "api"}, {"url": "game.example.com:27015", "config": {"stability_tests": 30}}]}"#,
+        )
+        .unwrap();
+
+        for targets in [toml, json] {
+            assert_eq!(targets.len(), 2);
+            assert_eq!(targets[0].profile, Some(DiagnosticProfile::Api));
+            assert_eq!(targets[1].config.as_ref().map(|c| c.stability_tests), Some(30));
+        }
+    }
+
+    #[test]
+    fn errors_name_the_offending_target() {
+        let error = load(
+            "bad-profile.toml",
+            "[[targets]]\nurl = \"https://example.com\"\n\n[[targets]]\nurl = \"https://api.example.com\"\nprofile = \"turbo\"\n",
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Target #2 (https://api.example.com): "), "{}", error);
+
+        let error = load("no-url.json", r#"{"targets": [{"profile": "web"}]}"#).unwrap_err();
+        assert!(error.starts_with("Target #1: "), "{}", error);
+    }
+
+    #[test]
+    fn malformed_or_empty_suites_are_rejected() {
+        assert!(load("broken.toml", "[[targets]\n").unwrap_err().contains("Invalid TOML"));
+        assert!(load("broken.json", "{").unwrap_err().contains("Invalid JSON"));
+        assert!(load("empty.json", r#"{"targets": []}"#).unwrap_err().contains("empty"));
+        assert!(load("missing.toml", "title = \"x\"\n").unwrap_err().contains("no `targets`"));
+    }
+
+    #[test]
+    fn config_wins_over_profile() {
+        let targets = load(
+            "precedence.toml",
+            "[[targets]]\nurl = \"https://example.com\"\nprofile = \"gaming\"\n[targets.config]\nstability_tests = 5\n\n[[targets]]\nurl = \"https://example.com\"\nprofile = \"gaming\"\n",
+        )
+        .unwrap();
+
+        let configs: Vec<DiagnosticConfig> = targets
+            .into_iter()
+            .map(|t| DiagnosticConfig::effective(t.profile, t.config))
+            .collect();
+        assert_eq!(configs[0].stability_tests, 5);
+        assert_eq!(configs[1].stability_tests, DiagnosticProfile::Gaming.config().stability_tests);
+    }
+}
@@ -0,0 +1,654 @@
+//! Plain-text Report Generator for NetCheck Diagnostic Reports
+//!
+//! Produces a monospace-aligned `.txt` version of the DOCX report for ticketing
+//! systems that only accept plain text. Sections and truncation limits mirror
+//! the DOCX generator.
+
+use crate::report_generator::{
+    bandwidth_lines, category_text, cert_validity_text, destination_text, format_hop_latency,
+    format_log_line, get_timing_status, ip_changes_text, jitter_assessment, latency_by_size_row,
+    network_quality_text, overall_status_text, per_ip_timing_row, proxy_detection_text,
+    public_resolver_text, raw_command_header, realtime_verdict_row, routing_loop_text,
+    score_breakdown_lines, security_header_lines, severity_marker, sla_text,
+    stability_failures_text, target_override_text, thresholds_text, TraceLogEntry,
+    LATENCY_BY_SIZE_HEADERS, MAX_REPORT_HOPS, MAX_REPORT_LOGS, PER_IP_HEADERS, REALTIME_HEADERS,
+};
+use crate::types::*;
+use std::fmt::Write;
+
+/// Report width in characters
+const WIDTH: usize = 80;
+
+/// Generate the plain-text report
+pub fn generate_text_report(report: &DiagnosticReport, logs: &[TraceLogEntry]) -> String {
+    let mut out = String::new();
+
+    write_header(&mut out, report);
+    write_executive_summary(&mut out, report);
+    write_dns_section(&mut out, report);
+    write_tcp_section(&mut out, report);
+    write_routing_section(&mut out, report);
+    write_stability_section(&mut out, report);
+    write_bandwidth_section(&mut out, report);
+    write_issues_section(&mut out, report);
+    write_recommendations_section(&mut out, report);
+    write_trace_logs_section(&mut out, logs);
+    write_raw_output_section(&mut out, report);
+    write_footer(&mut out);
+
+    out
+}
+
+/// Write a numbered section title with an underline
+fn write_section_title(out: &mut String, title: &str) {
+    let _ = writeln!(out, "{}", title);
+    let _ = writeln!(out, "{}", "-".repeat(title.chars().count()));
+    out.push('\n');
+}
+
+/// Write a "Label: value" line
+fn write_field(out: &mut String, label: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "{:<20}{}", format!("{}:", label), value);
+}
+
+/// Render an ASCII table, sizing each column to its widest cell
+fn write_table(out: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let separator = widths
+        .iter()
+        .map(|w| "-".repeat(w + 2))
+        .collect::<Vec<_>>()
+        .join("+");
+    let separator = format!("+{}+", separator);
+
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| {
+        let line = cells
+            .zip(&widths)
+            .map(|(cell, width)| format!(" {:<width$} ", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("|");
+        format!("|{}|", line)
+    };
+
+    let _ = writeln!(out, "{}", separator);
+    let _ = writeln!(out, "{}", format_row(&mut headers.iter().copied()));
+    let _ = writeln!(out, "{}", separator);
+    for row in rows {
+        let _ = writeln!(out, "{}", format_row(&mut row.iter().map(|c| c.as_str())));
+    }
+    let _ = writeln!(out, "{}", separator);
+}
+
+/// Write report header with title, URL, and timestamp
+fn write_header(out: &mut String, report: &DiagnosticReport) {
+    let _ = writeln!(out, "{}", "=".repeat(WIDTH));
+    let _ = writeln!(out, "{:^width$}", "NETWORK DIAGNOSTIC REPORT", width = WIDTH);
+    let _ = writeln!(out, "{}", "=".repeat(WIDTH));
+    out.push('\n');
+
+    write_field(out, "Target", &report.target_url);
+    write_field(out, "Generated", &report.timestamp);
+    if let Some(source) = &report.network_source {
+        write_field(out, "Network Source", source);
+    }
+    if let Some(target_override) = &report.target_override {
+        write_field(out, "Target Override", target_override_text(target_override));
+    }
+    write_field(out, "Tool", "NetCheck v1.0.0");
+    out.push('\n');
+}
+
+/// Write executive summary section
+fn write_executive_summary(out: &mut String, report: &DiagnosticReport) {
+    let (status_text, status_description) = overall_status_text(&report.overall_status);
+
+    write_section_title(out, "1. EXECUTIVE SUMMARY");
+
+    write_field(
+        out,
+        "Overall Status",
+        format!("{} - {}", status_text, status_description),
+    );
+    if let Some((verdict, checks)) = sla_text(report) {
+        write_field(out, "SLA", verdict);
+        for check in checks {
+            let _ = writeln!(out, "  {}", check);
+        }
+    }
+    write_field(out, "Issues Found", report.issues.len());
+    write_field(out, "Recommendations", report.recommendations.len());
+
+    if !report.issues.is_empty() {
+        let count = |predicate: fn(&IssueSeverity) -> bool| {
+            report.issues.iter().filter(|i| predicate(&i.severity)).count()
+        };
+        write_field(
+            out,
+            "Severity Breakdown",
+            format!(
+                "Errors: {} | Warnings: {} | Info: {}",
+                count(|s| matches!(s, IssueSeverity::Error)),
+                count(|s| matches!(s, IssueSeverity::Warning)),
+                count(|s| matches!(s, IssueSeverity::Info))
+            ),
+        );
+    }
+
+    if !report.score_breakdown.is_empty() {
+        let _ = writeln!(out, "Score Breakdown:");
+        for line in score_breakdown_lines(report) {
+            let _ = writeln!(out, "  {}", line);
+        }
+    }
+
+    if !report.summary_line.is_empty() {
+        write_field(out, "Summary", &report.summary_line);
+    }
+    if let Some(delta) = &report.delta_summary {
+        write_field(out, "Since Previous Run", delta);
+    }
+
+    if let Some(quality) = &report.network_quality {
+        let (grade_line, metrics_line) = network_quality_text(quality);
+        write_field(out, "Network Quality", grade_line);
+        let _ = writeln!(out, "{:<20}{}", "", metrics_line);
+    }
+
+    write_field(
+        out,
+        "Diagnostic Profile",
+        format!(
+            "{}{}",
+            report.profile.map(|p| p.label()).unwrap_or("Default"),
+            if report.strict {
+                " (strict mode: warnings count as failures)"
+            } else {
+                ""
+            }
+        ),
+    );
+    write_field(out, "Thresholds", thresholds_text(report));
+    out.push('\n');
+}
+
+/// Write DNS resolution section
+fn write_dns_section(out: &mut String, report: &DiagnosticReport) {
+    write_section_title(out, "2. DNS RESOLUTION");
+
+    let Some(dns) = &report.dns else {
+        let _ = writeln!(out, "DNS resolution failed or was not performed.\n");
+        return;
+    };
+
+    write_field(out, "Domain", &dns.domain);
+    write_field(
+        out,
+        "Resolved IPs",
+        if dns.resolved_ips.is_empty() {
+            "None found".to_string()
+        } else {
+            dns.resolved_ips.join(", ")
+        },
+    );
+    if !dns.resolved_ipv6.is_empty() {
+        write_field(out, "IPv6 Addresses", dns.resolved_ipv6.join(", "));
+    }
+    write_field(out, "Lookup Time", format!("{:.2} ms", dns.lookup_time_ms));
+    if let Some(ttl) = dns.ttl {
+        write_field(out, "TTL", format!("{} seconds", ttl));
+    }
+    if let Some(nameservers) = dns.nameservers.as_ref().filter(|ns| !ns.is_empty()) {
+        write_field(out, "Nameservers", nameservers.join(", "));
+    }
+    if let Some(backend) = &dns.backend {
+        write_field(
+            out,
+            "Resolved Via",
+            match backend {
+                DnsBackend::Native => "System resolver",
+                DnsBackend::Dig => "dig",
+                DnsBackend::Nslookup => "nslookup",
+                DnsBackend::Host => "host",
+            },
+        );
+    }
+    if let Some(resolver) = &dns.resolver {
+        write_field(out, "Resolver", public_resolver_text(resolver, dns));
+    }
+    if let Some(cdn) = &dns.using_cdn {
+        write_field(out, "CDN Detected", cdn);
+    }
+
+    if let Some(secure_dns) = &report.secure_dns {
+        write_field(
+            out,
+            "Encrypted DNS",
+            format!(
+                "DoH {}, DoT {}",
+                if secure_dns.doh_available { "Available" } else { "Blocked" },
+                match secure_dns.dot_available {
+                    Some(true) => "Available",
+                    Some(false) => "Blocked",
+                    None => "Not tested (kdig not installed)",
+                }
+            ),
+        );
+        for probe in &secure_dns.probes {
+            let protocol = match probe.protocol {
+                SecureDnsProtocol::Doh => "DoH",
+                SecureDnsProtocol::Dot => "DoT",
+            };
+            let outcome = if probe.success {
+                format!("OK in {:.0} ms", probe.time_ms)
+            } else {
+                format!("failed ({})", probe.error.as_deref().unwrap_or("unknown error"))
+            };
+            let _ = writeln!(
+                out,
+                "  - {} {} ({}): {}",
+                probe.provider, protocol, probe.endpoint, outcome
+            );
+        }
+    }
+
+    if let Some(blocklist) = &report.blocklist {
+        write_field(
+            out,
+            "Blocklist Check",
+            format!(
+                "{} IP(s) against {} list(s), {} listing(s)",
+                blocklist.checked_ips.len(),
+                blocklist.blocklists.len(),
+                blocklist.listings.len()
+            ),
+        );
+        for listing in &blocklist.listings {
+            let _ = writeln!(
+                out,
+                "  - {}{} listed on {} ({})",
+                listing.ip,
+                if listing.is_own_ip { " (your IP)" } else { "" },
+                listing.blocklist,
+                listing.return_code
+            );
+        }
+    }
+
+    out.push('\n');
+}
+
+/// Write TCP connection timing section
+fn write_tcp_section(out: &mut String, report: &DiagnosticReport) {
+    // Raw host:port targets have no HTTP/SSL timings to show
+    if let Some(port_check) = &report.port_check {
+        write_port_check_section(out, port_check);
+        return;
+    }
+
+    write_section_title(out, "3. TCP CONNECTION TIMING");
+
+    let Some(tcp) = &report.tcp else {
+        let _ = writeln!(out, "TCP connection test failed or was not performed.\n");
+        return;
+    };
+
+    let row = |phase: &str, value: f64, good: f64, warn: f64| {
+        vec![
+            phase.to_string(),
+            format!("{:.2}", value),
+            get_timing_status(value, good, warn).to_string(),
+        ]
+    };
+
+    write_table(
+        out,
+        &["Phase", "Time (ms)", "Status"],
+        &[
+            row("DNS Resolution", tcp.dns_time_ms, 100.0, 200.0),
+            row("TCP Connect", tcp.connect_time_ms, 200.0, 500.0),
+            row("SSL Handshake", tcp.ssl_time_ms, 300.0, 500.0),
+            row("Time to First Byte", tcp.ttfb_ms, 500.0, 1000.0),
+            row("Total Time", tcp.total_time_ms, 1000.0, 3000.0),
+        ],
+    );
+    out.push('\n');
+
+    write_field(out, "HTTP Response Code", tcp.http_code);
+    if let Some(ip) = &tcp.remote_ip {
+        write_field(out, "Connected IP", ip);
+    }
+    if !tcp.redirect_chain.is_empty() {
+        write_field(out, "Redirects", tcp.redirect_chain.join(" -> "));
+    }
+    if let Some(reason) = &tcp.failure_reason {
+        write_field(out, "Failure Reason", reason);
+    }
+    write_field(out, "Download Speed", format!("{:.2} Kbps", tcp.download_speed_kbps));
+
+    if let Some(headers) = &tcp.security_headers {
+        out.push('\n');
+        let _ = writeln!(out, "Security Headers:");
+        for line in security_header_lines(headers) {
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+
+    if let Some(per_ip) = &report.per_ip_tcp {
+        out.push('\n');
+        let _ = writeln!(out, "Per-IP Timing:");
+        let rows: Vec<Vec<String>> = per_ip
+            .iter()
+            .map(|(ip, tcp)| per_ip_timing_row(ip, tcp))
+            .collect();
+        write_table(out, &PER_IP_HEADERS, &rows);
+    }
+
+    if let Some(cert) = &report.ssl_cert {
+        out.push('\n');
+        let _ = writeln!(out, "SSL Certificate:");
+        let _ = writeln!(out, "  - Subject: {}", cert.subject);
+        let _ = writeln!(out, "  - Issuer: {}", cert.issuer);
+        let _ = writeln!(
+            out,
+            "  - Chain Complete: {}",
+            if cert.chain_complete {
+                "Yes"
+            } else {
+                "No - some clients will fail the handshake"
+            }
+        );
+        if let Some(message) = &cert.verify_message {
+            let _ = writeln!(out, "  - Verification: {}", message);
+        }
+        if let Some(validity) = cert_validity_text(cert) {
+            let _ = writeln!(out, "  - Valid: {}", validity);
+        }
+        let _ = writeln!(out, "Certificate Chain:");
+        for (idx, subject) in cert.chain_subjects.iter().enumerate() {
+            let _ = writeln!(out, "  {}. {}", idx, subject);
+        }
+    }
+
+    if let Some(proxy) = &report.proxy {
+        write_field(out, "Transparent Proxy", proxy_detection_text(proxy));
+    }
+
+    out.push('\n');
+}
+
+/// Write raw TCP connect section for host:port targets
+fn write_port_check_section(out: &mut String, port_check: &PortCheckResult) {
+    write_section_title(out, "3. TCP CONNECTION (RAW PORT)");
+
+    write_field(
+        out,
+        "Service",
+        format!("{}:{} ({})", port_check.host, port_check.port, port_check.remote_ip),
+    );
+    write_field(
+        out,
+        "Port Status",
+        if port_check.connected {
+            "Open"
+        } else {
+            "Closed / Unreachable"
+        },
+    );
+    write_field(
+        out,
+        "Connect Time",
+        format!(
+            "{:.2} ms ({})",
+            port_check.connect_time_ms,
+            get_timing_status(port_check.connect_time_ms, 200.0, 500.0)
+        ),
+    );
+    if let Some(banner) = &port_check.banner {
+        write_field(out, "Service Banner", banner);
+    }
+    if let Some(error) = &port_check.error {
+        write_field(out, "Error", error);
+    }
+    let _ = writeln!(out, "(Raw TCP mode: SSL and HTTP checks were skipped)\n");
+}
+
+/// Write network routing (traceroute) section
+fn write_routing_section(out: &mut String, report: &DiagnosticReport) {
+    write_section_title(out, "4. NETWORK ROUTING (TRACEROUTE)");
+
+    let Some(routing) = &report.routing else {
+        let _ = writeln!(out, "Routing test failed or was not performed.\n");
+        write_latency_by_size(out, report);
+        return;
+    };
+
+    write_field(out, "Target IP", &routing.target_ip);
+    write_field(out, "Total Hops", routing.total_hops);
+    write_field(out, "Total Time", format!("{:.2} ms", routing.total_time_ms));
+    write_field(out, "Avg Hop RTT", format_hop_latency(routing.avg_hop_latency_ms));
+    write_field(out, "Max Hop RTT", format_hop_latency(routing.max_hop_latency_ms));
+    if let Some(destination) = destination_text(routing) {
+        write_field(out, "Destination", destination);
+    }
+    if let Some(routing_loop) = routing_loop_text(routing) {
+        write_field(out, "Routing Loop", routing_loop);
+    }
+    out.push('\n');
+
+    if routing.hops.len() > MAX_REPORT_HOPS {
+        let _ = writeln!(
+            out,
+            "(Showing {} of {} hops)",
+            MAX_REPORT_HOPS,
+            routing.hops.len()
+        );
+    }
+
+    let rows: Vec<Vec<String>> = routing
+        .hops
+        .iter()
+        .take(MAX_REPORT_HOPS)
+        .map(|hop| {
+            vec![
+                hop.hop_number.to_string(),
+                hop.ip_address.clone(),
+                hop.hostname.clone().unwrap_or_else(|| "-".to_string()),
+                match hop.rtt_ms_opt {
+                    Some(rtt) => format!("{:.2}", rtt),
+                    None => "*".to_string(),
+                },
+                format!("{:.1}%", hop.packet_loss_percent),
+            ]
+        })
+        .collect();
+
+    write_table(
+        out,
+        &["Hop", "IP Address", "Hostname", "RTT (ms)", "Packet Loss"],
+        &rows,
+    );
+    out.push('\n');
+
+    write_latency_by_size(out, report);
+}
+
+/// Write ping latency per payload size
+fn write_latency_by_size(out: &mut String, report: &DiagnosticReport) {
+    let Some(sweep) = &report.latency_by_size else {
+        return;
+    };
+
+    write_field(out, "Latency by Size", format!("ping {}", sweep.target_ip));
+    let rows: Vec<Vec<String>> = sweep.samples.iter().chain(&sweep.mtu_probe).map(latency_by_size_row).collect();
+    write_table(out, &LATENCY_BY_SIZE_HEADERS, &rows);
+    out.push('\n');
+}
+
+/// Write connection stability section
+fn write_stability_section(out: &mut String, report: &DiagnosticReport) {
+    write_section_title(out, "5. CONNECTION STABILITY");
+
+    let Some(stability) = &report.stability else {
+        let _ = writeln!(out, "Stability test failed or was not performed.\n");
+        return;
+    };
+
+    write_field(out, "Total Tests", stability.total_tests);
+    write_field(out, "Successful", stability.successful_tests);
+    write_field(out, "Success Rate", format!("{:.1}%", stability.success_rate));
+    if let Some(failures) = stability_failures_text(stability) {
+        write_field(out, "Failures", failures);
+    }
+    if let Some(ip_changes) = ip_changes_text(stability) {
+        write_field(out, "Resolved IPs", ip_changes);
+    }
+    out.push('\n');
+
+    let _ = writeln!(out, "Response Time Metrics:");
+    let _ = writeln!(out, "  - Minimum: {:.2} ms", stability.min_time_ms);
+    let _ = writeln!(out, "  - Average: {:.2} ms", stability.avg_time_ms);
+    let _ = writeln!(out, "  - Maximum: {:.2} ms", stability.max_time_ms);
+    let _ = writeln!(out, "  - Jitter: {:.2} ms", stability.jitter_ms);
+    out.push('\n');
+
+    write_field(out, "Jitter Assessment", jitter_assessment(stability.jitter_ms));
+    out.push('\n');
+
+    if !report.realtime_suitability.is_empty() {
+        let _ = writeln!(out, "Real-time Suitability:");
+        let rows: Vec<Vec<String>> = report
+            .realtime_suitability
+            .iter()
+            .map(realtime_verdict_row)
+            .collect();
+        write_table(out, &REALTIME_HEADERS, &rows);
+        out.push('\n');
+    }
+}
+
+/// Write download vs upload bandwidth section
+fn write_bandwidth_section(out: &mut String, report: &DiagnosticReport) {
+    write_section_title(out, "6. BANDWIDTH (DOWNLOAD VS UPLOAD)");
+
+    let Some(bandwidth) = &report.bandwidth else {
+        let _ = writeln!(out, "Bandwidth test failed or was not performed.\n");
+        return;
+    };
+
+    for (label, value) in bandwidth_lines(bandwidth) {
+        write_field(out, label, value);
+    }
+    out.push('\n');
+}
+
+/// Write detected issues section
+fn write_issues_section(out: &mut String, report: &DiagnosticReport) {
+    write_section_title(out, "7. DETECTED ISSUES");
+
+    if report.issues.is_empty() {
+        let _ = writeln!(out, "No issues detected. Network connection appears healthy.\n");
+        return;
+    }
+
+    for (idx, issue) in report.issues.iter().enumerate() {
+        let _ = writeln!(out, "Issue #{}: {}", idx + 1, issue.title);
+        let _ = writeln!(
+            out,
+            "Category: {} | Severity: {}",
+            category_text(&issue.category),
+            severity_marker(&issue.severity)
+        );
+        let _ = writeln!(out, "Description: {}", issue.description);
+
+        if !issue.possible_causes.is_empty() {
+            let _ = writeln!(out, "Possible Causes:");
+            for cause in &issue.possible_causes {
+                let _ = writeln!(out, "  - {}", cause);
+            }
+        }
+
+        if !issue.solutions.is_empty() {
+            let _ = writeln!(out, "Recommended Solutions:");
+            for solution in &issue.solutions {
+                let _ = writeln!(out, "  - {}", solution);
+            }
+        }
+
+        out.push('\n');
+    }
+}
+
+/// Write recommendations section
+fn write_recommendations_section(out: &mut String, report: &DiagnosticReport) {
+    write_section_title(out, "8. RECOMMENDATIONS");
+
+    if report.recommendations.is_empty() {
+        let _ = writeln!(out, "No specific recommendations at this time.");
+    } else {
+        for recommendation in &report.recommendations {
+            let _ = writeln!(out, "-> {}", recommendation);
+        }
+    }
+
+    out.push('\n');
+}
+
+/// Write trace logs section (appendix)
+fn write_trace_logs_section(out: &mut String, logs: &[TraceLogEntry]) {
+    let _ = writeln!(out, "{}", "=".repeat(WIDTH));
+    write_section_title(out, "APPENDIX: DIAGNOSTIC TRACE LOG");
+
+    if logs.is_empty() {
+        let _ = writeln!(out, "No trace logs available.\n");
+        return;
+    }
+
+    if logs.len() > MAX_REPORT_LOGS {
+        let _ = writeln!(
+            out,
+            "(Showing {} of {} log entries)\n",
+            MAX_REPORT_LOGS,
+            logs.len()
+        );
+    }
+
+    for log in &logs[..logs.len().min(MAX_REPORT_LOGS)] {
+        let _ = writeln!(out, "{}", format_log_line(log));
+        if let Some(raw_data) = log.raw_data.as_ref().filter(|r| !r.is_empty()) {
+            let _ = writeln!(out, "    {}", raw_data);
+        }
+    }
+
+    out.push('\n');
+}
+
+/// Write raw command output section (appendix, only when captured)
+fn write_raw_output_section(out: &mut String, report: &DiagnosticReport) {
+    if report.raw_output.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(out, "{}", "=".repeat(WIDTH));
+    write_section_title(out, "APPENDIX: RAW COMMAND OUTPUT");
+
+    for entry in &report.raw_output {
+        let _ = writeln!(out, "{}", raw_command_header(entry));
+        for line in entry.stdout.lines().chain(entry.stderr.lines()) {
+            let _ = writeln!(out, "    {}", line);
+        }
+        out.push('\n');
+    }
+}
+
+/// Write footer with tool information
+fn write_footer(out: &mut String) {
+    let _ = writeln!(out, "{}", "=".repeat(WIDTH));
+    let _ = writeln!(out, "{:^width$}", "Generated by NetCheck v1.0.0", width = WIDTH);
+    let _ = writeln!(out, "{:^width$}", "Network Diagnostic Tool", width = WIDTH);
+}
@@ -1,4 +1,6 @@
+use crate::config::{DiagnosticProfile, DiagnosticThresholds};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Status of a diagnostic step
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -16,10 +18,62 @@ pub enum DiagnosticStatus {
 pub struct DnsResult {
     pub domain: String,
     pub resolved_ips: Vec<String>,
+    /// AAAA records, queried alongside the A records
+    #[serde(default)]
+    pub resolved_ipv6: Vec<String>,
+    /// Time of the primary A-record query only
     pub lookup_time_ms: f64,
     pub ttl: Option<u32>,
     pub nameservers: Option<Vec<String>>,
     pub using_cdn: Option<String>,
+    /// Backend that produced the answer
+    pub backend: Option<DnsBackend>,
+    /// Public resolver that answered after the local one failed (`None` = local)
+    pub resolver: Option<String>,
+    /// Why the local resolver failed, when a public resolver had to be used
+    pub local_resolver_error: Option<String>,
+}
+
+/// DNS resolution backend used by `check_dns`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsBackend {
+    Native,
+    Dig,
+    Nslookup,
+    Host,
+}
+
+/// Encrypted DNS transport
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecureDnsProtocol {
+    /// DNS-over-HTTPS (port 443)
+    Doh,
+    /// DNS-over-TLS (port 853)
+    Dot,
+}
+
+/// Resolution attempt against one encrypted DNS resolver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecureDnsProbe {
+    pub provider: String,
+    pub protocol: SecureDnsProtocol,
+    pub endpoint: String,
+    pub success: bool,
+    pub resolved_ips: Vec<String>,
+    pub time_ms: f64,
+    pub error: Option<String>,
+}
+
+/// DNS-over-HTTPS / DNS-over-TLS availability result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecureDnsResult {
+    pub probes: Vec<SecureDnsProbe>,
+    /// At least one DoH resolver answered
+    pub doh_available: bool,
+    /// At least one DoT resolver answered, `None` when DoT could not be tested
+    pub dot_available: Option<bool>,
 }
 
 /// TCP Connection Timing Result
@@ -32,6 +86,117 @@ pub struct TcpResult {
     pub total_time_ms: f64,
     pub http_code: u16,
     pub download_speed_kbps: f64,
+    /// Why curl failed, when it exited with an error
+    pub failure_reason: Option<String>,
+    /// Security headers of the final response
+    pub security_headers: Option<SecurityHeaders>,
+    /// IP curl actually connected to (`%{remote_ip}`), IPv4 or IPv6
+    pub remote_ip: Option<String>,
+    /// URLs visited while following redirects, the requested one first
+    /// (empty when there was no redirect)
+    #[serde(default)]
+    pub redirect_chain: Vec<String>,
+    /// Local clock minus the server's `Date` header, in seconds (positive:
+    /// the local clock is ahead); `None` without a `Date` header
+    #[serde(default)]
+    pub clock_skew_secs: Option<i64>,
+}
+
+/// Security-relevant response headers (values as sent, `None` when absent)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityHeaders {
+    /// Strict-Transport-Security (HSTS)
+    pub hsts: Option<String>,
+    pub content_security_policy: Option<String>,
+    pub x_frame_options: Option<String>,
+    pub x_content_type_options: Option<String>,
+}
+
+impl TcpResult {
+    /// Result for a connection that failed before any timing was available
+    pub fn failed(reason: String) -> Self {
+        Self {
+            dns_time_ms: 0.0,
+            connect_time_ms: 0.0,
+            ssl_time_ms: 0.0,
+            ttfb_ms: 0.0,
+            total_time_ms: 0.0,
+            http_code: 0,
+            download_speed_kbps: 0.0,
+            failure_reason: Some(reason),
+            security_headers: None,
+            remote_ip: None,
+            redirect_chain: Vec::new(),
+            clock_skew_secs: None,
+        }
+    }
+    
+    /// The server answered, and not with a server error (5xx)
+    pub fn is_healthy(&self) -> bool {
+        self.failure_reason.is_none() && self.http_code > 0 && self.http_code < 500
+    }
+}
+
+/// Server certificate and chain information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SslCertInfo {
+    pub subject: String,
+    pub issuer: String,
+    /// Subjects of the presented chain, leaf first
+    pub chain_subjects: Vec<String>,
+    /// Every issuer is present up to a trusted root
+    pub chain_complete: bool,
+    pub verify_code: Option<i32>,
+    pub verify_message: Option<String>,
+    /// Validity window of the leaf certificate
+    pub not_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub not_after: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Raw TCP connect result for non-HTTP targets (host:port)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortCheckResult {
+    pub host: String,
+    pub port: u16,
+    pub remote_ip: String,
+    pub connected: bool,
+    pub connect_time_ms: f64,
+    /// First line the service sent after connecting, if any
+    pub banner: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Reachability of one outbound port
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PortStatus {
+    /// Connection established
+    Open,
+    /// Actively rejected (refused/reset), usually a firewall sending RST
+    Blocked,
+    /// No answer before the timeout, packets silently dropped
+    Filtered,
+}
+
+/// One port of a common-ports scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortProbe {
+    pub port: u16,
+    /// Typical service on this port, e.g. "SMTP"
+    pub service: String,
+    pub status: PortStatus,
+    /// Only set for open ports
+    pub connect_time_ms: Option<f64>,
+}
+
+/// Outbound reachability of common ports against a host that accepts any port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortScanResult {
+    pub host: String,
+    pub remote_ip: String,
+    pub ports: Vec<PortProbe>,
+    /// e.g. "13 of 15 ports open; blocked: 25 (SMTP); filtered: 3389 (RDP)"
+    pub summary: String,
 }
 
 /// A single hop in the routing path
@@ -40,7 +205,11 @@ pub struct RouteHop {
     pub hop_number: u32,
     pub ip_address: String,
     pub hostname: Option<String>,
+    /// Kept for compatibility; 0.0 for timeout hops, prefer `rtt_ms_opt`
     pub rtt_ms: f64,
+    /// RTT of the hop, `None` when the hop timed out ("*")
+    #[serde(default)]
+    pub rtt_ms_opt: Option<f64>,
     pub packet_loss_percent: f64,
 }
 
@@ -51,6 +220,124 @@ pub struct RoutingResult {
     pub hops: Vec<RouteHop>,
     pub total_hops: u32,
     pub total_time_ms: f64,
+    /// Chart-ready RTT per hop, with gaps (`None`) for timeout hops
+    #[serde(default)]
+    pub rtt_series: Vec<Option<f64>>,
+    /// Average RTT over responding hops, `None` if no hop responded
+    pub avg_hop_latency_ms: Option<f64>,
+    /// Highest RTT over responding hops, `None` if no hop responded
+    pub max_hop_latency_ms: Option<f64>,
+    /// traceroute produced no hops because it lacks privileges (even with the fallback probe method)
+    #[serde(default)]
+    pub needs_privileges: bool,
+    /// A hop answered from `target_ip`; `None` when unknown (no target IP or no hops)
+    #[serde(default)]
+    pub destination_reached: Option<bool>,
+    /// traceroute used up its TTL limit (`MAX_TRACE_HOPS`)
+    #[serde(default)]
+    pub hit_hop_limit: bool,
+    /// IPs answering again after another router (routing loop), in order of
+    /// first appearance; empty when the trace reached the target.
+    /// `hops` is cut after the first repeat
+    #[serde(default)]
+    pub loop_ips: Vec<String>,
+    /// Our public IP as seen from the internet, `None` if it could not be detected
+    #[serde(default)]
+    pub public_ip: Option<String>,
+}
+
+impl RoutingResult {
+    /// Sequence of hop RTTs in hop order, timeout hops as `None`
+    pub fn hop_rtt_series(hops: &[RouteHop]) -> Vec<Option<f64>> {
+        hops.iter().map(|h| h.rtt_ms_opt).collect()
+    }
+
+    /// Average RTT of the hops that responded (timeouts are skipped, not 0ms)
+    pub fn average_hop_rtt(hops: &[RouteHop]) -> Option<f64> {
+        let rtts: Vec<f64> = hops.iter().filter_map(|h| h.rtt_ms_opt).collect();
+        if rtts.is_empty() {
+            None
+        } else {
+            Some(rtts.iter().sum::<f64>() / rtts.len() as f64)
+        }
+    }
+
+    /// Highest RTT of the hops that responded
+    pub fn max_hop_rtt(hops: &[RouteHop]) -> Option<f64> {
+        hops.iter().filter_map(|h| h.rtt_ms_opt).reduce(f64::max)
+    }
+
+    /// IPs that answered again after another router did (`A … B … A`), in
+    /// order of first appearance
+    /// 
+    /// Back-to-back answers from one IP (`A, A` or `A, *, A`) come from
+    /// routers that don't decrement the TTL or from MPLS tunnels, not loops.
+    pub fn repeated_ips(hops: &[RouteHop]) -> Vec<String> {
+        let mut repeated: Vec<String> = Vec::new();
+        for hop in hops {
+            if hop.ip_address != "*"
+                && !repeated.contains(&hop.ip_address)
+                && Self::cycle_repeat_index(hops, &hop.ip_address).is_some()
+            {
+                repeated.push(hop.ip_address.clone());
+            }
+        }
+        repeated
+    }
+
+    /// Index of the hop where `ip` answers again after another router
+    pub fn cycle_repeat_index(hops: &[RouteHop], ip: &str) -> Option<usize> {
+        let first = hops.iter().position(|h| h.ip_address == ip)?;
+        let mut other_router_seen = false;
+        for (i, hop) in hops.iter().enumerate().skip(first + 1) {
+            if hop.ip_address == ip {
+                if other_router_seen {
+                    return Some(i);
+                }
+            } else if hop.ip_address != "*" {
+                other_router_seen = true;
+            }
+        }
+        None
+    }
+
+    /// Hop numbers at which `ip` answered
+    pub fn hop_numbers_of(&self, ip: &str) -> Vec<u32> {
+        self.hops
+            .iter()
+            .filter(|h| h.ip_address == ip)
+            .map(|h| h.hop_number)
+            .collect()
+    }
+}
+
+/// Ping statistics for one payload size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingSizeSample {
+    pub payload_bytes: u32,
+    /// `None` when no reply came back at this size
+    pub min_rtt_ms: Option<f64>,
+    pub avg_rtt_ms: Option<f64>,
+    pub max_rtt_ms: Option<f64>,
+    pub packet_loss_percent: f64,
+    /// Sent with the don't-fragment bit set
+    #[serde(default)]
+    pub dont_fragment: bool,
+    /// ping reported "message too long": the packet exceeds the local MTU
+    #[serde(default)]
+    pub too_large: bool,
+}
+
+/// Latency profile across ping payload sizes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBySizeResult {
+    pub target_ip: String,
+    /// Samples ordered from smallest to largest payload
+    pub samples: Vec<PingSizeSample>,
+    /// 1472-byte don't-fragment probe, a full 1500-byte packet;
+    /// `None` when ping could not run it
+    #[serde(default)]
+    pub mtu_probe: Option<PingSizeSample>,
 }
 
 /// Connection Stability Test Result
@@ -63,10 +350,143 @@ pub struct StabilityResult {
     pub avg_time_ms: f64,
     pub max_time_ms: f64,
     pub jitter_ms: f64,
+    /// Failed probes that got a non-2xx/3xx response (server side)
+    #[serde(default)]
+    pub http_errors: u32,
+    /// Failed probes that hit the time limit
+    #[serde(default)]
+    pub timeouts: u32,
+    /// Failed probes whose connection was refused/reset or never set up
+    #[serde(default)]
+    pub connection_errors: u32,
+    /// Times the resolved IP set changed between probes (with re-resolve)
+    #[serde(default)]
+    pub ip_changes: u32,
+    /// Every IP the domain resolved to during the test (empty without re-resolve)
+    #[serde(default)]
+    pub seen_ips: Vec<String>,
 }
 
-/// Issue severity level
+/// Real-time application class evaluated for suitability
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RealtimeUseCase {
+    Voip,
+    Gaming,
+    VideoCall,
+}
+
+/// Whether the link is fit for one real-time use case
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UseCaseVerdict {
+    pub use_case: RealtimeUseCase,
+    /// Requirement in words, e.g. "jitter < 30 ms, loss < 1%"
+    pub requirement: String,
+    /// `None` when a required metric was not measured
+    pub suitable: Option<bool>,
+    pub latency_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    pub loss_percent: Option<f64>,
+}
+
+/// One SLA limit compared with the measured value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaCheck {
+    /// Metric name, e.g. "TTFB"
+    pub metric: String,
+    /// Limit in words, e.g. "<= 800 ms"
+    pub requirement: String,
+    /// `None` when the metric was not measured (counts as failed)
+    pub actual: Option<f64>,
+    pub passed: bool,
+}
+
+/// A blocklist that flags an IP
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlocklistListing {
+    pub ip: String,
+    pub blocklist: String,
+    /// Return code from the list (127.0.0.x), encodes the listing reason
+    pub return_code: String,
+    /// True when the listed IP is the user's own public IP
+    pub is_own_ip: bool,
+}
+
+/// IP reputation (DNSBL) check result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlocklistResult {
+    pub checked_ips: Vec<String>,
+    pub blocklists: Vec<String>,
+    pub public_ip: Option<String>,
+    pub listings: Vec<BlocklistListing>,
+}
+
+/// Transparent proxy / content modification check result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyDetectionResult {
+    pub control_url: String,
+    pub http_code: u16,
+    pub expected_sha256: String,
+    pub actual_sha256: String,
+    /// Body or status of the control resource differs from the known response
+    pub content_modified: bool,
+    /// Proxy headers the control server never sends ("Via: 1.1 squid", ...)
+    pub proxy_headers: Vec<String>,
+    pub proxy_detected: bool,
+}
+
+/// Real-asset download throughput result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetDownloadResult {
+    pub url: String,
+    pub http_code: u16,
+    pub bytes_downloaded: u64,
+    pub total_bytes: Option<u64>,
+    pub elapsed_ms: f64,
+    pub throughput_kbps: f64,
+}
+
+/// Separate download and upload throughput against a speed test server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthResult {
+    pub server: String,
+    /// Kilobytes per second (curl's bytes/s divided by 1024)
+    pub download_kbps: f64,
+    /// Kilobytes per second
+    pub upload_kbps: f64,
+    /// Upload as a fraction of download; `None` when download measured zero
+    pub upload_ratio: Option<f64>,
+    /// Average TCP handshake to the server during the download; `None` when no probe connected
+    #[serde(default)]
+    pub loaded_latency_ms: Option<f64>,
+}
+
+/// Letter grade for responsiveness (A best, F worst)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum QualityGrade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+/// Headline network quality summary (in the spirit of Apple's networkQuality)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkQuality {
+    /// Round trip on an idle connection (TCP handshake)
+    pub idle_latency_ms: Option<f64>,
+    /// Round trip while the link is loaded (from the bandwidth check)
+    pub loaded_latency_ms: Option<f64>,
+    /// Kilobytes per second
+    pub throughput_kbps: Option<f64>,
+    /// Round trips per minute under working conditions
+    pub rpm: Option<u32>,
+    pub grade: Option<QualityGrade>,
+}
+
+/// Issue severity level
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum IssueSeverity {
     Info,
@@ -97,8 +517,32 @@ pub struct DiagnosticIssue {
     pub solutions: Vec<String>,
 }
 
-/// Overall diagnostic status
+/// One problem found by `validate_diagnostic`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationProblem {
+    /// Error: `run_diagnostic` would refuse to start. Warning: part of the run
+    /// won't work as configured
+    pub severity: IssueSeverity,
+    /// Config field or area at fault, e.g. "source_ip", "tools"
+    pub field: String,
+    pub message: String,
+}
+
+/// What a diagnostic would run and what would stop it, without sending probes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// URL as it would be probed (override applied), or host:port in raw TCP mode
+    pub target_url: String,
+    pub raw_tcp: bool,
+    pub checks_to_run: Vec<String>,
+    pub checks_skipped: Vec<String>,
+    pub problems: Vec<ValidationProblem>,
+    /// No Error-level problem
+    pub valid: bool,
+}
+
+/// Overall diagnostic status
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum OverallStatus {
     Excellent,
@@ -114,12 +558,68 @@ pub struct DiagnosticReport {
     pub target_url: String,
     pub timestamp: String,
     pub dns: Option<DnsResult>,
+    pub secure_dns: Option<SecureDnsResult>,
     pub tcp: Option<TcpResult>,
+    /// TCP timing pinned to each resolved IP, keyed by IP
+    pub per_ip_tcp: Option<BTreeMap<String, TcpResult>>,
+    pub ssl_cert: Option<SslCertInfo>,
+    /// Raw TCP check, set instead of `tcp` for host:port targets
+    pub port_check: Option<PortCheckResult>,
     pub routing: Option<RoutingResult>,
     pub stability: Option<StabilityResult>,
+    pub latency_by_size: Option<LatencyBySizeResult>,
+    pub blocklist: Option<BlocklistResult>,
+    pub proxy: Option<ProxyDetectionResult>,
+    #[serde(default)]
+    pub bandwidth: Option<BandwidthResult>,
+    pub network_quality: Option<NetworkQuality>,
+    /// Per-use-case verdicts (VoIP, gaming, video calls)
+    #[serde(default)]
+    pub realtime_suitability: Vec<UseCaseVerdict>,
     pub overall_status: OverallStatus,
+    /// Every configured SLA limit held; `None` when no SLA limit was configured
+    pub sla_met: Option<bool>,
+    /// Per-metric SLA results
+    #[serde(default)]
+    pub sla_checks: Vec<SlaCheck>,
+    /// Score deductions behind `overall_status` as (reason, points), e.g. ("Jitter cao", -5)
+    #[serde(default)]
+    pub score_breakdown: Vec<(String, i32)>,
     pub issues: Vec<DiagnosticIssue>,
     pub recommendations: Vec<String>,
+    /// Profile the run was configured with, if any
+    pub profile: Option<DiagnosticProfile>,
+    /// Thresholds the analysis graded against
+    #[serde(default)]
+    pub thresholds: DiagnosticThresholds,
+    /// Warnings were treated as failures when grading the overall status
+    #[serde(default)]
+    pub strict: bool,
+    /// Interface / source IP the probes were bound to (`None` = default route)
+    pub network_source: Option<String>,
+    /// Connect address / Host+SNI override in effect, e.g.
+    /// "Host/SNI example.com, connecting to 203.0.113.10"
+    pub target_override: Option<String>,
+    /// Main changes versus the previous run for this target in this session
+    pub delta_summary: Option<String>,
+    /// One-sentence verdict for sharing, e.g. "example.com: GOOD — DNS 45ms, connect 120ms"
+    #[serde(default)]
+    pub summary_line: String,
+    /// Raw output of the tools run by the checks (only with `capture_raw`)
+    #[serde(default)]
+    pub raw_output: Vec<RawCommandOutput>,
+}
+
+/// Raw output of one external command run during the diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawCommandOutput {
+    pub timestamp: String,
+    /// Full command line, e.g. "curl -s -w ... https://example.com"
+    pub command: String,
+    /// `None` when the process was killed by a signal
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
 }
 
 /// Progress event sent to frontend
@@ -131,3 +631,79 @@ pub struct ProgressEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(hop_number: u32, ip_address: &str, rtt_ms_opt: Option<f64>) -> RouteHop {
+        RouteHop {
+            hop_number,
+            ip_address: ip_address.to_string(),
+            hostname: None,
+            rtt_ms: rtt_ms_opt.unwrap_or(0.0),
+            rtt_ms_opt,
+            packet_loss_percent: if rtt_ms_opt.is_none() { 100.0 } else { 0.0 },
+        }
+    }
+
+    #[test]
+    fn all_timeout_hops_have_no_latency() {
+        let hops = vec![hop(1, "*", None), hop(2, "*", None), hop(3, "*", None)];
+
+        assert_eq!(RoutingResult::hop_rtt_series(&hops), vec![None, None, None]);
+        assert_eq!(RoutingResult::average_hop_rtt(&hops), None);
+        assert_eq!(RoutingResult::max_hop_rtt(&hops), None);
+    }
+
+    #[test]
+    fn timeout_hops_are_skipped_not_averaged_as_zero() {
+        let hops = vec![hop(1, "192.168.1.1", Some(2.0)), hop(2, "*", None), hop(3, "10.0.0.1", Some(10.0))];
+
+        assert_eq!(RoutingResult::hop_rtt_series(&hops), vec![Some(2.0), None, Some(10.0)]);
+        assert_eq!(RoutingResult::average_hop_rtt(&hops), Some(6.0));
+        assert_eq!(RoutingResult::max_hop_rtt(&hops), Some(10.0));
+    }
+
+    fn path(ips: &[&str]) -> Vec<RouteHop> {
+        ips.iter()
+            .enumerate()
+            .map(|(i, ip)| hop(i as u32 + 1, ip, (*ip != "*").then_some(5.0)))
+            .collect()
+    }
+
+    #[test]
+    fn repeated_ips_finds_real_cycles() {
+        let hops = path(&["10.0.0.1", "10.0.0.2", "10.0.0.3", "10.0.0.2", "10.0.0.3"]);
+
+        assert_eq!(RoutingResult::repeated_ips(&hops), vec!["10.0.0.2", "10.0.0.3"]);
+        assert_eq!(RoutingResult::cycle_repeat_index(&hops, "10.0.0.2"), Some(3));
+    }
+
+    #[test]
+    fn repeated_ips_ignores_back_to_back_answers() {
+        let hops = path(&["10.0.0.1", "10.0.0.2", "10.0.0.2", "*", "10.0.0.2", "10.0.0.9"]);
+
+        assert!(RoutingResult::repeated_ips(&hops).is_empty());
+        assert_eq!(RoutingResult::cycle_repeat_index(&hops, "10.0.0.2"), None);
+    }
+
+    #[test]
+    fn repeated_ips_ignores_timeouts() {
+        let hops = path(&["10.0.0.1", "*", "10.0.0.2", "*"]);
+        assert!(RoutingResult::repeated_ips(&hops).is_empty());
+    }
+
+    #[test]
+    fn tcp_result_health_excludes_failures_and_server_errors() {
+        let mut result = TcpResult::failed("timeout".to_string());
+        assert!(!result.is_healthy());
+
+        result.failure_reason = None;
+        result.http_code = 503;
+        assert!(!result.is_healthy());
+
+        result.http_code = 404;
+        assert!(result.is_healthy());
+    }
+}
@@ -0,0 +1,263 @@
+//! Pre-flight validation of a diagnostic run
+//!
+//! Checks the target, config and installed tools the way `run_diagnostic`
+//! would use them, without sending a single probe, so a batch or monitoring
+//! job with a typo fails immediately instead of halfway through a run.
+
+use crate::checks::CheckRegistry;
+use crate::config::DiagnosticConfig;
+use crate::diagnostic::{parse_domain, SourceBinding};
+use crate::types::*;
+use crate::{prepare_target, PreparedTarget};
+
+/// Validate `target_url` with `config`, collecting every problem found
+pub fn validate(target_url: &str, config: &DiagnosticConfig) -> ValidationReport {
+    let mut problems: Vec<ValidationProblem> = Vec::new();
+
+    let (target, raw_tcp) = match prepare_target(target_url, config) {
+        Ok(PreparedTarget { raw_target: Some((host, port)), .. }) => (format!("{}:{}", host, port), true),
+        Ok(prepared) => (prepared.url, false),
+        Err(e) => {
+            // A valid target means the override was rejected
+            let field = if parse_domain(target_url).is_ok() { "connect_ip / host_override" } else { "target" };
+            problems.push(error(field, e));
+            (target_url.to_string(), config.raw_tcp)
+        }
+    };
+
+    let interface = SourceBinding::from_config(config.interface.as_deref(), None);
+    if let Err(e) = &interface {
+        problems.push(error("interface", e.clone()));
+    }
+    let source_ip = SourceBinding::from_config(None, config.source_ip.as_deref());
+    if let Err(e) = &source_ip {
+        problems.push(error("source_ip", e.clone()));
+    }
+    // Each is fine on its own; only the combination can still fail
+    if interface.is_ok() && source_ip.is_ok() {
+        if let Err(e) = SourceBinding::from_config(config.interface.as_deref(), config.source_ip.as_deref()) {
+            problems.push(error("interface / source_ip", e));
+        }
+    }
+
+    let registry = CheckRegistry::with_default_checks();
+    let names = registry.names();
+    for unknown in config.disabled_checks.iter().filter(|d| !names.contains(&d.as_str())) {
+        problems.push(warning(
+            "disabled_checks",
+            format!("Unknown check \"{}\" (available: {})", unknown, names.join(", ")),
+        ));
+    }
+
+    let plan = registry.plan(config, raw_tcp);
+    let checks_to_run: Vec<String> = plan.iter().map(|(name, _)| name.to_string()).collect();
+    let checks_skipped: Vec<String> = names
+        .iter()
+        .filter(|name| !checks_to_run.iter().any(|c| c == *name))
+        .map(|name| name.to_string())
+        .collect();
+
+    // Tools with the checks that need them, in first-use order
+    let mut tools: Vec<(&str, Vec<&str>)> = Vec::new();
+    let webhook_tools: &[&str] = if config.webhook.is_some() { &["curl"] } else { &[] };
+    let users = plan
+        .iter()
+        .flat_map(|(name, needs)| needs.iter().map(move |tool| (*tool, *name)))
+        .chain(webhook_tools.iter().map(|tool| (*tool, "webhook")));
+    for (tool, user) in users {
+        match tools.iter_mut().find(|(t, _)| *t == tool) {
+            Some((_, needed_by)) => needed_by.push(user),
+            None => tools.push((tool, vec![user])),
+        }
+    }
+    for (tool, needed_by) in tools.iter().filter(|(tool, _)| !on_path(tool)) {
+        problems.push(error(
+            "tools",
+            format!("{} not found on PATH (needed by {})", tool, needed_by.join(", ")),
+        ));
+    }
+
+    validate_numbers(config, &mut problems);
+
+    if config.check_own_ip_reputation && !config.check_blocklists {
+        problems.push(warning(
+            "check_own_ip_reputation",
+            "Has no effect without check_blocklists".to_string(),
+        ));
+    }
+
+    if let Some(webhook) = &config.webhook {
+        match url::Url::parse(&webhook.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => problems.push(error(
+                "webhook.url",
+                format!("Unsupported scheme \"{}\" (use http or https)", url.scheme()),
+            )),
+            Err(e) => problems.push(error("webhook.url", format!("Invalid URL {}: {}", webhook.url, e))),
+        }
+        if webhook.auth_header.as_deref().is_some_and(|h| !h.contains(':')) {
+            problems.push(error(
+                "webhook.auth_header",
+                "Must be a full header line, e.g. \"Authorization: Bearer <token>\"".to_string(),
+            ));
+        }
+    }
+
+    let valid = !problems.iter().any(|p| matches!(p.severity, IssueSeverity::Error));
+    ValidationReport {
+        target_url: target,
+        raw_tcp,
+        checks_to_run,
+        checks_skipped,
+        problems,
+        valid,
+    }
+}
+
+/// Check counts, thresholds and SLA limits for impossible values
+fn validate_numbers(config: &DiagnosticConfig, problems: &mut Vec<ValidationProblem>) {
+    if config.run_stability && config.stability_tests == 0 {
+        problems.push(warning(
+            "stability_tests",
+            "Is 0, so the stability check has nothing to measure".to_string(),
+        ));
+    }
+
+    let thresholds = &config.thresholds;
+    let mut limits: Vec<(&str, Option<f64>)> = vec![
+        ("thresholds.dns_ms", Some(thresholds.dns_ms)),
+        ("thresholds.connect_ms", Some(thresholds.connect_ms)),
+        ("thresholds.ssl_ms", Some(thresholds.ssl_ms)),
+        ("thresholds.total_ms", Some(thresholds.total_ms)),
+        ("thresholds.ttfb_ms", thresholds.ttfb_ms),
+        ("thresholds.jitter_ms", Some(thresholds.jitter_ms)),
+    ];
+    if let Some(sla) = &config.sla {
+        let sla_limits = [
+            sla.max_dns_ms,
+            sla.max_ttfb_ms,
+            sla.max_total_ms,
+            sla.min_success_rate,
+            sla.max_jitter_ms,
+        ];
+        if sla_limits.iter().all(Option::is_none) {
+            problems.push(warning("sla", "Sets no limit, so the run is not graded".to_string()));
+        }
+        limits.extend([
+            ("sla.max_dns_ms", sla.max_dns_ms),
+            ("sla.max_ttfb_ms", sla.max_ttfb_ms),
+            ("sla.max_total_ms", sla.max_total_ms),
+            ("sla.max_jitter_ms", sla.max_jitter_ms),
+        ]);
+        if let Some(rate) = sla.min_success_rate.filter(|r| !(0.0..=100.0).contains(r)) {
+            problems.push(error(
+                "sla.min_success_rate",
+                format!("{} is not a percentage (0-100)", rate),
+            ));
+        }
+    }
+    for (field, value) in limits {
+        if let Some(value) = value.filter(|v| !v.is_finite() || *v <= 0.0) {
+            problems.push(error(field, format!("{} is not a positive number of milliseconds", value)));
+        }
+    }
+}
+
+/// Whether `program` is an executable file in a PATH directory
+fn on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(program);
+        candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+    })
+}
+
+fn error(field: &str, message: String) -> ValidationProblem {
+    ValidationProblem {
+        severity: IssueSeverity::Error,
+        field: field.to_string(),
+        message,
+    }
+}
+
+fn warning(field: &str, message: String) -> ValidationProblem {
+    ValidationProblem {
+        severity: IssueSeverity::Warning,
+        field: field.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SlaConfig;
+
+    /// Severities of the problems reported on `field`
+    fn problems_on(report: &ValidationReport, field: &str) -> Vec<IssueSeverity> {
+        report
+            .problems
+            .iter()
+            .filter(|p| p.field == field)
+            .map(|p| p.severity.clone())
+            .collect()
+    }
+
+    #[test]
+    fn negative_and_nan_thresholds_are_errors() {
+        let mut config = DiagnosticConfig::default();
+        config.thresholds.dns_ms = -1.0;
+        config.thresholds.jitter_ms = f64::NAN;
+        let report = validate("https://example.com", &config);
+
+        assert_eq!(problems_on(&report, "thresholds.dns_ms"), vec![IssueSeverity::Error]);
+        assert_eq!(problems_on(&report, "thresholds.jitter_ms"), vec![IssueSeverity::Error]);
+        assert!(problems_on(&report, "thresholds.connect_ms").is_empty());
+        assert!(!report.valid);
+    }
+
+    #[test]
+    fn out_of_range_success_rate_is_an_error() {
+        let mut config = DiagnosticConfig {
+            sla: Some(SlaConfig { min_success_rate: Some(150.0), ..Default::default() }),
+            ..Default::default()
+        };
+        let report = validate("https://example.com", &config);
+        assert_eq!(problems_on(&report, "sla.min_success_rate"), vec![IssueSeverity::Error]);
+
+        config.sla = Some(SlaConfig { min_success_rate: Some(99.5), ..Default::default() });
+        let report = validate("https://example.com", &config);
+        assert!(problems_on(&report, "sla.min_success_rate").is_empty());
+        assert!(problems_on(&report, "sla").is_empty());
+    }
+
+    #[test]
+    fn unknown_disabled_check_is_a_warning() {
+        let config = DiagnosticConfig {
+            disabled_checks: vec!["routing".to_string(), "rouitng".to_string()],
+            ..Default::default()
+        };
+        let report = validate("https://example.com", &config);
+
+        assert_eq!(problems_on(&report, "disabled_checks"), vec![IssueSeverity::Warning]);
+        assert!(report.problems.iter().any(|p| p.message.contains("\"rouitng\"")));
+        assert!(report.checks_skipped.contains(&"routing".to_string()));
+    }
+
+    #[test]
+    fn target_errors_are_attributed_to_the_right_field() {
+        let config = DiagnosticConfig {
+            connect_ip: Some("not-an-ip".to_string()),
+            ..Default::default()
+        };
+        let report = validate("https://example.com", &config);
+        assert_eq!(problems_on(&report, "connect_ip / host_override"), vec![IssueSeverity::Error]);
+        assert!(problems_on(&report, "target").is_empty());
+
+        let report = validate("http://", &DiagnosticConfig::default());
+        assert_eq!(problems_on(&report, "target"), vec![IssueSeverity::Error]);
+        assert!(problems_on(&report, "connect_ip / host_override").is_empty());
+    }
+}
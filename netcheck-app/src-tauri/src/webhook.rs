@@ -0,0 +1,123 @@
+//! Webhook delivery for completed NetCheck Diagnostic Reports
+//!
+//! POSTs the final report JSON to a configured URL so unattended runs can
+//! feed incident tooling. Delivery failures never fail the diagnostic itself.
+
+use crate::config::{WebhookCondition, WebhookConfig};
+use crate::types::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+/// Whether the report matches the webhook's `post_on` condition
+pub fn should_post(config: &WebhookConfig, status: &OverallStatus) -> bool {
+    match config.post_on {
+        WebhookCondition::Always => true,
+        WebhookCondition::Degraded => {
+            matches!(status, OverallStatus::Poor | OverallStatus::Failed)
+        }
+    }
+}
+
+/// POST the report JSON to the webhook, returning the HTTP status code
+///
+/// The auth header is passed to curl through a private temp file rather than
+/// on the command line, where any local user could read it with `ps`.
+pub async fn post_report(config: &WebhookConfig, report: &DiagnosticReport) -> Result<u16, String> {
+    let body = serde_json::to_vec(report).map_err(|e| format!("Failed to serialize report: {}", e))?;
+
+    let header_path = match &config.auth_header {
+        Some(header) => Some(
+            write_private_header(header)
+                .map_err(|e| format!("Failed to write webhook auth header: {}", e))?,
+        ),
+        None => None,
+    };
+    let result = send(config, &body, header_path.as_deref()).await;
+    if let Some(path) = &header_path {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Write `header` to a temp file only the current user can read, for `curl -H @file`
+fn write_private_header(header: &str) -> std::io::Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!(
+        "netcheck-webhook-{}-{}.hdr",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    ));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    if let Err(e) = writeln!(file, "{}", header) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// Run curl with the report body on stdin
+async fn send(config: &WebhookConfig, body: &[u8], header_path: Option<&Path>) -> Result<u16, String> {
+    let mut args: Vec<String> = vec![
+        "-s".to_string(),
+        "-o".to_string(),
+        "/dev/null".to_string(),
+        "-w".to_string(),
+        "%{http_code}".to_string(),
+        "--connect-timeout".to_string(),
+        "10".to_string(),
+        "--max-time".to_string(),
+        "20".to_string(),
+        "-X".to_string(),
+        "POST".to_string(),
+        "-H".to_string(),
+        "Content-Type: application/json".to_string(),
+        "--data-binary".to_string(),
+        "@-".to_string(),
+    ];
+    if let Some(path) = header_path {
+        args.push("-H".to_string());
+        args.push(format!("@{}", path.to_string_lossy()));
+    }
+    args.push(config.url.clone());
+
+    let mut child = tokio::process::Command::new("curl")
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(body)
+            .await
+            .map_err(|e| format!("Failed to send report body: {}", e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+    let http_code: u16 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(0);
+
+    if (200..300).contains(&http_code) {
+        Ok(http_code)
+    } else if http_code == 0 {
+        Err(format!("Could not reach webhook {}", config.url))
+    } else {
+        Err(format!("Webhook {} returned HTTP {}", config.url, http_code))
+    }
+}
@@ -14,10 +14,14 @@ export interface DiagnosticStep {
 export interface DnsResult {
   domain: string;
   resolved_ips: string[];
+  resolved_ipv6?: string[];
   lookup_time_ms: number;
   ttl?: number;
   nameservers?: string[];
   using_cdn?: string;
+  backend?: "native" | "dig" | "nslookup" | "host" | null;
+  resolver?: string | null;
+  local_resolver_error?: string | null;
 }
 
 export interface TcpResult {
@@ -28,6 +32,55 @@ export interface TcpResult {
   total_time_ms: number;
   http_code: number;
   download_speed_kbps: number;
+  failure_reason?: string | null;
+  security_headers?: SecurityHeaders | null;
+  remote_ip?: string | null;
+  redirect_chain?: string[]; // requested URL first, empty without redirects
+  clock_skew_secs?: number | null; // local clock minus server Date header
+}
+
+export interface SecurityHeaders {
+  hsts?: string | null;
+  content_security_policy?: string | null;
+  x_frame_options?: string | null;
+  x_content_type_options?: string | null;
+}
+
+export interface SslCertInfo {
+  subject: string;
+  issuer: string;
+  chain_subjects: string[];
+  chain_complete: boolean;
+  verify_code?: number | null;
+  verify_message?: string | null;
+  not_before?: string | null; // RFC 3339
+  not_after?: string | null;
+}
+
+export interface PortCheckResult {
+  host: string;
+  port: number;
+  remote_ip: string;
+  connected: boolean;
+  connect_time_ms: number;
+  banner?: string | null;
+  error?: string | null;
+}
+
+export type PortStatus = "open" | "blocked" | "filtered";
+
+export interface PortProbe {
+  port: number;
+  service: string;
+  status: PortStatus;
+  connect_time_ms?: number | null; // open ports only
+}
+
+export interface PortScanResult {
+  host: string;
+  remote_ip: string;
+  ports: PortProbe[];
+  summary: string;
 }
 
 export interface RouteHop {
@@ -35,6 +88,7 @@ export interface RouteHop {
   ip_address: string;
   hostname?: string;
   rtt_ms: number;
+  rtt_ms_opt?: number | null; // null when the hop timed out
   packet_loss_percent: number;
   // Enhanced fields for visualization
   geo_location?: string;
@@ -50,9 +104,15 @@ export interface RoutingResult {
   hops: RouteHop[];
   total_hops: number;
   total_time_ms: number;
+  rtt_series?: (number | null)[]; // per-hop RTT, null gaps for timeouts
   bottleneck_hops?: number[];
-  avg_hop_latency_ms?: number;
-  max_hop_latency_ms?: number;
+  avg_hop_latency_ms?: number | null; // over responding hops only
+  max_hop_latency_ms?: number | null;
+  needs_privileges?: boolean;
+  destination_reached?: boolean | null; // null when unknown
+  hit_hop_limit?: boolean; // traceroute used up its TTL limit
+  loop_ips?: string[]; // IPs repeating across hops; hops are cut after the first repeat
+  public_ip?: string | null;
 }
 
 export interface StabilityResult {
@@ -63,6 +123,11 @@ export interface StabilityResult {
   avg_time_ms: number;
   max_time_ms: number;
   jitter_ms: number;
+  http_errors?: number; // failed probes by cause
+  timeouts?: number;
+  connection_errors?: number;
+  ip_changes?: number; // with stability_reresolve
+  seen_ips?: string[];
   test_results?: TestResult[];
 }
 
@@ -73,16 +138,155 @@ export interface TestResult {
   timestamp: string;
 }
 
+export interface PingSizeSample {
+  payload_bytes: number;
+  min_rtt_ms?: number | null;
+  avg_rtt_ms?: number | null;
+  max_rtt_ms?: number | null;
+  packet_loss_percent: number;
+  dont_fragment?: boolean;
+  too_large?: boolean; // ping reported "message too long"
+}
+
+export interface LatencyBySizeResult {
+  target_ip: string;
+  samples: PingSizeSample[];
+  mtu_probe?: PingSizeSample | null; // 1472 bytes, don't-fragment
+}
+
 export interface DiagnosticReport {
   target_url: string;
   timestamp: string;
   dns: DnsResult | null;
+  secure_dns?: SecureDnsResult | null;
   tcp: TcpResult | null;
+  per_ip_tcp?: Record<string, TcpResult> | null;
+  ssl_cert?: SslCertInfo | null;
+  port_check?: PortCheckResult | null;
   routing: RoutingResult | null;
   stability: StabilityResult | null;
+  latency_by_size?: LatencyBySizeResult | null;
+  blocklist?: BlocklistResult | null;
+  proxy?: ProxyDetectionResult | null;
+  bandwidth?: BandwidthResult | null;
+  network_quality?: NetworkQuality | null;
+  realtime_suitability?: UseCaseVerdict[];
   overall_status: "excellent" | "good" | "acceptable" | "poor" | "failed";
+  score_breakdown?: [string, number][]; // [reason, negative points]
+  sla_met?: boolean | null; // null when no SLA limit was configured
+  sla_checks?: SlaCheck[];
   issues: DiagnosticIssue[];
   recommendations: string[];
+  profile?: DiagnosticProfile | null;
+  thresholds?: DiagnosticThresholds;
+  strict?: boolean;
+  network_source?: string | null;
+  target_override?: string | null;
+  delta_summary?: string | null;
+  summary_line?: string;
+  raw_output?: RawCommandOutput[]; // only with capture_raw
+}
+
+export interface RawCommandOutput {
+  timestamp: string;
+  command: string;
+  exit_code?: number | null;
+  stdout: string;
+  stderr: string;
+}
+
+export type DiagnosticProfile = "web" | "gaming" | "streaming" | "api";
+
+export interface DiagnosticThresholds {
+  dns_ms: number;
+  connect_ms: number;
+  ssl_ms: number;
+  total_ms: number;
+  ttfb_ms?: number | null;
+  jitter_ms: number;
+}
+
+export interface SecureDnsProbe {
+  provider: string;
+  protocol: "doh" | "dot";
+  endpoint: string;
+  success: boolean;
+  resolved_ips: string[];
+  time_ms: number;
+  error?: string | null;
+}
+
+export interface SecureDnsResult {
+  probes: SecureDnsProbe[];
+  doh_available: boolean;
+  dot_available?: boolean | null;
+}
+
+export interface SlaCheck {
+  metric: string;
+  requirement: string;
+  actual?: number | null; // null when not measured (counts as failed)
+  passed: boolean;
+}
+
+export interface BlocklistListing {
+  ip: string;
+  blocklist: string;
+  return_code: string;
+  is_own_ip: boolean;
+}
+
+export interface BlocklistResult {
+  checked_ips: string[];
+  blocklists: string[];
+  public_ip?: string | null;
+  listings: BlocklistListing[];
+}
+
+export interface ProxyDetectionResult {
+  control_url: string;
+  http_code: number;
+  expected_sha256: string;
+  actual_sha256: string;
+  content_modified: boolean;
+  proxy_headers: string[];
+  proxy_detected: boolean;
+}
+
+export interface BandwidthResult {
+  server: string;
+  download_kbps: number; // KB/s
+  upload_kbps: number; // KB/s
+  upload_ratio?: number | null; // upload / download, null when download measured zero
+  loaded_latency_ms?: number | null; // TCP handshake during the download
+}
+
+export type RealtimeUseCase = "voip" | "gaming" | "video_call";
+
+export interface UseCaseVerdict {
+  use_case: RealtimeUseCase;
+  requirement: string;
+  suitable?: boolean | null;
+  latency_ms?: number | null;
+  jitter_ms?: number | null;
+  loss_percent?: number | null;
+}
+
+export interface NetworkQuality {
+  idle_latency_ms?: number | null;
+  loaded_latency_ms?: number | null;
+  throughput_kbps?: number | null; // KB/s
+  rpm?: number | null;
+  grade?: "A" | "B" | "C" | "D" | "F" | null;
+}
+
+export interface AssetDownloadResult {
+  url: string;
+  http_code: number;
+  bytes_downloaded: number;
+  total_bytes?: number | null;
+  elapsed_ms: number;
+  throughput_kbps: number;
 }
 
 export interface DiagnosticIssue {
@@ -94,6 +298,22 @@ export interface DiagnosticIssue {
   solutions: string[];
 }
 
+// Result of validate_diagnostic (no probes sent)
+export interface ValidationProblem {
+  severity: "info" | "warning" | "error";
+  field: string; // config field or area, e.g. "source_ip", "tools"
+  message: string;
+}
+
+export interface ValidationReport {
+  target_url: string;
+  raw_tcp: boolean;
+  checks_to_run: string[];
+  checks_skipped: string[];
+  problems: ValidationProblem[];
+  valid: boolean; // no error-level problem
+}
+
 // Events emitted during diagnostic
 export interface DiagnosticEvent {
   step: string;